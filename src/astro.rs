@@ -1,22 +1,107 @@
+use std::cmp::{max, min};
 use std::f64::consts::PI;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use euclid::{vec3, Angle, Rotation3D, Vector3D};
 
-use crate::{Planet, Star};
+use crate::{Planet, SkyObject, Star, VariableKind, J2000};
 
 enum U {}
 
+pub struct Elongation {
+    pub time: DateTime<Utc>,
+    pub angle: f64, // positive east of the Sun
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EclipseKind {
+    PartialSolar,
+    CentralSolar, // total and annular can't be told apart with circular orbits
+    PenumbralLunar,
+    PartialLunar,
+    TotalLunar,
+}
+
+pub struct Eclipse {
+    pub time: DateTime<Utc>, // greatest eclipse as seen from the location
+    pub kind: EclipseKind,
+}
+
+pub struct Crossing {
+    pub time: DateTime<Utc>,
+    pub rising: bool,
+}
+
+pub struct Passage {
+    pub rise: Option<DateTime<Utc>>,
+    pub transit: Option<DateTime<Utc>>, // only when the culmination is above the horizon
+    pub set: Option<DateTime<Utc>>,
+}
+
+// the stretch of a night with an object up while the sky is fully dark
+pub struct DarkWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub best: DateTime<Utc>, // when the object stands highest within the window
+    pub all_night: bool,     // up from the end of dusk to the start of dawn
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WorldId {
+    Earth,
+    Mars,
+}
+
+#[derive(Clone, Copy)]
+pub struct World {
+    pub id: WorldId,
+    pub name: &'static str,
+    pub semimajor: f64, // millions of km
+    sidereal: f64,      // seconds
+    phase: f64,         // orbital phase at the unix epoch
+    inclination: f64,
+    incl_phase: f64,
+    eccentricity: f64, // only the distance to the Sun feels it
+    anomaly: f64,      // mean anomaly at the unix epoch
+    anomalistic: f64,  // seconds
+    axial_tilt: f64,   // to the ecliptic
+    axial_direction: f64,
+    daily_phase: f64, // rotation at the unix epoch
+    sidereal_day: f64,
+}
+
+// the phases at the unix epoch the model is synced to, together so they can be checked against another reference
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Calibration {
+    pub solar_phase: f64, // the Earth's mean longitude
+    pub solar_anomaly: f64,
+    pub daily_phase: f64, // the Earth's rotation
+    pub moon_phase: f64,  // the Moon's mean longitude
+    pub moon_anomaly: f64,
+    pub nodal_phase: f64, // the Moon's from the ascending node
+}
+
 pub struct Engine {
     pub time: DateTime<Utc>,
+    pub aberration: bool,
+    pub refraction: bool,
     ts: f64,
+    world: World,
+    calibration: Calibration,
     normal: Vector3D<f64, U>,
     north: Vector3D<f64, U>,
 }
 
-const INITIAL_PHASE: f64 = 1.740805; // sync with stellarium
+const INITIAL_PHASE: f64 = 1.756755; // mean longitude at the unix epoch, meeus
 const SIDEREAL_YEAR: f64 = 365.256363004 * 24.0 * 60.0 * 60.0; // stellarium
-const SEMIMAJOR: f64 = 149.598; // nssdc.gsfc.nasa.gov
+const SEMIMAJOR: f64 = 149.598; // nssdc.gsfc.nasa.gov, in millions of km
+pub const SUN_ANGULAR_RADIUS: f64 = 959.63 / 3600.0 * PI / 180.0; // nssdc.gsfc.nasa.gov
+const EARTH_ECCENTRICITY: f64 = 0.016709; // meeus
+const INITIAL_SOLAR_ANOMALY: f64 = 6.245051; // mean anomaly at the unix epoch, meeus
+const ANOMALISTIC_YEAR: f64 = 365.259636 * 24.0 * 60.0 * 60.0; // meeus
+const PHASE_COEFFICIENT: f64 = 0.02; // magnitudes per degree of phase angle, a rough average
+const SPEED_OF_LIGHT: f64 = 0.299792458; // millions of km per second
+pub const LIGHT_TIME_PER_AU: f64 = SEMIMAJOR / SPEED_OF_LIGHT; // seconds
 
 const INITIAL_DAILY_PHASE: f64 = 1.741395; // sync with stellarium
 const SIDEREAL_DAY: f64 = 23.9344694 * 60.0 * 60.0; // stellarium
@@ -24,12 +109,137 @@ const SIDEREAL_DAY: f64 = 23.9344694 * 60.0 * 60.0; // stellarium
 const AXIAL_TILT: f64 = 23.436169775089777 * PI / 180.0; // https://www.astro.sunysb.edu/fwalter/PHY515/coords.html
 const AXIAL_DIRECTION: f64 = PI / 2.0; // https://www.astro.sunysb.edu/fwalter/PHY515/coords.html
 
-const INITIAL_MOON_PHASE: f64 = 3.43; // eclipse
-const SIDEREAL_MONTH: f64 = 27.321582 * 24.0 * 60.0 * 60.0; // stellarium
+const INITIAL_MOON_PHASE: f64 = 3.462222; // mean longitude at the unix epoch, meeus
+const SIDEREAL_MONTH: f64 = 27.321661 * 24.0 * 60.0 * 60.0; // meeus
 
 const MOON_INCLINATION: f64 = 5.145396 * PI / 180.0; // stellarium
-const INITIAL_NODAL_PHASE: f64 = 5.0; // eclipse
-const NODAL_PERIOD: f64 = 18.61 * SIDEREAL_YEAR;
+const INITIAL_ANOMALY: f64 = 4.455881; // mean anomaly at the unix epoch, meeus
+const ANOMALISTIC_MONTH: f64 = 27.554550 * 24.0 * 60.0 * 60.0; // meeus
+const EQUATION_OF_CENTER: f64 = 6.289 * PI / 180.0; // the orbit is circular elsewhere, meeus
+const LUNAR_EQUATOR_INCLINATION: f64 = 1.543 * PI / 180.0; // to the ecliptic, meeus
+const INITIAL_NODAL_PHASE: f64 = 4.961879; // from the ascending node at the unix epoch, meeus
+const NODAL_PERIOD: f64 = 6793.478 * 24.0 * 60.0 * 60.0; // meeus
+
+// the largest periodic terms in the Moon's longitude as degrees and multiples of the mean elongation from the Sun,
+// the Sun's mean anomaly and the Moon's, meeus; the reduction to the ecliptic comes from the inclined orbit itself
+const LUNAR_TERMS: [(f64, f64, f64, f64); 15] = [
+    (6.288774, 0.0, 0.0, 1.0),  // equation of center
+    (1.274027, 2.0, 0.0, -1.0), // evection
+    (0.658314, 2.0, 0.0, 0.0),  // variation
+    (0.213618, 0.0, 0.0, 2.0),
+    (-0.185116, 0.0, 1.0, 0.0), // annual equation
+    (0.058793, 2.0, 0.0, -2.0),
+    (0.057066, 2.0, -1.0, -1.0),
+    (0.053322, 2.0, 0.0, 1.0),
+    (0.045758, 2.0, -1.0, 0.0),
+    (-0.040923, 0.0, 1.0, -1.0),
+    (-0.034720, 1.0, 0.0, 0.0), // parallactic inequality
+    (-0.030383, 0.0, 1.0, 1.0),
+    (0.010675, 4.0, 0.0, -1.0),
+    (0.010034, 0.0, 0.0, 3.0),
+    (0.008548, 4.0, 0.0, -2.0),
+];
+
+const MOON_DISTANCE: f64 = 0.38500056; // mean, millions of km, meeus
+
+// the largest periodic terms in the Moon's distance as millions of km and the same multiples, meeus
+const LUNAR_DISTANCE_TERMS: [(f64, f64, f64, f64); 4] = [
+    (-0.020905355, 0.0, 0.0, 1.0),
+    (-0.003699111, 2.0, 0.0, -1.0),
+    (-0.002955968, 2.0, 0.0, 0.0),
+    (-0.000569925, 0.0, 0.0, 2.0),
+];
+const MOON_RADIUS: f64 = 0.0017374; // millions of km, nssdc.gsfc.nasa.gov
+const EARTH_RADIUS: f64 = 0.006371; // millions of km
+pub const MOON_ANGULAR_RADIUS: f64 = 1737.4 / 384400.0; // nssdc.gsfc.nasa.gov
+const SHADOW_ENLARGEMENT: f64 = 1.02; // the atmosphere widens the Earth's shadow
+const ECLIPSE_LIMIT: f64 = 1.6 * PI / 180.0; // no eclipse with the Moon further from the ecliptic at syzygy
+const ECLIPSE_SEASON: f64 = 18.5 * PI / 180.0; // solar ecliptic limit, the Sun's reach from a node, meeus
+const ECLIPSE_WINDOW: i64 = 240; // minutes searched on each side of a syzygy
+const ECLIPSE_SAMPLING: i64 = 2; // minutes
+
+const GALACTIC_POLE_ASCENSION: f64 = 192.85948 * PI / 180.0; // J2000, iau
+const GALACTIC_POLE_DECLINATION: f64 = 27.12825 * PI / 180.0; // J2000, iau
+const GALACTIC_NODE: f64 = 122.93192 * PI / 180.0; // galactic longitude of the north celestial pole
+
+const SUNRISE_ALTITUDE: f64 = -50.0 / 60.0 * PI / 180.0; // refraction and the solar semidiameter
+const MOONRISE_ALTITUDE: f64 = 0.125 * PI / 180.0; // parallax of the geocentric Moon less refraction and semidiameter
+const PLANETRISE_ALTITUDE: f64 = -34.0 / 60.0 * PI / 180.0; // refraction only, the disks are too small to matter
+const ASTRONOMICAL_TWILIGHT: f64 = -18.0 / 180.0 * PI; // solar altitude where the sky is fully dark
+const REFRACTION_FLOOR: f64 = -PI / 180.0; // the formula breaks down further below, refraction is held there
+const CROSSING_SAMPLING: i64 = 10; // minutes between altitude samples
+const HORIZON_SAMPLES: usize = 720; // azimuths searched for the hour circles
+const SUN_LONGITUDE_ITERATIONS: usize = 12;
+pub const CROSSING_SEARCH_DAYS: i64 = 2; // the Moon rises almost an hour later each day, so one day isn't enough
+
+const UNIX_EPOCH_JD: f64 = 2440587.5;
+const ECLIPSE_DURATION: f64 = 0.14; // fraction of the period, algol
+
+pub const EARTH: World = World {
+    id: WorldId::Earth,
+    name: "Earth",
+    semimajor: SEMIMAJOR,
+    sidereal: SIDEREAL_YEAR,
+    phase: INITIAL_PHASE,
+    inclination: 0.0,
+    incl_phase: 0.0,
+    eccentricity: EARTH_ECCENTRICITY,
+    anomaly: INITIAL_SOLAR_ANOMALY,
+    anomalistic: ANOMALISTIC_YEAR,
+    axial_tilt: AXIAL_TILT,
+    axial_direction: AXIAL_DIRECTION,
+    daily_phase: INITIAL_DAILY_PHASE,
+    sidereal_day: SIDEREAL_DAY,
+};
+
+// orbit as in planets.dat, the pole and rotation from the iau working group on cartographic coordinates
+pub const MARS: World = World {
+    id: WorldId::Mars,
+    name: "Mars",
+    semimajor: 227.956,
+    sidereal: 59355072.0,
+    phase: 0.232884, // mean longitude at the unix epoch, meeus
+    inclination: 1.848 * PI / 180.0,
+    incl_phase: 4.0,
+    eccentricity: 0.0934,
+    anomaly: 0.653039,
+    anomalistic: 686.998 * 24.0 * 60.0 * 60.0,
+    axial_tilt: 26.717789 * PI / 180.0,
+    axial_direction: -0.123887,
+    daily_phase: 2.090633,
+    sidereal_day: 88642.663,
+};
+
+pub const WORLDS: [World; 2] = [EARTH, MARS];
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            solar_phase: INITIAL_PHASE,
+            solar_anomaly: INITIAL_SOLAR_ANOMALY,
+            daily_phase: INITIAL_DAILY_PHASE,
+            moon_phase: INITIAL_MOON_PHASE,
+            moon_anomaly: INITIAL_ANOMALY,
+            nodal_phase: INITIAL_NODAL_PHASE,
+        }
+    }
+}
+
+impl Calibration {
+    fn earth(&self) -> World {
+        World {
+            phase: self.solar_phase,
+            anomaly: self.solar_anomaly,
+            daily_phase: self.daily_phase,
+            ..EARTH
+        }
+    }
+
+    // an engine on the Earth, where the searches for rises, windows and eclipses run
+    pub fn engine(&self, time: DateTime<Utc>, latitude: f64, longitude: f64) -> Engine {
+        Engine::calibrated(&EARTH, time, latitude, longitude, *self)
+    }
+}
 
 const X_UNIT: Vector3D<f64, U> = vec3(1.0, 0.0, 0.0);
 const Z_UNIT: Vector3D<f64, U> = vec3(0.0, 0.0, 1.0);
@@ -46,6 +256,41 @@ fn get_phase(ts: f64, initial_phase: f64, period: f64) -> f64 {
     (initial_phase + (ts / period * 2.0 * PI) % (2.0 * PI)) % (2.0 * PI)
 }
 
+fn get_julian_date(ts: f64) -> f64 {
+    UNIX_EPOCH_JD + ts / 86400.0
+}
+
+fn precess(ascension: f64, declination: f64, from: f64, to: f64) -> (f64, f64) {
+    // between the mean equinoxes of two julian dates, iau 1976, meeus 21.2 and 21.4
+    let big_t = (from - 2451545.0) / 36525.0;
+    let t = (to - from) / 36525.0;
+    let arcsec = PI / 180.0 / 3600.0;
+    let base = (2306.2181 + 1.39656 * big_t - 0.000139 * big_t * big_t) * t;
+    let zeta = (base + (0.30188 - 0.000344 * big_t) * t * t + 0.017998 * t * t * t) * arcsec;
+    let z = (base + (1.09468 + 0.000066 * big_t) * t * t + 0.018203 * t * t * t) * arcsec;
+    let theta = ((2004.3109 - 0.85330 * big_t - 0.000217 * big_t * big_t) * t
+        - (0.42665 + 0.000217 * big_t) * t * t
+        - 0.041833 * t * t * t)
+        * arcsec;
+    let a = declination.cos() * (ascension + zeta).sin();
+    let b = theta.cos() * declination.cos() * (ascension + zeta).cos() - theta.sin() * declination.sin();
+    let c = theta.sin() * declination.cos() * (ascension + zeta).cos() + theta.cos() * declination.sin();
+    ((a.atan2(b) + z).rem_euclid(2.0 * PI), c.clamp(-1.0, 1.0).asin())
+}
+
+pub fn galactic_to_equatorial(longitude: f64, latitude: f64) -> (f64, f64) {
+    let declination = (latitude.sin() * GALACTIC_POLE_DECLINATION.sin()
+        + latitude.cos() * GALACTIC_POLE_DECLINATION.cos() * (GALACTIC_NODE - longitude).cos())
+    .clamp(-1.0, 1.0)
+    .asin();
+    let ascension = GALACTIC_POLE_ASCENSION
+        + (latitude.cos() * (GALACTIC_NODE - longitude).sin()).atan2(
+            latitude.sin() * GALACTIC_POLE_DECLINATION.cos()
+                - latitude.cos() * GALACTIC_POLE_DECLINATION.sin() * (GALACTIC_NODE - longitude).cos(),
+        );
+    (ascension.rem_euclid(2.0 * PI), declination)
+}
+
 fn to_local_coords(lat: f64, lon: f64, vec: Vector3D<f64, U>) -> Vector3D<f64, U> {
     rot_z(lon, rot_y(-lat, vec))
 }
@@ -54,20 +299,26 @@ fn to_recent_coords(daily_phase: f64, vec: Vector3D<f64, U>) -> Vector3D<f64, U>
     rot_z(daily_phase, vec)
 }
 
+fn to_equatorial(vec: Vector3D<f64, U>) -> (f64, f64) {
+    // undoes to_global_coords and to_local_coords, giving (ascension, declination)
+    let vec = rot_z(AXIAL_DIRECTION, rot_y(-AXIAL_TILT, rot_z(-AXIAL_DIRECTION, vec)));
+    (vec.y.atan2(vec.x).rem_euclid(2.0 * PI), vec.z.clamp(-1.0, 1.0).asin())
+}
+
 fn to_global_coords(axial_tilt: f64, axial_direction: f64, vec: Vector3D<f64, U>) -> Vector3D<f64, U> {
     rot_z(axial_direction, rot_y(axial_tilt, rot_z(-axial_direction, vec)))
 }
 
-fn get_normal_and_north(ts: f64, latitude: f64, longitude: f64) -> (Vector3D<f64, U>, Vector3D<f64, U>) {
-    let daily_phase = get_phase(ts, INITIAL_DAILY_PHASE, SIDEREAL_DAY);
+fn get_normal_and_north(world: &World, ts: f64, latitude: f64, longitude: f64) -> (Vector3D<f64, U>, Vector3D<f64, U>) {
+    let daily_phase = get_phase(ts, world.daily_phase, world.sidereal_day);
     let normal = to_global_coords(
-        AXIAL_TILT,
-        AXIAL_DIRECTION,
+        world.axial_tilt,
+        world.axial_direction,
         to_recent_coords(daily_phase, to_local_coords(latitude, longitude, X_UNIT)),
     );
     let north = to_global_coords(
-        AXIAL_TILT,
-        AXIAL_DIRECTION,
+        world.axial_tilt,
+        world.axial_direction,
         to_recent_coords(daily_phase, to_local_coords(latitude, longitude, Z_UNIT)),
     );
     (normal, north)
@@ -81,10 +332,32 @@ fn get_object_direction(object_phase: f64) -> Vector3D<f64, U> {
     rot_z(object_phase, X_UNIT)
 }
 
+fn get_orbital_velocity(phase: f64, semimajor: f64, sidereal: f64) -> Vector3D<f64, U> {
+    // circular orbit, so the velocity is perpendicular to the radius vector; expressed as a fraction of c
+    rot_z(phase + PI / 2.0, X_UNIT) * (2.0 * PI * semimajor / sidereal / SPEED_OF_LIGHT)
+}
+
+fn apply_aberration(to_object: Vector3D<f64, U>, velocity: Vector3D<f64, U>) -> Vector3D<f64, U> {
+    (to_object + velocity).normalize()
+}
+
 fn get_inclined_direction(to_moon: Vector3D<f64, U>, inclination: f64, nodal_phase: f64) -> Vector3D<f64, U> {
     rot_z(-nodal_phase, rot_y(-inclination, rot_z(nodal_phase, to_moon)))
 }
 
+fn get_transit_separation(to_sun: Vector3D<f64, U>, to_planet: Vector3D<f64, U>) -> Option<f64> {
+    // a conjunction is only a transit if the planet is in front of the Sun and within its disk
+    if to_planet.length() >= to_sun.length() {
+        return None;
+    }
+    let separation = to_sun.normalize().dot(to_planet.normalize()).clamp(-1.0, 1.0).acos();
+    if separation < SUN_ANGULAR_RADIUS {
+        Some(separation)
+    } else {
+        None
+    }
+}
+
 fn get_altitude(normal: Vector3D<f64, U>, to_object: Vector3D<f64, U>) -> f64 {
     PI / 2.0 - normal.dot(to_object).acos()
 }
@@ -101,7 +374,8 @@ fn get_azimuth(normal: Vector3D<f64, U>, north: Vector3D<f64, U>, to_object: Vec
 }
 
 fn get_lunar_phase(to_sun: Vector3D<f64, U>, to_moon: Vector3D<f64, U>) -> f64 {
-    let angle = to_sun.dot(to_moon).acos();
+    // rounding can push the dot product of nearly aligned directions past one
+    let angle = to_sun.dot(to_moon).clamp(-1.0, 1.0).acos();
     if to_sun.cross(to_moon).dot(Z_UNIT) > 0.0 {
         angle
     } else {
@@ -143,70 +417,416 @@ fn get_moon_angle(
     }
 }
 
+impl World {
+    pub fn is_earth(&self) -> bool {
+        self.id == WorldId::Earth
+    }
+
+    fn position(&self, ts: f64) -> Vector3D<f64, U> {
+        // heliocentric, in millions of km, the mean place moved along the ellipse to the second order
+        let phase = get_phase(ts, self.phase, self.sidereal);
+        let anomaly = get_phase(ts, self.anomaly, self.anomalistic);
+        let e = self.eccentricity;
+        let center = 2.0 * e * anomaly.sin() + 1.25 * e * e * (2.0 * anomaly).sin();
+        let distance = self.semimajor * (1.0 - e * anomaly.cos());
+        get_inclined_direction(get_object_direction(phase + center), self.inclination, self.incl_phase) * distance
+    }
+
+    fn velocity(&self, ts: f64) -> Vector3D<f64, U> {
+        let phase = get_phase(ts, self.phase, self.sidereal);
+        let velocity = get_orbital_velocity(phase, self.semimajor, self.sidereal);
+        get_inclined_direction(velocity, self.inclination, self.incl_phase)
+    }
+}
+
+impl EclipseKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            EclipseKind::PartialSolar => "partial solar",
+            EclipseKind::CentralSolar => "central solar",
+            EclipseKind::PenumbralLunar => "penumbral lunar",
+            EclipseKind::PartialLunar => "partial lunar",
+            EclipseKind::TotalLunar => "total lunar",
+        }
+    }
+}
+
 impl Engine {
     pub fn new(time: DateTime<Utc>, latitude: f64, longitude: f64) -> Self {
+        Self::on_world(&EARTH, time, latitude, longitude)
+    }
+
+    // the phases meeus and stellarium give, without the [calibration] of the config
+    pub fn on_world(world: &World, time: DateTime<Utc>, latitude: f64, longitude: f64) -> Self {
+        Self::calibrated(world, time, latitude, longitude, Calibration::default())
+    }
+
+    pub fn calibrated(
+        world: &World,
+        time: DateTime<Utc>,
+        latitude: f64,
+        longitude: f64,
+        calibration: Calibration,
+    ) -> Self {
         let ts = time.timestamp() as f64 + time.timestamp_subsec_nanos() as f64 * 1e-9;
-        let (normal, north) = get_normal_and_north(ts, latitude, longitude);
+        let world = if world.is_earth() { calibration.earth() } else { *world };
+        let (normal, north) = get_normal_and_north(&world, ts, latitude, longitude);
         Self {
             time,
+            aberration: false,
+            refraction: false,
             ts,
+            world,
+            calibration,
             normal,
             north,
         }
     }
 
-    pub fn get_star_position(&self, star: &Star) -> (f64, f64) {
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn calibration(&self) -> Calibration {
+        self.calibration
+    }
+
+    fn on_earth(&self) -> bool {
+        self.world.is_earth()
+    }
+
+    fn aberrate(&self, to_object: Vector3D<f64, U>) -> Vector3D<f64, U> {
+        if self.aberration {
+            apply_aberration(to_object, self.world.velocity(self.ts))
+        } else {
+            to_object
+        }
+    }
+
+    fn get_apparent_altitude(&self, to_object: Vector3D<f64, U>) -> f64 {
+        let alt = get_altitude(self.normal, to_object);
+        if self.refraction {
+            refract(alt)
+        } else {
+            alt
+        }
+    }
+
+    fn get_sun_direction(&self) -> Vector3D<f64, U> {
+        -self.world.position(self.ts).normalize()
+    }
+
+    pub fn get_equatorial_position(&self, ascension: f64, declination: f64) -> (f64, f64) {
         let to_star = to_global_coords(
             AXIAL_TILT,
             AXIAL_DIRECTION,
-            to_local_coords(star.declination, star.ascension, X_UNIT),
+            to_local_coords(declination, ascension, X_UNIT),
         );
+        let to_star = self.aberrate(to_star);
 
-        let alt = get_altitude(self.normal, to_star);
+        let alt = self.get_apparent_altitude(to_star);
         let az = get_azimuth(self.normal, self.north, to_star);
 
         (alt, az)
     }
 
+    pub fn horizontal_to_equatorial(&self, alt: f64, az: f64) -> (f64, f64) {
+        let alt = if self.refraction { unrefract(alt) } else { alt };
+        let east = self.north.cross(self.normal);
+        let to_object = self.normal * alt.sin() + (self.north * az.cos() + east * az.sin()) * alt.cos();
+        to_equatorial(to_object)
+    }
+
+    pub fn get_julian_date(&self) -> f64 {
+        get_julian_date(self.ts)
+    }
+
+    pub fn get_sidereal_time(&self) -> f64 {
+        // local, in hours, the right ascension on the meridian
+        self.horizontal_to_equatorial(PI / 2.0, 0.0).0 / PI * 12.0
+    }
+
+    pub fn get_horizon_hours(&self) -> Vec<(u32, f64)> {
+        // azimuths where the hour circle of each whole hour of right ascension meets the horizon,
+        // every great circle through the poles crosses it twice, once for h and once for h + 12
+        let hours_at = |az: f64| self.horizontal_to_equatorial(0.0, az).0 / PI * 12.0;
+        let step = 2.0 * PI / HORIZON_SAMPLES as f64;
+        let mut crossings = Vec::new();
+        for i in 0..HORIZON_SAMPLES {
+            let az = i as f64 * step;
+            let start = hours_at(az);
+            let change = (hours_at(az + step) - start + 12.0).rem_euclid(24.0) - 12.0;
+            for hour in 0..24 {
+                let offset = (hour as f64 - start + 12.0).rem_euclid(24.0) - 12.0;
+                let fraction = offset / change;
+                if (0.0..1.0).contains(&fraction) {
+                    crossings.push((hour, az + fraction * step));
+                }
+            }
+        }
+        crossings
+    }
+
+    pub fn get_solar_time(&self) -> f64 {
+        // apparent local, in hours, noon is when the Sun crosses the meridian
+        let hour_angle = self.get_sidereal_time() - self.get_sun_equatorial().0 / PI * 12.0;
+        (hour_angle + 12.0).rem_euclid(24.0)
+    }
+
+    pub fn get_galactic_position(&self, longitude: f64, latitude: f64) -> (f64, f64) {
+        let (ascension, declination) = galactic_to_equatorial(longitude, latitude);
+        self.get_equatorial_position(ascension, declination)
+    }
+
+    pub fn get_star_equatorial(&self, star: &Star) -> (f64, f64) {
+        // the catalog place carried from its epoch into the frame the engine is fixed to, the rotation of the sky
+        // already follows the moving equinox so the place isn't carried on to the date
+        precess(star.ascension, star.declination, star.epoch, J2000)
+    }
+
+    pub fn get_star_position(&self, star: &Star) -> (f64, f64) {
+        let (ascension, declination) = self.get_star_equatorial(star);
+        self.get_equatorial_position(ascension, declination)
+    }
+
+    pub fn get_companion_position(&self, star: &Star) -> Option<(f64, f64)> {
+        let companion = star.companion.as_ref()?;
+        let (ascension, declination) = self.get_star_equatorial(star);
+        // small angle offsets, position angle is measured from north through east
+        let offset_declination = declination + companion.separation * companion.position_angle.cos();
+        let offset_ascension = ascension + companion.separation * companion.position_angle.sin() / declination.cos();
+        Some(self.get_equatorial_position(offset_ascension, offset_declination))
+    }
+
+    pub fn get_star_magnitude(&self, star: &Star) -> f64 {
+        let Some(variability) = &star.variability else {
+            return star.magnitude;
+        };
+        let cycle = (get_julian_date(self.ts) - variability.epoch) / variability.period;
+        let cycle = cycle - cycle.floor();
+        let amplitude = variability.min_magnitude - variability.max_magnitude;
+        let dimming = match variability.kind {
+            // epoch is the time of maximum light
+            VariableKind::Pulsating => (1.0 - (2.0 * PI * cycle).cos()) / 2.0,
+            // epoch is the middle of the primary eclipse
+            VariableKind::Eclipsing => (1.0 - cycle.min(1.0 - cycle) / (ECLIPSE_DURATION / 2.0)).max(0.0),
+        };
+        variability.max_magnitude + amplitude * dimming
+    }
+
     pub fn get_sun_position(&self) -> (f64, f64) {
-        let phase = get_phase(self.ts, INITIAL_PHASE, SIDEREAL_YEAR);
-        let to_sun = get_sun_direction(phase);
+        let to_sun = self.get_sun_direction();
 
-        let alt = get_altitude(self.normal, to_sun);
+        let alt = self.get_apparent_altitude(to_sun);
         let az = get_azimuth(self.normal, self.north, to_sun);
 
         (alt, az)
     }
 
-    pub fn get_ecliptic_points(&self) -> [(f64, f64); 3] {
-        let phase = get_phase(self.ts, INITIAL_PHASE, SIDEREAL_YEAR);
+    pub fn get_sun_distance(&self) -> f64 {
+        // in au
+        self.world.position(self.ts).length() / SEMIMAJOR
+    }
 
-        let to_sun = get_sun_direction(phase);
-        let alt0 = get_altitude(self.normal, to_sun);
-        let az0 = get_azimuth(self.normal, self.north, to_sun);
+    pub fn get_sun_equatorial(&self) -> (f64, f64) {
+        to_equatorial(self.get_sun_direction())
+    }
 
-        let to_sun = get_sun_direction((phase + 2.0 * PI / 3.0) % (2.0 * PI));
-        let alt1 = get_altitude(self.normal, to_sun);
-        let az1 = get_azimuth(self.normal, self.north, to_sun);
+    pub fn get_sun_longitude(&self) -> f64 {
+        // ecliptic, from the march equinox
+        let (ascension, declination) = self.get_sun_equatorial();
+        (ascension.sin() * AXIAL_TILT.cos() + declination.tan() * AXIAL_TILT.sin())
+            .atan2(ascension.cos())
+            .rem_euclid(2.0 * PI)
+    }
 
-        let to_sun = get_sun_direction((phase + 4.0 * PI / 3.0) % (2.0 * PI));
-        let alt2 = get_altitude(self.normal, to_sun);
-        let az2 = get_azimuth(self.normal, self.north, to_sun);
+    pub fn get_ecliptic_points(&self, count: usize) -> Vec<(f64, f64)> {
+        // evenly spaced around the ecliptic starting from the Sun, three are enough to fit its projected circle
+        (0..count)
+            .map(|i| self.get_ecliptic_offset(2.0 * PI * i as f64 / count as f64, 0.0))
+            .collect()
+    }
 
-        [(alt0, az0), (alt1, az1), (alt2, az2)]
+    pub fn get_ecliptic_offset(&self, elongation: f64, latitude: f64) -> (f64, f64) {
+        // the point that far along the ecliptic from the Sun, eastwards, and that far north of it
+        // the Sun only leaves the ecliptic when seen from another world, so it is flattened onto it
+        let to_sun = self.get_sun_direction();
+        let to_sun = vec3(to_sun.x, to_sun.y, 0.0).normalize();
+        let to_point = rot_z(elongation, to_sun) * latitude.cos() + Z_UNIT * latitude.sin();
+        (
+            self.get_apparent_altitude(to_point),
+            get_azimuth(self.normal, self.north, to_point),
+        )
     }
 
-    pub fn get_moon_position(&self) -> (f64, f64, f64, f64) {
-        let moon_phase = get_phase(self.ts, INITIAL_MOON_PHASE, SIDEREAL_MONTH);
-        let to_moon = get_object_direction(moon_phase);
+    fn get_lunar_arguments(&self) -> (f64, f64, f64) {
+        // the Moon's mean elongation from the Sun, the Sun's mean anomaly and the Moon's
+        let calibration = &self.calibration;
+        let moon_phase = get_phase(self.ts, calibration.moon_phase, SIDEREAL_MONTH);
+        (
+            moon_phase - get_phase(self.ts, calibration.solar_phase + PI, EARTH.sidereal),
+            get_phase(self.ts, calibration.solar_anomaly, EARTH.anomalistic),
+            get_phase(self.ts, calibration.moon_anomaly, ANOMALISTIC_MONTH),
+        )
+    }
+
+    fn get_moon_distance(&self) -> f64 {
+        // from the Earth's center, in millions of km
+        let (elongation, solar_anomaly, anomaly) = self.get_lunar_arguments();
+        let correction: f64 = LUNAR_DISTANCE_TERMS
+            .iter()
+            .map(|&(amount, d, m, mp)| amount * (d * elongation + m * solar_anomaly + mp * anomaly).cos())
+            .sum();
+        MOON_DISTANCE + correction
+    }
+
+    fn get_moon_direction(&self) -> Vector3D<f64, U> {
+        self.get_observer_to_moon().normalize()
+    }
+
+    fn get_observer_to_moon(&self) -> Vector3D<f64, U> {
+        // from the Earth's center, or from the world looked from, in millions of km
+        let moon_phase = get_phase(self.ts, self.calibration.moon_phase, SIDEREAL_MONTH);
+        let (elongation, solar_anomaly, anomaly) = self.get_lunar_arguments();
+        let correction: f64 = LUNAR_TERMS
+            .iter()
+            .map(|&(degrees, d, m, mp)| degrees * (d * elongation + m * solar_anomaly + mp * anomaly).sin())
+            .sum();
+        let to_moon = get_object_direction(moon_phase + correction / 180.0 * PI);
+
+        let nodal_phase = get_phase(self.ts, self.calibration.nodal_phase, NODAL_PERIOD);
+        let to_moon = get_inclined_direction(to_moon, MOON_INCLINATION, nodal_phase) * self.get_moon_distance();
+        if self.on_earth() {
+            return to_moon;
+        }
+        self.calibration.earth().position(self.ts) + to_moon - self.world.position(self.ts)
+    }
+
+    pub fn get_sun_angular_radius(&self) -> f64 {
+        SUN_ANGULAR_RADIUS / self.get_sun_distance()
+    }
+
+    pub fn get_moon_angular_radius(&self) -> f64 {
+        // seen from the surface, an earth radius closer overhead than from the center
+        let to_moon = self.get_observer_to_moon();
+        let to_moon = if self.on_earth() {
+            to_moon - self.normal * EARTH_RADIUS
+        } else {
+            to_moon
+        };
+        MOON_RADIUS / to_moon.length()
+    }
+
+    fn get_eclipse(&self) -> Option<(EclipseKind, f64)> {
+        // the kind of eclipse seen from the location and the separation of the disks from the shadow or the Sun
+        if !self.on_earth() {
+            return None;
+        }
+        let to_sun = self.get_sun_direction();
+        let to_moon = self.get_moon_direction();
+        // the disks and the shadow grow and shrink with the distances
+        let sun_distance = self.get_sun_distance();
+        let sun_radius = SUN_ANGULAR_RADIUS / sun_distance;
+        let moon_distance = self.get_moon_distance();
+        if to_sun.dot(to_moon) > 0.0 {
+            // the observer sits an earth radius off the center, which shifts the Moon by up to a degree
+            let to_moon = to_moon * moon_distance - self.normal * EARTH_RADIUS;
+            let moon_radius = MOON_RADIUS / to_moon.length();
+            let to_moon = to_moon.normalize();
+            let separation = to_sun.dot(to_moon).clamp(-1.0, 1.0).acos();
+            let kind = match separation {
+                _ if get_altitude(self.normal, to_sun) <= 0.0 => None,
+                d if d < (sun_radius - moon_radius).abs() => Some(EclipseKind::CentralSolar),
+                d if d < sun_radius + moon_radius => Some(EclipseKind::PartialSolar),
+                _ => None,
+            };
+            kind.map(|kind| (kind, separation))
+        } else {
+            let moon_radius = MOON_RADIUS / moon_distance;
+            let parallax = EARTH_RADIUS / moon_distance + EARTH_RADIUS / (sun_distance * SEMIMAJOR);
+            let umbra = SHADOW_ENLARGEMENT * (parallax - sun_radius);
+            let penumbra = SHADOW_ENLARGEMENT * (parallax + sun_radius);
+            let separation = (-to_sun).dot(to_moon).clamp(-1.0, 1.0).acos();
+            let kind = match separation {
+                _ if get_altitude(self.normal, to_moon) <= 0.0 => None,
+                d if d < umbra - moon_radius => Some(EclipseKind::TotalLunar),
+                d if d < umbra + moon_radius => Some(EclipseKind::PartialLunar),
+                d if d < penumbra + moon_radius => Some(EclipseKind::PenumbralLunar),
+                _ => None,
+            };
+            kind.map(|kind| (kind, separation))
+        }
+    }
+
+    pub fn get_solar_eclipse(&self) -> Option<f64> {
+        // separation of the Moon from the center of the solar disk while it covers part of it
+        match self.get_eclipse()? {
+            (EclipseKind::PartialSolar | EclipseKind::CentralSolar, separation) => Some(separation),
+            _ => None,
+        }
+    }
 
-        let nodal_phase = get_phase(self.ts, INITIAL_NODAL_PHASE, NODAL_PERIOD);
-        let to_moon = get_inclined_direction(to_moon, MOON_INCLINATION, nodal_phase);
+    fn get_ascending_node(&self) -> Vector3D<f64, U> {
+        // where the Moon's orbit crosses the ecliptic northwards
+        let nodal_phase = get_phase(self.ts, self.calibration.nodal_phase, NODAL_PERIOD);
+        rot_z(-nodal_phase - PI / 2.0, X_UNIT)
+    }
 
-        let sun_phase = get_phase(self.ts, INITIAL_PHASE, SIDEREAL_YEAR);
-        let to_sun = get_sun_direction(sun_phase);
+    pub fn get_lunar_nodes(&self) -> Option<[(f64, f64); 2]> {
+        // ascending and descending, they only mean something as seen from the Earth
+        if !self.on_earth() {
+            return None;
+        }
+        let node = self.get_ascending_node();
+        Some([node, -node].map(|node| {
+            (
+                self.get_apparent_altitude(node),
+                get_azimuth(self.normal, self.north, node),
+            )
+        }))
+    }
 
-        let alt = get_altitude(self.normal, to_moon);
+    pub fn is_eclipse_season(&self) -> bool {
+        // the Sun close enough to a node for the next new or full moon to bring an eclipse
+        let separation = self
+            .get_sun_direction()
+            .dot(self.get_ascending_node())
+            .clamp(-1.0, 1.0)
+            .acos();
+        self.on_earth() && separation.min(PI - separation) < ECLIPSE_SEASON
+    }
+
+    pub fn get_parallactic_angle(&self, alt: f64, az: f64) -> f64 {
+        // at the object, from the direction to the zenith to the direction to the pole of the world,
+        // positive west of the meridian; how fast it changes is the field rotation of an alt-az mount
+        let pole = to_global_coords(self.world.axial_tilt, self.world.axial_direction, Z_UNIT);
+        let pole_alt = get_altitude(self.normal, pole);
+        // the pole lies due north by the definition of north
+        (-az.sin() * pole_alt.cos()).atan2(alt.cos() * pole_alt.sin() - alt.sin() * pole_alt.cos() * az.cos())
+    }
+
+    pub fn get_moon_libration(&self) -> (f64, f64) {
+        // selenographic longitude and latitude of the point facing the Earth
+        let calibration = &self.calibration;
+        let moon_phase = get_phase(self.ts, calibration.moon_phase, SIDEREAL_MONTH);
+        let nodal_phase = get_phase(self.ts, calibration.nodal_phase, NODAL_PERIOD);
+        let anomaly = get_phase(self.ts, calibration.moon_anomaly, ANOMALISTIC_MONTH);
+        // uniform rotation against the uneven orbital motion
+        let longitude = -EQUATION_OF_CENTER * anomaly.sin();
+        // the lunar equator is tilted the other way from the orbit, cassini's laws
+        let latitude = -(MOON_INCLINATION + LUNAR_EQUATOR_INCLINATION) * (moon_phase - nodal_phase).sin();
+        (longitude, latitude)
+    }
+
+    pub fn get_moon_equatorial(&self) -> (f64, f64) {
+        to_equatorial(self.get_moon_direction())
+    }
+
+    pub fn get_moon_position(&self) -> (f64, f64, f64, f64) {
+        let to_moon = self.get_moon_direction();
+        let to_sun = self.get_sun_direction();
+
+        let alt = self.get_apparent_altitude(to_moon);
         let az = get_azimuth(self.normal, self.north, to_moon);
         let lunar_phase = get_lunar_phase(to_sun, to_moon);
         let angle = get_moon_angle(self.normal, self.north, to_moon, to_sun, az, lunar_phase);
@@ -214,26 +834,592 @@ impl Engine {
         (alt, az, lunar_phase, angle)
     }
 
-    pub fn get_planet_position(&self, planet: &Planet) -> (f64, f64) {
-        let phase = get_phase(self.ts, INITIAL_PHASE, SIDEREAL_YEAR);
-        let to_earth = get_object_direction(phase);
-
+    fn get_observer_to_planet(&self, planet: &Planet) -> Vector3D<f64, U> {
         let phase = get_phase(self.ts, planet.phase, planet.sidereal);
         let to_planet = get_object_direction(phase);
         let to_planet = get_inclined_direction(to_planet, planet.inclination, planet.incl_phase);
 
-        let earth_to_planet = (to_planet * planet.semimajor - to_earth * SEMIMAJOR).normalize();
+        to_planet * planet.semimajor - self.world.position(self.ts)
+    }
 
-        let alt = get_altitude(self.normal, earth_to_planet);
-        let az = get_azimuth(self.normal, self.north, earth_to_planet);
+    pub fn is_observer(&self, planet: &Planet) -> bool {
+        planet.name == self.world.name
+    }
 
-        (alt, az)
+    pub fn get_earth_position(&self) -> Option<(f64, f64)> {
+        // only there to be seen from another world
+        if self.on_earth() {
+            return None;
+        }
+        let earth = self.calibration.earth();
+        let to_earth = self.aberrate((earth.position(self.ts) - self.world.position(self.ts)).normalize());
+        Some((
+            self.get_apparent_altitude(to_earth),
+            get_azimuth(self.normal, self.north, to_earth),
+        ))
+    }
+
+    pub fn get_planet_position(&self, planet: &Planet) -> (f64, f64, f64) {
+        let observer_to_planet = self.get_observer_to_planet(planet);
+        let distance = observer_to_planet.length() / SEMIMAJOR;
+        let observer_to_planet = self.aberrate(observer_to_planet.normalize());
+
+        let alt = self.get_apparent_altitude(observer_to_planet);
+        let az = get_azimuth(self.normal, self.north, observer_to_planet);
+
+        (alt, az, distance)
+    }
+
+    pub fn get_object_position(&self, object: SkyObject, stars: &[Star], planets: &[Planet]) -> Option<(f64, f64)> {
+        // none for the layers without a catalog to look them up in
+        match object {
+            SkyObject::Star(index) => Some(self.get_star_position(&stars[index])),
+            SkyObject::Planet(index) => {
+                let (alt, az, _) = self.get_planet_position(&planets[index]);
+                Some((alt, az))
+            }
+            SkyObject::Sun => Some(self.get_sun_position()),
+            SkyObject::Moon => {
+                let (alt, az, _, _) = self.get_moon_position();
+                Some((alt, az))
+            }
+            SkyObject::DeepSky(_) | SkyObject::Satellite(_) => None,
+        }
+    }
+
+    pub fn get_object_equatorial(&self, object: SkyObject, stars: &[Star], planets: &[Planet]) -> Option<(f64, f64)> {
+        match object {
+            SkyObject::Star(index) => Some(self.get_star_equatorial(&stars[index])),
+            SkyObject::Planet(index) => Some(self.get_planet_equatorial(&planets[index])),
+            SkyObject::Sun => Some(self.get_sun_equatorial()),
+            SkyObject::Moon => Some(self.get_moon_equatorial()),
+            SkyObject::DeepSky(_) | SkyObject::Satellite(_) => None,
+        }
+    }
+
+    pub fn get_hour_angle(&self, object: SkyObject, stars: &[Star], planets: &[Planet]) -> Option<f64> {
+        // radians west of the meridian, negative while the object still climbs towards it
+        let (ascension, _) = self.get_object_equatorial(object, stars, planets)?;
+        Some((self.get_sidereal_time() / 12.0 * PI - ascension + PI).rem_euclid(2.0 * PI) - PI)
+    }
+
+    pub fn get_planet_equatorial(&self, planet: &Planet) -> (f64, f64) {
+        to_equatorial(self.aberrate(self.get_observer_to_planet(planet).normalize()))
+    }
+
+    pub fn get_planet_magnitude(&self, planet: &Planet) -> f64 {
+        let to_planet = self.get_observer_to_planet(planet);
+        let phase = get_phase(self.ts, planet.phase, planet.sidereal);
+        let from_sun = get_inclined_direction(get_object_direction(phase), planet.inclination, planet.incl_phase);
+        let phase_angle = from_sun.dot(to_planet.normalize()).clamp(-1.0, 1.0).acos();
+        let distances = planet.semimajor * to_planet.length() / (SEMIMAJOR * SEMIMAJOR);
+        planet.abs_magnitude + 5.0 * distances.log10() + PHASE_COEFFICIENT * phase_angle / PI * 180.0
+    }
+
+    pub fn get_elongation(&self, planet: &Planet) -> f64 {
+        let to_sun = self.get_sun_direction();
+        let to_planet = self.get_observer_to_planet(planet).normalize();
+        let angle = to_sun.dot(to_planet).clamp(-1.0, 1.0).acos();
+        if to_sun.cross(to_planet).dot(Z_UNIT) > 0.0 {
+            angle
+        } else {
+            -angle
+        }
+    }
+
+    pub fn get_solar_transit(&self, planet: &Planet) -> Option<f64> {
+        get_transit_separation(-self.world.position(self.ts), self.get_observer_to_planet(planet))
+    }
+}
+
+pub fn get_refraction(alt: f64) -> f64 {
+    // saemundsson, how much the air lifts an object at the true altitude alt, half a degree at the horizon
+    let degrees = alt.max(REFRACTION_FLOOR) / PI * 180.0;
+    let minutes = 1.02 / ((degrees + 10.3 / (degrees + 5.11)) / 180.0 * PI).tan();
+    minutes / 60.0 / 180.0 * PI
+}
+
+pub fn refract(alt: f64) -> f64 {
+    alt + get_refraction(alt)
+}
+
+pub fn unrefract(apparent: f64) -> f64 {
+    // the true altitude that appears at apparent, refraction changes slowly enough to converge in a dozen or so steps
+    (0..20).fold(apparent, |alt, _| apparent - get_refraction(alt))
+}
+
+pub fn is_circumpolar(declination: f64, latitude: f64) -> bool {
+    // never sets, on the side of the raised pole and closer to it than the latitude is to the equator
+    declination * latitude.signum() > PI / 2.0 - latitude.abs()
+}
+
+pub fn get_airmass(alt: f64) -> f64 {
+    // kasten and young, finite at the horizon where the plane-parallel 1 / sin(alt) blows up
+    let alt = alt.max(0.0);
+    let degrees = alt / PI * 180.0;
+    1.0 / (alt.sin() + 0.50572 * (degrees + 6.07995).powf(-1.6364))
+}
+
+pub fn find_greatest_elongations(
+    world: &World,
+    calibration: &Calibration,
+    planet: &Planet,
+    start: DateTime<Utc>,
+    days: i64,
+) -> Vec<Elongation> {
+    let elongation = |time: DateTime<Utc>| {
+        Engine::calibrated(world, time, 0.0, 0.0, *calibration)
+            .get_elongation(planet)
+            .abs()
+    };
+    let day = Duration::days(1);
+    let mut elongations = Vec::new();
+    let mut previous = elongation(start - day);
+    let mut current = elongation(start);
+    for i in 1..=days {
+        let next = elongation(start + day * i as i32);
+        if current > previous && current >= next {
+            // ternary search for the maximum within a day of the sampled one
+            let mut low = start + day * (i as i32 - 2);
+            let mut high = start + day * i as i32;
+            while high - low > Duration::seconds(60) {
+                let third = (high - low) / 3;
+                if elongation(low + third) < elongation(high - third) {
+                    low += third;
+                } else {
+                    high -= third;
+                }
+            }
+            let time = low + (high - low) / 2;
+            elongations.push(Elongation {
+                time,
+                angle: Engine::calibrated(world, time, 0.0, 0.0, *calibration).get_elongation(planet),
+            });
+        }
+        previous = current;
+        current = next;
     }
+    elongations
+}
+
+pub fn find_crossings(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    horizon: f64,
+    altitude: impl Fn(DateTime<Utc>) -> f64,
+) -> Vec<Crossing> {
+    let step = Duration::minutes(CROSSING_SAMPLING);
+    let mut crossings = Vec::new();
+    let mut time = start;
+    let mut above = altitude(time) > horizon;
+    while time < end {
+        let next = min(time + step, end);
+        let next_above = altitude(next) > horizon;
+        if next_above != above {
+            // bisection down to a second
+            let (mut low, mut high) = (time, next);
+            while high - low > Duration::seconds(1) {
+                let middle = low + (high - low) / 2;
+                if (altitude(middle) > horizon) == above {
+                    low = middle;
+                } else {
+                    high = middle;
+                }
+            }
+            crossings.push(Crossing {
+                time: low + (high - low) / 2,
+                rising: next_above,
+            });
+        }
+        time = next;
+        above = next_above;
+    }
+    crossings
+}
+
+fn find_transit(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    horizon: f64,
+    altitude: impl Fn(DateTime<Utc>) -> f64,
+) -> Option<DateTime<Utc>> {
+    let step = Duration::minutes(CROSSING_SAMPLING);
+    let mut samples = Vec::new();
+    let mut time = start;
+    while time <= end {
+        samples.push((time, altitude(time)));
+        time += step;
+    }
+    for window in samples.windows(3) {
+        let [(before, a0), (_, a1), (after, a2)] = window else {
+            unreachable!()
+        };
+        if a1 <= a0 || a1 < a2 {
+            continue;
+        }
+        // ternary search for the highest point down to a second
+        let (mut low, mut high) = (*before, *after);
+        while high - low > Duration::seconds(1) {
+            let third = (high - low) / 3;
+            if altitude(low + third) < altitude(high - third) {
+                low += third;
+            } else {
+                high -= third;
+            }
+        }
+        let transit = low + (high - low) / 2;
+        if altitude(transit) > horizon {
+            return Some(transit);
+        }
+    }
+    None
+}
+
+pub fn find_passage(start: DateTime<Utc>, horizon: f64, altitude: impl Fn(DateTime<Utc>) -> f64) -> Option<Passage> {
+    // the first rise, culmination and set within a day, none when the object stays below the horizon
+    let end = start + Duration::days(1);
+    let crossings = find_crossings(start, end, horizon, &altitude);
+    if crossings.is_empty() && altitude(start) <= horizon {
+        return None;
+    }
+    Some(Passage {
+        rise: crossings
+            .iter()
+            .find(|crossing| crossing.rising)
+            .map(|crossing| crossing.time),
+        transit: find_transit(start, end, horizon, &altitude),
+        set: crossings
+            .iter()
+            .find(|crossing| !crossing.rising)
+            .map(|crossing| crossing.time),
+    })
+}
+
+pub fn get_sun_passage(
+    calibration: &Calibration,
+    start: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+) -> Option<Passage> {
+    find_passage(start, SUNRISE_ALTITUDE, |time| {
+        calibration.engine(time, latitude, longitude).get_sun_position().0
+    })
+}
+
+pub fn get_moon_passage(
+    calibration: &Calibration,
+    start: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+) -> Option<Passage> {
+    // the Moon moves half a degree an hour, so it is looked up anew for every sample
+    find_passage(start, MOONRISE_ALTITUDE, |time| {
+        calibration.engine(time, latitude, longitude).get_moon_position().0
+    })
+}
+
+pub fn get_planet_passage(
+    calibration: &Calibration,
+    start: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+    planet: &Planet,
+) -> Option<Passage> {
+    find_passage(start, PLANETRISE_ALTITUDE, |time| {
+        calibration
+            .engine(time, latitude, longitude)
+            .get_planet_position(planet)
+            .0
+    })
+}
+
+pub fn get_passage_azimuths(
+    passage: Option<Passage>,
+    azimuth: impl Fn(DateTime<Utc>) -> f64,
+) -> (Option<f64>, Option<f64>) {
+    // where along the horizon the rise and the set of the passage happen
+    match passage {
+        Some(passage) => (passage.rise.map(&azimuth), passage.set.map(&azimuth)),
+        None => (None, None),
+    }
+}
+
+pub fn get_sun_azimuths(
+    calibration: &Calibration,
+    start: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+) -> (Option<f64>, Option<f64>) {
+    get_passage_azimuths(get_sun_passage(calibration, start, latitude, longitude), |time| {
+        calibration.engine(time, latitude, longitude).get_sun_position().1
+    })
+}
+
+pub fn get_moon_azimuths(
+    calibration: &Calibration,
+    start: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+) -> (Option<f64>, Option<f64>) {
+    get_passage_azimuths(get_moon_passage(calibration, start, latitude, longitude), |time| {
+        calibration.engine(time, latitude, longitude).get_moon_position().1
+    })
+}
+
+pub fn find_next_crossing(
+    start: DateTime<Utc>,
+    horizon: f64,
+    rising: bool,
+    altitude: impl Fn(DateTime<Utc>) -> f64,
+) -> Option<DateTime<Utc>> {
+    // the first rise or set after the start, none when the object stays up or down all along
+    let end = start + Duration::days(CROSSING_SEARCH_DAYS);
+    find_crossings(start, end, horizon, altitude)
+        .into_iter()
+        .find(|crossing| crossing.rising == rising)
+        .map(|crossing| crossing.time)
+}
+
+pub fn find_sun_crossing(
+    calibration: &Calibration,
+    start: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+    rising: bool,
+) -> Option<DateTime<Utc>> {
+    find_next_crossing(start, SUNRISE_ALTITUDE, rising, |time| {
+        calibration.engine(time, latitude, longitude).get_sun_position().0
+    })
+}
+
+pub fn find_moon_crossing(
+    calibration: &Calibration,
+    start: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+    rising: bool,
+) -> Option<DateTime<Utc>> {
+    find_next_crossing(start, MOONRISE_ALTITUDE, rising, |time| {
+        calibration.engine(time, latitude, longitude).get_moon_position().0
+    })
+}
+
+pub fn find_dark_window(
+    start: DateTime<Utc>,
+    horizon: f64,
+    sun_altitude: impl Fn(DateTime<Utc>) -> f64,
+    altitude: impl Fn(DateTime<Utc>) -> f64,
+) -> Option<DarkWindow> {
+    // the longest stretch within a day of the object above the horizon after dusk and before dawn,
+    // none when it is never up in the dark
+    let end = start + Duration::days(1);
+    let object_margin = |time| altitude(time) - horizon;
+    let sun_margin = |time| ASTRONOMICAL_TWILIGHT - sun_altitude(time);
+    let margin = |time| f64::min(object_margin(time), sun_margin(time));
+    let mut windows = Vec::new();
+    let mut since = (margin(start) > 0.0).then_some(start);
+    for crossing in find_crossings(start, end, 0.0, margin) {
+        match (crossing.rising, since) {
+            (true, _) => since = Some(crossing.time),
+            (false, Some(rise)) => {
+                windows.push((rise, crossing.time));
+                since = None;
+            }
+            (false, None) => {}
+        }
+    }
+    if let Some(rise) = since {
+        windows.push((rise, end));
+    }
+    let (window_start, window_end) = windows.into_iter().max_by_key(|&(rise, set)| set - rise)?;
+    // sampled for the highest point, then narrowed down to a second around it
+    let step = Duration::minutes(CROSSING_SAMPLING);
+    let mut best = window_start;
+    let mut time = window_start;
+    while time <= window_end {
+        if altitude(time) > altitude(best) {
+            best = time;
+        }
+        time += step;
+    }
+    if altitude(window_end) > altitude(best) {
+        best = window_end;
+    }
+    let (mut low, mut high) = (max(best - step, window_start), min(best + step, window_end));
+    while high - low > Duration::seconds(1) {
+        let third = (high - low) / 3;
+        if altitude(low + third) < altitude(high - third) {
+            low += third;
+        } else {
+            high -= third;
+        }
+    }
+    // the twilight rather than the horizon closes the window at both ends
+    let sun_bound = |time| sun_margin(time) < object_margin(time);
+    Some(DarkWindow {
+        start: window_start,
+        end: window_end,
+        best: low + (high - low) / 2,
+        all_night: sun_bound(window_start) && sun_bound(window_end),
+    })
+}
+
+pub fn get_star_window(
+    calibration: &Calibration,
+    start: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+    star: &Star,
+) -> Option<DarkWindow> {
+    find_dark_window(
+        start,
+        PLANETRISE_ALTITUDE,
+        |time| calibration.engine(time, latitude, longitude).get_sun_position().0,
+        |time| calibration.engine(time, latitude, longitude).get_star_position(star).0,
+    )
+}
+
+pub fn get_planet_window(
+    calibration: &Calibration,
+    start: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+    planet: &Planet,
+) -> Option<DarkWindow> {
+    find_dark_window(
+        start,
+        PLANETRISE_ALTITUDE,
+        |time| calibration.engine(time, latitude, longitude).get_sun_position().0,
+        |time| {
+            calibration
+                .engine(time, latitude, longitude)
+                .get_planet_position(planet)
+                .0
+        },
+    )
+}
+
+pub fn get_moon_window(
+    calibration: &Calibration,
+    start: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+) -> Option<DarkWindow> {
+    find_dark_window(
+        start,
+        MOONRISE_ALTITUDE,
+        |time| calibration.engine(time, latitude, longitude).get_sun_position().0,
+        |time| calibration.engine(time, latitude, longitude).get_moon_position().0,
+    )
+}
+
+pub fn get_day_length(calibration: &Calibration, start: DateTime<Utc>, latitude: f64, longitude: f64) -> Duration {
+    let end = start + Duration::days(1);
+    let altitude = |time| calibration.engine(time, latitude, longitude).get_sun_position().0;
+    let mut day = Duration::zero();
+    let mut since = (altitude(start) > SUNRISE_ALTITUDE).then_some(start);
+    for crossing in find_crossings(start, end, SUNRISE_ALTITUDE, altitude) {
+        match (crossing.rising, since) {
+            (true, _) => since = Some(crossing.time),
+            (false, Some(rise)) => {
+                day += crossing.time - rise;
+                since = None;
+            }
+            (false, None) => {}
+        }
+    }
+    if let Some(rise) = since {
+        day += end - rise;
+    }
+    day
+}
+
+pub fn find_sun_longitude(
+    world: &World,
+    calibration: &Calibration,
+    start: DateTime<Utc>,
+    longitude: f64,
+) -> DateTime<Utc> {
+    // the nearest time the Sun stands at that ecliptic longitude, stepping by its mean motion,
+    // the orbits are close enough to circles for a few steps to settle it
+    let mut time = start;
+    for _ in 0..SUN_LONGITUDE_ITERATIONS {
+        let current = Engine::calibrated(world, time, 0.0, 0.0, *calibration).get_sun_longitude();
+        let offset = (longitude - current + PI).rem_euclid(2.0 * PI) - PI;
+        time += Duration::milliseconds((offset / (2.0 * PI) * world.sidereal * 1000.0) as i64);
+    }
+    time
+}
+
+pub fn find_eclipses(
+    calibration: &Calibration,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+) -> Vec<Eclipse> {
+    // the eclipses of the Moon and by the Moon, so always seen from the Earth; syzygies are found on a daily scan
+    // of the lunar phase against the mean Sun, then only their surroundings are searched, wide enough for the hours
+    // the true Sun can lead or trail it
+    let lunar_phase = |time: DateTime<Utc>| {
+        let engine = calibration.engine(time, 0.0, 0.0);
+        let to_sun = get_sun_direction(get_phase(engine.ts, engine.calibration.solar_phase, SIDEREAL_YEAR));
+        get_lunar_phase(to_sun, engine.get_moon_direction())
+    };
+    let mut eclipses = Vec::new();
+    let day = Duration::days(1);
+    let mut time = start;
+    let mut phase = lunar_phase(time);
+    while time < end {
+        let next_phase = lunar_phase(time + day);
+        // new moon wraps the phase around, full moon passes through pi
+        let syzygy = if next_phase < phase {
+            Some(true)
+        } else if phase < PI && next_phase >= PI {
+            Some(false)
+        } else {
+            None
+        };
+        if let Some(new_moon) = syzygy {
+            let before = |phase: f64| if new_moon { phase > PI } else { phase < PI };
+            let (mut low, mut high) = (time, time + day);
+            while high - low > Duration::minutes(1) {
+                let middle = low + (high - low) / 2;
+                if before(lunar_phase(middle)) {
+                    low = middle;
+                } else {
+                    high = middle;
+                }
+            }
+            let engine = calibration.engine(low, 0.0, 0.0);
+            if engine.get_moon_direction().z.abs().asin() < ECLIPSE_LIMIT {
+                // the greatest eclipse is where the disks come closest
+                let greatest = (-ECLIPSE_WINDOW..=ECLIPSE_WINDOW)
+                    .step_by(ECLIPSE_SAMPLING as usize)
+                    .map(|minute| low + Duration::minutes(minute))
+                    .filter_map(|time| {
+                        let (kind, separation) = calibration.engine(time, latitude, longitude).get_eclipse()?;
+                        Some((Eclipse { time, kind }, separation))
+                    })
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .map(|(eclipse, _)| eclipse);
+                if let Some(eclipse) = greatest.filter(|eclipse| (start..end).contains(&eclipse.time)) {
+                    eclipses.push(eclipse);
+                }
+            }
+        }
+        time += day;
+        phase = next_phase;
+    }
+    eclipses
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::math::angular_distance;
+    use crate::{Companion, ObjectKind, Variability};
+    use chrono::Duration;
 
     const Y_UNIT: Vector3D<f64, U> = vec3(0.0, 1.0, 0.0);
 
@@ -242,6 +1428,20 @@ mod tests {
         assert!((get_phase(0.0, INITIAL_PHASE, SIDEREAL_YEAR) - INITIAL_PHASE).abs() < 1e-4);
     }
 
+    #[test]
+    fn test_galactic_to_equatorial() {
+        let (ascension, declination) = galactic_to_equatorial(0.0, 0.0);
+        assert!((ascension / PI * 180.0 - 266.405).abs() < 1e-3);
+        assert!((declination / PI * 180.0 + 28.936).abs() < 1e-3);
+
+        let (ascension, declination) = galactic_to_equatorial(1.0, PI / 2.0);
+        assert!((ascension - GALACTIC_POLE_ASCENSION).abs() < 1e-9);
+        assert!((declination - GALACTIC_POLE_DECLINATION).abs() < 1e-9);
+
+        let (_, declination) = galactic_to_equatorial(GALACTIC_NODE, GALACTIC_POLE_DECLINATION);
+        assert!((declination - PI / 2.0).abs() < 1e-6);
+    }
+
     #[test]
     fn test_to_local_coords() {
         let normal = X_UNIT;
@@ -269,6 +1469,265 @@ mod tests {
         assert!((to_global_coords(PI / 2.0, -PI / 2.0, axis) + Y_UNIT).length() < 1e-15);
     }
 
+    #[test]
+    fn test_to_equatorial() {
+        for (ascension, declination) in [(0.0, 0.0), (1.0, 0.5), (4.0, -1.2), (6.0, 1.5)] {
+            let vec = to_global_coords(
+                AXIAL_TILT,
+                AXIAL_DIRECTION,
+                to_local_coords(declination, ascension, X_UNIT),
+            );
+            let (ra, dec) = to_equatorial(vec);
+            assert!((ra - ascension).abs() < 1e-12);
+            assert!((dec - declination).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_horizontal_to_equatorial() {
+        let time = DateTime::parse_from_rfc3339("2024-05-01T22:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let engine = Engine::new(time, 0.9, 0.1);
+        for (ascension, declination) in [(1.0, 0.5), (4.0, -0.2), (6.0, 1.5)] {
+            let (alt, az) = engine.get_equatorial_position(ascension, declination);
+            let (ra, dec) = engine.horizontal_to_equatorial(alt, az);
+            assert!((ra - ascension).abs() < 1e-9);
+            assert!((dec - declination).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_get_horizon_hours() {
+        let time = DateTime::parse_from_rfc3339("2024-05-01T22:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let engine = Engine::new(time, 0.9, 0.1);
+        let hours = engine.get_horizon_hours();
+        assert_eq!(hours.len(), 24);
+        for &(hour, az) in &hours {
+            let ra = engine.horizontal_to_equatorial(0.0, az).0 / PI * 12.0;
+            let error = (ra - hour as f64 + 12.0).rem_euclid(24.0) - 12.0;
+            assert!(error.abs() < 0.01);
+        }
+        // a sidereal hour later each hour takes the place of the one before
+        let later = Engine::new(time + Duration::seconds((SIDEREAL_DAY / 24.0) as i64), 0.9, 0.1);
+        let later_hours = later.get_horizon_hours();
+        let position = |hours: &[(u32, f64)], hour: u32| hours.iter().find(|tick| tick.0 == hour).unwrap().1;
+        for &(hour, az) in &hours {
+            let moved = position(&later_hours, (hour + 1) % 24);
+            let error = (moved - az + PI).rem_euclid(2.0 * PI) - PI;
+            assert!(error.abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_get_sun_equatorial() {
+        // the Sun sits at the ascending node of the ecliptic at the march equinox and 23.4° north in june,
+        // give or take the third of a degree the equinox has precessed since j2000
+        let equinox = DateTime::parse_from_rfc3339("2024-03-20T03:06:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let (ra, dec) = Engine::new(equinox, 0.0, 0.0).get_sun_equatorial();
+        assert!(ra.min(2.0 * PI - ra) < 0.01 && dec.abs() < 0.005);
+        let (ra, dec) = Engine::new(equinox + Duration::days(93), 0.0, 0.0).get_sun_equatorial();
+        assert!((ra - PI / 2.0).abs() < 0.01 && (dec - AXIAL_TILT).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_get_moon_direction() {
+        // meeus' example 47.a for 1992-04-12 0h TD, the longitude moved from the equinox of date to j2000
+        let time = DateTime::parse_from_rfc3339("1992-04-11T23:58:59Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let engine = Engine::new(time, 0.0, 0.0);
+        let to_moon = engine.get_moon_direction();
+        let longitude = to_moon.y.atan2(to_moon.x) / PI * 180.0;
+        let latitude = to_moon.z.asin() / PI * 180.0;
+        assert!((longitude - 133.2705).abs() < 0.1);
+        assert!((latitude + 3.2291).abs() < 0.1);
+        assert!((engine.get_moon_distance() - 0.3684097).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_get_object_position() {
+        let time = DateTime::parse_from_rfc3339("2024-04-08T18:42:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let engine = Engine::new(time, 0.9, 0.1);
+        let planets = [Planet {
+            name: "Mars".to_string(),
+            semimajor: 227.9,
+            sidereal: 686.98,
+            phase: 0.0,
+            inclination: 0.0,
+            incl_phase: 0.0,
+            abs_magnitude: -1.5,
+            texture: None,
+        }];
+        let (alt, az, _) = engine.get_planet_position(&planets[0]);
+        assert_eq!(
+            engine.get_object_position(SkyObject::Planet(0), &[], &planets),
+            Some((alt, az))
+        );
+        assert_eq!(
+            engine.get_object_position(SkyObject::Sun, &[], &[]),
+            Some(engine.get_sun_position())
+        );
+        let (alt, az, _, _) = engine.get_moon_position();
+        assert_eq!(engine.get_object_position(SkyObject::Moon, &[], &[]), Some((alt, az)));
+        // and the other way round, equatorial back to the same horizontal place
+        for object in [SkyObject::Planet(0), SkyObject::Sun, SkyObject::Moon] {
+            let (ascension, declination) = engine.get_object_equatorial(object, &[], &planets).unwrap();
+            let (alt, az) = engine.get_object_position(object, &[], &planets).unwrap();
+            let (alt2, az2) = engine.get_equatorial_position(ascension, declination);
+            assert!((alt - alt2).abs() < 1e-9 && (az - az2).abs() < 1e-9);
+        }
+        // nothing to look up for the layers that have no catalog yet
+        for object in [SkyObject::DeepSky(0), SkyObject::Satellite(0)] {
+            assert_eq!(engine.get_object_position(object, &[], &planets), None);
+            assert_eq!(engine.get_hour_angle(object, &[], &planets), None);
+        }
+        assert_eq!(SkyObject::Moon.kind(), ObjectKind::Moon);
+        assert_eq!(SkyObject::Sun.kind(), ObjectKind::Sun);
+        assert_eq!(SkyObject::DeepSky(3).kind(), ObjectKind::DeepSky);
+        assert_eq!(SkyObject::Satellite(3).kind(), ObjectKind::Satellite);
+    }
+
+    #[test]
+    fn test_get_angular_radii() {
+        // the Moon covered the Sun in april 2024 but left a ring in october 2023, both at greatest eclipse
+        let total = DateTime::parse_from_rfc3339("2024-04-08T18:17:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let engine = Engine::new(total, 25.3 / 180.0 * PI, -104.1 / 180.0 * PI);
+        assert!(engine.get_moon_angular_radius() > engine.get_sun_angular_radius());
+        let annular = DateTime::parse_from_rfc3339("2023-10-14T17:59:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let engine = Engine::new(annular, 11.4 / 180.0 * PI, -83.1 / 180.0 * PI);
+        assert!(engine.get_moon_angular_radius() < engine.get_sun_angular_radius());
+        assert!((engine.get_sun_angular_radius() / PI * 180.0 - 0.2667).abs() < 0.001);
+        // tiny from Mars
+        let mars = Engine::on_world(&MARS, total, 0.0, 0.0);
+        assert!(mars.get_moon_angular_radius() < MOON_ANGULAR_RADIUS / 50.0);
+    }
+
+    #[test]
+    fn test_get_ecliptic_points() {
+        let time = DateTime::parse_from_rfc3339("2024-03-20T03:06:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let engine = Engine::new(time, 0.9, 0.1);
+        let points = engine.get_ecliptic_points(12);
+        assert_eq!(points.len(), 12);
+        let sun = engine.get_sun_position();
+        assert!(angular_distance(points[0], sun) < 1e-6);
+        for (i, &point) in points.iter().enumerate() {
+            let next = points[(i + 1) % points.len()];
+            assert!((angular_distance(point, next) - PI / 6.0).abs() < 1e-9);
+        }
+        assert!(angular_distance(engine.get_ecliptic_points(3)[1], points[4]) < 1e-9);
+    }
+
+    #[test]
+    fn test_get_lunar_nodes() {
+        let time = DateTime::parse_from_rfc3339("2024-04-08T18:42:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let engine = Engine::new(time, 0.9, 0.1);
+        // the Moon heads north just past the ascending node
+        let nodal_phase = get_phase(engine.ts, INITIAL_NODAL_PHASE, NODAL_PERIOD);
+        let past_node = rot_z(0.1, engine.get_ascending_node());
+        assert!(get_inclined_direction(past_node, MOON_INCLINATION, nodal_phase).z > 0.0);
+        // the nodes sit opposite each other on the ecliptic
+        let [(alt0, az0), (alt1, az1)] = engine.get_lunar_nodes().unwrap();
+        assert!((alt0 + alt1).abs() < 1e-9);
+        assert!((az0 - az1).abs() > 1.0);
+        assert!(Engine::on_world(&MARS, time, 0.9, 0.1).get_lunar_nodes().is_none());
+        // the eclipses of april and september 2024 fell in eclipse seasons, the june solstice didn't
+        assert!(engine.is_eclipse_season());
+        for (date, season) in [("2024-09-18T02:44:00Z", true), ("2024-06-21T00:00:00Z", false)] {
+            let time = DateTime::parse_from_rfc3339(date).unwrap().with_timezone(&Utc);
+            assert_eq!(Engine::new(time, 0.9, 0.1).is_eclipse_season(), season);
+        }
+    }
+
+    #[test]
+    fn test_get_hour_angle() {
+        // the Sun crosses the meridian of greenwich about twelve minutes after noon early in march
+        let hour_angle = |time: &str| {
+            let time = DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc);
+            Engine::new(time, 0.9, 0.0)
+                .get_hour_angle(SkyObject::Sun, &[], &[])
+                .unwrap()
+        };
+        assert!(hour_angle("2024-03-01T12:05:00Z") < 0.0);
+        assert!(hour_angle("2024-03-01T12:20:00Z") > 0.0);
+        assert!(hour_angle("2024-03-01T12:12:00Z").abs() < 0.5 / 180.0 * PI);
+        assert!((hour_angle("2024-03-01T00:12:00Z").abs() - PI).abs() < 0.5 / 180.0 * PI);
+    }
+
+    #[test]
+    fn test_get_parallactic_angle() {
+        let time = DateTime::parse_from_rfc3339("2024-03-01T21:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let latitude = 0.9;
+        let engine = Engine::new(time, latitude, 0.1);
+        let sidereal = engine.get_sidereal_time() / 12.0 * PI;
+        for (ascension, declination) in [(1.0, 0.3), (4.0, -0.2), (2.5, 1.2)] {
+            let (alt, az) = engine.get_equatorial_position(ascension, declination);
+            // the textbook formula from the hour angle
+            let hour_angle = sidereal - ascension;
+            let expected = hour_angle
+                .sin()
+                .atan2(latitude.tan() * declination.cos() - declination.sin() * hour_angle.cos());
+            let difference = (engine.get_parallactic_angle(alt, az) - expected + PI).rem_euclid(2.0 * PI) - PI;
+            assert!(difference.abs() < 1e-6);
+        }
+        // nothing turns on the meridian south of the zenith
+        assert!(engine.get_parallactic_angle(0.5, PI).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_get_moon_libration() {
+        let start = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let librations: Vec<(f64, f64)> = (0..28)
+            .map(|day| Engine::new(start + Duration::days(day), 0.0, 0.0).get_moon_libration())
+            .collect();
+        let extent = |values: Vec<f64>| {
+            let max = values.iter().cloned().fold(f64::MIN, f64::max);
+            let min = values.iter().cloned().fold(f64::MAX, f64::min);
+            (min / PI * 180.0, max / PI * 180.0)
+        };
+        // rocking by up to 6.3° in longitude and 6.7° in latitude over a month
+        let (min, max) = extent(librations.iter().map(|l| l.0).collect());
+        assert!(min < -5.5 && max > 5.5 && min >= -6.3 && max <= 6.3);
+        let (min, max) = extent(librations.iter().map(|l| l.1).collect());
+        assert!(min < -6.0 && max > 6.0 && min >= -6.7 && max <= 6.7);
+    }
+
+    #[test]
+    fn test_get_solar_time() {
+        let time = DateTime::parse_from_rfc3339("2024-11-03T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let latitude = 51.477 / 180.0 * PI;
+        let engine = Engine::new(time, latitude, 0.0);
+        let solar_time = engine.get_solar_time();
+        // the equation of time stays within a quarter of an hour
+        assert!((solar_time - 9.0).abs() < 0.3);
+        let noon = time + Duration::seconds(((12.0 - solar_time) * 3600.0) as i64);
+        let (_, az) = Engine::new(noon, latitude, 0.0).get_sun_position();
+        assert!((az - PI).abs() < 0.01);
+        // an hour for every fifteen degrees of longitude
+        let east = Engine::new(time, latitude, PI / 2.0).get_solar_time();
+        assert!((east - solar_time - 6.0).abs() < 0.01);
+    }
+
     #[test]
     fn test_get_sun_direction() {
         assert!((get_sun_direction(0.0) + X_UNIT).length() < 1e-15);
@@ -283,6 +1742,140 @@ mod tests {
         assert!((get_object_direction(PI / 2.0) - Y_UNIT).length() < 1e-15);
     }
 
+    #[test]
+    fn test_get_orbital_velocity() {
+        let velocity = get_orbital_velocity(0.0, SEMIMAJOR, SIDEREAL_YEAR);
+        assert!((velocity.normalize() - Y_UNIT).length() < 1e-15);
+        assert!((velocity.length() - 9.93e-5).abs() < 1e-7);
+    }
+
+    #[test]
+    fn test_apply_aberration() {
+        // 2024-03-20, close to the equinox: stars in the Sun's direction are at 90° from the apex, maximum shift
+        let time = DateTime::parse_from_rfc3339("2024-03-20T03:06:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let engine = Engine::new(time, 0.0, 0.0);
+        let phase = get_phase(engine.ts, INITIAL_PHASE, SIDEREAL_YEAR);
+        let to_sun = get_sun_direction(phase);
+        let apparent = apply_aberration(to_sun, get_orbital_velocity(phase, SEMIMAJOR, SIDEREAL_YEAR));
+        let shift = to_sun.dot(apparent).acos() / PI * 180.0 * 3600.0;
+        assert!((shift - 20.5).abs() < 0.2);
+
+        let mut engine = engine;
+        let star = Star::new(0, 0.0, 0.0, 0.0);
+        let (alt0, az0) = engine.get_star_position(&star);
+        engine.aberration = true;
+        let (alt1, az1) = engine.get_star_position(&star);
+        let shift =
+            (alt0.sin() * alt1.sin() + alt0.cos() * alt1.cos() * (az1 - az0).cos()).acos() / PI * 180.0 * 3600.0;
+        assert!((shift - 20.5).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_get_star_equatorial() {
+        // at the catalog epoch the star is where the catalog puts it
+        let time = DateTime::parse_from_rfc3339("2000-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let engine = Engine::new(time, 0.9, 0.1);
+        let star = Star {
+            name: Some("Sirius".to_string()),
+            ..Star::new(32349, 1.7677, -0.2917, -1.46)
+        };
+        assert!((engine.get_julian_date() - star.epoch).abs() < 1e-9);
+        assert_eq!(engine.get_star_equatorial(&star), (star.ascension, star.declination));
+        assert_eq!(
+            engine.get_star_position(&star),
+            engine.get_equatorial_position(star.ascension, star.declination)
+        );
+
+        // the same star given for another epoch lands on the same place
+        let (ascension, declination) = precess(star.ascension, star.declination, J2000, 2462088.69);
+        let later = Star {
+            epoch: 2462088.69,
+            ..Star::new(32349, ascension, declination, -1.46)
+        };
+        let (ascension, declination) = engine.get_star_equatorial(&later);
+        assert!((ascension - star.ascension).abs() < 1e-9 && (declination - star.declination).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_precess() {
+        // meeus' example 21.b, theta persei from j2000 to 2028 november 13.19
+        let (ascension, declination) = precess(41.054063 / 180.0 * PI, 49.227750 / 180.0 * PI, J2000, 2462088.69);
+        assert!((ascension / PI * 180.0 - 41.547214).abs() < 1e-5);
+        assert!((declination / PI * 180.0 - 49.348483).abs() < 1e-5);
+        let (back, _) = precess(ascension, declination, 2462088.69, J2000);
+        assert!((back / PI * 180.0 - 41.054063).abs() < 1e-5);
+        assert_eq!(precess(1.0, 0.5, J2000, J2000), (1.0, 0.5));
+    }
+
+    #[test]
+    fn test_get_julian_date() {
+        assert!((get_julian_date(0.0) - 2440587.5).abs() < 1e-9);
+        assert!((get_julian_date(946728000.0) - 2451545.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_get_star_magnitude() {
+        let mut star = Star::new(14576, 0.0, 0.0, 2.1);
+        let time = DateTime::from_timestamp(0, 0).unwrap();
+        assert_eq!(Engine::new(time, 0.0, 0.0).get_star_magnitude(&star), 2.1);
+
+        let epoch = get_julian_date(0.0);
+        star.variability = Some(Variability {
+            kind: VariableKind::Eclipsing,
+            max_magnitude: 2.09,
+            min_magnitude: 3.39,
+            period: 2.8673043,
+            epoch,
+        });
+        let at = |days: f64| Engine::new(time + Duration::seconds((days * 86400.0) as i64), 0.0, 0.0);
+        assert!((at(0.0).get_star_magnitude(&star) - 3.39).abs() < 1e-9);
+        assert!((at(2.8673043 / 2.0).get_star_magnitude(&star) - 2.09).abs() < 1e-9);
+        assert!((at(2.8673043 * 10.0).get_star_magnitude(&star) - 3.39).abs() < 1e-4);
+
+        star.variability = Some(Variability {
+            kind: VariableKind::Pulsating,
+            max_magnitude: 3.48,
+            min_magnitude: 4.37,
+            period: 5.366249,
+            epoch,
+        });
+        assert!((at(0.0).get_star_magnitude(&star) - 3.48).abs() < 1e-9);
+        assert!((at(5.366249 / 2.0).get_star_magnitude(&star) - 4.37).abs() < 1e-4);
+        assert!((at(5.366249 / 4.0).get_star_magnitude(&star) - (3.48 + 4.37) / 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_get_companion_position() {
+        let engine = Engine::new(DateTime::from_timestamp(0, 0).unwrap(), 0.5, 0.0);
+        let mut star = Star {
+            double: true,
+            ..Star::new(0, 1.0, 0.5, 0.0)
+        };
+        assert!(engine.get_companion_position(&star).is_none());
+
+        star.companion = Some(Companion {
+            separation: 0.01,
+            position_angle: 0.0,
+            magnitude: 0.0,
+        });
+        let (alt, az) = engine.get_companion_position(&star).unwrap();
+        let (expected_alt, expected_az) = engine.get_equatorial_position(1.0, 0.51);
+        assert!((alt - expected_alt).abs() < 1e-12 && (az - expected_az).abs() < 1e-12);
+
+        star.companion = Some(Companion {
+            separation: 0.01,
+            position_angle: PI / 2.0,
+            magnitude: 0.0,
+        });
+        let (alt, az) = engine.get_companion_position(&star).unwrap();
+        let (expected_alt, expected_az) = engine.get_equatorial_position(1.0 + 0.01 / 0.5f64.cos(), 0.5);
+        assert!((alt - expected_alt).abs() < 1e-12 && (az - expected_az).abs() < 1e-12);
+    }
+
     #[test]
     fn test_get_inclined_direction() {
         assert!((get_inclined_direction(X_UNIT, PI / 2.0, 0.0) - Z_UNIT).length() < 1e-15);
@@ -292,6 +1885,107 @@ mod tests {
         assert!((get_inclined_direction(Z_UNIT, PI / 2.0, PI / 2.0) - Y_UNIT).length() < 1e-15);
     }
 
+    #[test]
+    fn test_get_transit_separation() {
+        let to_sun = X_UNIT * SEMIMAJOR;
+        assert_eq!(get_transit_separation(to_sun, X_UNIT * 40.0), Some(0.0));
+        let separation = get_transit_separation(to_sun, vec3(40.0, 0.1, 0.0)).unwrap();
+        assert!((separation - (0.1f64 / 40.0).atan()).abs() < 1e-12);
+        // near-conjunction passing north of the disk
+        assert_eq!(get_transit_separation(to_sun, vec3(40.0, 0.0, 0.2)), None);
+        // superior conjunction, behind the Sun
+        assert_eq!(get_transit_separation(to_sun, X_UNIT * 200.0), None);
+    }
+
+    #[test]
+    fn test_find_greatest_elongations() {
+        let venus = Planet {
+            name: "Venus".to_string(),
+            semimajor: 108.210,
+            sidereal: 19414166.4,
+            phase: 4.7,
+            inclination: 3.395 / 180.0 * PI,
+            incl_phase: 2.96,
+            abs_magnitude: -4.47,
+            texture: None,
+        };
+        let start = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let elongations = find_greatest_elongations(&EARTH, &Calibration::default(), &venus, start, 600);
+        // one eastern and one western elongation per 584 day synodic period
+        assert_eq!(elongations.len(), 2);
+        assert!(elongations[0].angle * elongations[1].angle < 0.0);
+        for elongation in elongations {
+            // circular orbits give asin(a_venus / a_earth) = 46.3°
+            assert!((elongation.angle.abs() / PI * 180.0 - 46.3).abs() < 1.5);
+            let engine = Engine::new(elongation.time, 0.0, 0.0);
+            for offset in [-1, 1] {
+                let other = Engine::new(elongation.time + Duration::hours(offset), 0.0, 0.0);
+                assert!(other.get_elongation(&venus).abs() <= engine.get_elongation(&venus).abs() + 1e-9);
+            }
+        }
+        // from Mars the orbit of Venus is smaller, asin(a_venus / a_mars) = 28.3° give or take its eccentricity
+        let elongations = find_greatest_elongations(&MARS, &Calibration::default(), &venus, start, 600);
+        assert!(!elongations.is_empty());
+        for elongation in elongations {
+            assert!((elongation.angle.abs() / PI * 180.0 - 28.3).abs() < 4.0);
+        }
+    }
+
+    #[test]
+    fn test_get_planet_distance() {
+        let jupiter = Planet {
+            name: "Jupiter".to_string(),
+            semimajor: 778.479,
+            sidereal: 374335689.6,
+            phase: 3.6,
+            inclination: 1.304 / 180.0 * PI,
+            incl_phase: 3.27,
+            abs_magnitude: -9.40,
+            texture: None,
+        };
+        let start = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let distances: Vec<f64> = (0..400)
+            .step_by(5)
+            .map(|day| {
+                Engine::new(start + Duration::days(day), 0.0, 0.0)
+                    .get_planet_position(&jupiter)
+                    .2
+            })
+            .collect();
+        // 5.2 au from the Sun, one au closer at opposition and one further at conjunction
+        let nearest = distances.iter().cloned().fold(f64::MAX, f64::min);
+        let furthest = distances.iter().cloned().fold(f64::MIN, f64::max);
+        assert!((nearest - 4.2).abs() < 0.05);
+        assert!((furthest - 6.2).abs() < 0.05);
+        assert!((LIGHT_TIME_PER_AU - 499.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_get_planet_magnitude() {
+        let venus = Planet {
+            name: "Venus".to_string(),
+            semimajor: 108.210,
+            sidereal: 19414166.4,
+            phase: 4.7,
+            inclination: 3.395 / 180.0 * PI,
+            incl_phase: 2.96,
+            abs_magnitude: -4.47,
+            texture: None,
+        };
+        let start = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        // venus stays between about -4.9 and -3.8 over its synodic period
+        for day in (0..584).step_by(8) {
+            let magnitude = Engine::new(start + Duration::days(day), 0.0, 0.0).get_planet_magnitude(&venus);
+            assert!((-5.0..-3.5).contains(&magnitude));
+        }
+    }
+
     #[test]
     fn test_get_altitude() {
         assert!((get_altitude(X_UNIT, X_UNIT) - PI / 2.0).abs() < 1e-15);
@@ -352,4 +2046,421 @@ mod tests {
                 < 1e-15
         );
     }
+
+    #[test]
+    fn test_refraction() {
+        let minutes = |angle: f64| angle / PI * 180.0 * 60.0;
+        // about 29' at the horizon, a minute at 45 degrees and nothing at the zenith
+        assert!((minutes(get_refraction(0.0)) - 29.0).abs() < 0.5);
+        assert!((minutes(get_refraction(PI / 4.0)) - 1.0).abs() < 0.05);
+        assert!(minutes(get_refraction(PI / 2.0)).abs() < 0.01);
+        for alt in [-0.1, -0.01, 0.0, 0.02, 0.3, 1.2] {
+            assert!((unrefract(refract(alt)) - alt).abs() < 1e-9);
+        }
+        // low objects bunch up, the Sun on the horizon is squashed by a sixth or so
+        let radius = 0.25 / 180.0 * PI;
+        let flattening = (refract(radius) - refract(-radius)) / (2.0 * radius);
+        assert!(flattening > 0.75 && flattening < 0.9);
+
+        let time = DateTime::parse_from_rfc3339("2024-03-20T18:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut engine = Engine::new(time, 0.9, 0.1);
+        let (alt, az) = engine.get_sun_position();
+        engine.refraction = true;
+        let (apparent, apparent_az) = engine.get_sun_position();
+        assert!((apparent - refract(alt)).abs() < 1e-12 && apparent_az == az);
+        let equatorial = engine.horizontal_to_equatorial(apparent, az);
+        engine.refraction = false;
+        let expected = engine.horizontal_to_equatorial(alt, az);
+        assert!((equatorial.0 - expected.0).abs() < 1e-9 && (equatorial.1 - expected.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_get_ecliptic_offset() {
+        let time = DateTime::parse_from_rfc3339("2024-03-20T19:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let engine = Engine::new(time, 0.9, 0.1);
+        let sun = engine.get_sun_position();
+        assert!(angular_distance(engine.get_ecliptic_offset(0.0, 0.0), sun) < 1e-6);
+        let east = engine.get_ecliptic_offset(PI / 3.0, 0.0);
+        assert!((angular_distance(east, sun) - PI / 3.0).abs() < 1e-9);
+        // the pole of the ecliptic is a right angle from all of it
+        let pole = engine.get_ecliptic_offset(1.0, PI / 2.0);
+        assert!((angular_distance(pole, sun) - PI / 2.0).abs() < 1e-9);
+        assert!((angular_distance(pole, east) - PI / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_find_sun_longitude() {
+        // the june solstice of 2024 was at 20:51 UT on the 20th, a year of circles and no nutation lands within hours
+        let start = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let solstice = find_sun_longitude(&EARTH, &Calibration::default(), start, PI / 2.0);
+        let expected = DateTime::parse_from_rfc3339("2024-06-20T20:51:00Z").unwrap();
+        assert!((solstice - expected.with_timezone(&Utc)).num_hours().abs() < 12);
+        let longitude = Engine::new(solstice, 0.0, 0.0).get_sun_longitude();
+        assert!((longitude - PI / 2.0).abs() < 1e-6);
+        // the march equinox backwards from a little after it
+        let equinox = find_sun_longitude(&EARTH, &Calibration::default(), start + Duration::days(90), 0.0);
+        let expected = DateTime::parse_from_rfc3339("2024-03-20T03:06:00Z").unwrap();
+        assert!((equinox - expected.with_timezone(&Utc)).num_hours().abs() < 12);
+        // a year on Mars
+        let mars = find_sun_longitude(&MARS, &Calibration::default(), start, PI);
+        assert!((Engine::on_world(&MARS, mars, 0.0, 0.0).get_sun_longitude() - PI).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_calibration() {
+        let time = DateTime::parse_from_rfc3339("2024-03-20T03:06:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let default = Engine::calibrated(&EARTH, time, 0.9, 0.1, Calibration::default());
+        let engine = Engine::new(time, 0.9, 0.1);
+        assert_eq!(default.get_sun_position(), engine.get_sun_position());
+        assert_eq!(default.get_moon_position(), engine.get_moon_position());
+        // turning the Earth a little further moves the sky along, turning the Moon moves only the Moon
+        let turned = Calibration {
+            daily_phase: INITIAL_DAILY_PHASE + 0.1,
+            ..Calibration::default()
+        };
+        let turned_engine = Engine::calibrated(&EARTH, time, 0.9, 0.1, turned);
+        assert!((turned_engine.get_sidereal_time() - engine.get_sidereal_time() - 0.1 / PI * 12.0).abs() < 1e-9);
+        // the searches follow the calibration they are handed, other worlds keep their own rotation
+        let transit = |calibration: Calibration| get_sun_passage(&calibration, time, 0.9, 0.1).unwrap().transit;
+        assert_ne!(transit(turned), transit(Calibration::default()));
+        let mars = Engine::calibrated(&MARS, time, 0.9, 0.1, turned);
+        assert_eq!(
+            mars.get_sidereal_time(),
+            Engine::on_world(&MARS, time, 0.9, 0.1).get_sidereal_time()
+        );
+        let moved = Calibration {
+            moon_phase: INITIAL_MOON_PHASE + 0.1,
+            ..Calibration::default()
+        };
+        let moved = Engine::calibrated(&EARTH, time, 0.9, 0.1, moved);
+        assert_eq!(moved.get_sun_position(), engine.get_sun_position());
+        assert_ne!(moved.get_moon_position(), engine.get_moon_position());
+    }
+
+    #[test]
+    fn test_is_circumpolar() {
+        let degrees = |degrees: f64| degrees / 180.0 * PI;
+        // polaris and the plough from greenwich, not the southern cross or anything from the equator
+        assert!(is_circumpolar(degrees(89.3), degrees(51.5)));
+        assert!(is_circumpolar(degrees(55.0), degrees(51.5)));
+        assert!(!is_circumpolar(degrees(-60.0), degrees(51.5)));
+        assert!(!is_circumpolar(degrees(89.3), 0.0));
+        // the other way round down south, and everything in its hemisphere at the pole
+        assert!(is_circumpolar(degrees(-60.0), degrees(-40.0)));
+        assert!(!is_circumpolar(degrees(60.0), degrees(-40.0)));
+        assert!(is_circumpolar(degrees(1.0), degrees(90.0)));
+    }
+
+    #[test]
+    fn test_get_airmass() {
+        assert!((get_airmass(PI / 2.0) - 1.0).abs() < 1e-3);
+        assert!((get_airmass(PI / 6.0) - 2.0).abs() < 0.01);
+        assert!((get_airmass(0.0) - 38.0).abs() < 0.1);
+        assert_eq!(get_airmass(-0.1), get_airmass(0.0));
+    }
+
+    #[test]
+    fn test_find_crossings() {
+        let start = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let hours = |time: DateTime<Utc>| (time - start).num_seconds() as f64 / 3600.0;
+        let crossings = find_crossings(start, start + Duration::days(1), 0.5, |time| {
+            (hours(time) / 24.0 * 2.0 * PI).sin()
+        });
+        assert_eq!(crossings.len(), 2);
+        assert!(crossings[0].rising && !crossings[1].rising);
+        assert!((hours(crossings[0].time) - 2.0).abs() < 1e-3);
+        assert!((hours(crossings[1].time) - 10.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_get_day_length() {
+        let hours = |date: &str, latitude: f64| {
+            let start = DateTime::parse_from_rfc3339(date).unwrap().with_timezone(&Utc);
+            get_day_length(&Calibration::default(), start, latitude / 180.0 * PI, 0.0).num_seconds() as f64 / 3600.0
+        };
+        // greenwich, about 16h38m at the june solstice and 7h50m at the december one
+        assert!((hours("2024-06-21T00:00:00Z", 51.477) - 16.63).abs() < 0.2);
+        assert!((hours("2024-12-21T00:00:00Z", 51.477) - 7.83).abs() < 0.2);
+        // a little over twelve hours at the equator all year round
+        assert!((hours("2024-03-20T00:00:00Z", 0.0) - 12.1).abs() < 0.1);
+        // polar day and polar night
+        assert_eq!(hours("2024-06-21T00:00:00Z", 80.0), 24.0);
+        assert_eq!(hours("2024-12-21T00:00:00Z", 80.0), 0.0);
+    }
+
+    #[test]
+    fn test_find_passage() {
+        let start = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let hours = |time: DateTime<Utc>| (time - start).num_seconds() as f64 / 3600.0;
+        let altitude = |time| (hours(time) / 24.0 * 2.0 * PI).sin();
+        let passage = find_passage(start, 0.5, altitude).unwrap();
+        assert!((hours(passage.rise.unwrap()) - 2.0).abs() < 1e-3);
+        assert!((hours(passage.transit.unwrap()) - 6.0).abs() < 1e-3);
+        assert!((hours(passage.set.unwrap()) - 10.0).abs() < 1e-3);
+        // circumpolar and never rising
+        let passage = find_passage(start, -2.0, altitude).unwrap();
+        assert!(passage.rise.is_none() && passage.set.is_none());
+        assert!(passage.transit.is_some());
+        assert!(find_passage(start, 2.0, altitude).is_none());
+    }
+
+    #[test]
+    fn test_get_sun_azimuths() {
+        // the sunrise swings from the southeast in winter to the northeast in summer, mirrored by the sunset
+        let latitude = 51.477 / 180.0 * PI;
+        let azimuths = |date: &str| {
+            let start = DateTime::parse_from_rfc3339(date).unwrap().with_timezone(&Utc);
+            let (rise, set) = get_sun_azimuths(&Calibration::default(), start, latitude, 0.0);
+            (rise.unwrap() / PI * 180.0, set.unwrap() / PI * 180.0)
+        };
+        let (rise, set) = azimuths("2024-12-21T00:00:00Z");
+        assert!((rise - 128.0).abs() < 1.0 && (set - 232.0).abs() < 1.0);
+        let (rise, set) = azimuths("2024-06-21T00:00:00Z");
+        assert!((rise - 49.0).abs() < 1.0 && (set - 311.0).abs() < 1.0);
+        let (rise, set) = azimuths("2024-03-20T00:00:00Z");
+        assert!((rise - 90.0).abs() < 1.5 && (set - 270.0).abs() < 1.5);
+        // the midnight sun neither rises nor sets
+        let start = DateTime::parse_from_rfc3339("2024-06-21T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            get_sun_azimuths(&Calibration::default(), start, 71.17 / 180.0 * PI, 0.0),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn test_find_next_crossing() {
+        let start = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let hours = |time: DateTime<Utc>| (time - start).num_seconds() as f64 / 3600.0;
+        let altitude = |time| (hours(time) / 24.0 * 2.0 * PI).sin();
+        let rise = find_next_crossing(start, 0.5, true, altitude).unwrap();
+        assert!((hours(rise) - 2.0).abs() < 1e-3);
+        let set = find_next_crossing(start, 0.5, false, altitude).unwrap();
+        assert!((hours(set) - 10.0).abs() < 1e-3);
+        // past the rise, the next one is a day later
+        let rise = find_next_crossing(start + Duration::hours(3), 0.5, true, altitude).unwrap();
+        assert!((hours(rise) - 26.0).abs() < 1e-3);
+        assert!(find_next_crossing(start, 2.0, true, altitude).is_none());
+        // the midnight sun at the north cape in june never sets
+        let june = DateTime::parse_from_rfc3339("2024-06-21T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert!(find_sun_crossing(&Calibration::default(), june, 71.17 / 180.0 * PI, 0.0, false).is_none());
+    }
+
+    #[test]
+    fn test_find_dark_window() {
+        // starting at noon, the Sun lowest at midnight and fully dark from about 20:06 to 03:54
+        let start = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let hours = |time: DateTime<Utc>| (time - start).num_seconds() as f64 / 3600.0;
+        let sun = |time| 0.6 * (hours(time) / 24.0 * 2.0 * PI).cos();
+        let culminating = |at: f64| move |time| 0.5 * ((hours(time) - at) / 24.0 * 2.0 * PI).cos();
+        let dusk = (ASTRONOMICAL_TWILIGHT / 0.6).acos() / (2.0 * PI) * 24.0;
+
+        // up all night, best at the culmination
+        let window = find_dark_window(start, 0.0, sun, culminating(14.0)).unwrap();
+        assert!((hours(window.start) - dusk).abs() < 1e-3);
+        assert!((hours(window.end) - (24.0 - dusk)).abs() < 1e-3);
+        assert!((hours(window.best) - 14.0).abs() < 1e-3);
+        assert!(window.all_night);
+        // setting at midnight, best right at dusk while it is still high
+        let window = find_dark_window(start, 0.0, sun, culminating(6.0)).unwrap();
+        assert!((hours(window.end) - 12.0).abs() < 1e-3);
+        assert!((hours(window.best) - dusk).abs() < 1e-3);
+        assert!(!window.all_night);
+        // only up by day
+        assert!(find_dark_window(start, 0.0, sun, culminating(0.0)).is_none());
+        // no dark sky at all
+        assert!(find_dark_window(start, 0.0, |time| sun(time) + 0.5, culminating(12.0)).is_none());
+    }
+
+    #[test]
+    fn test_get_moon_passage() {
+        let start = DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let latitude = 51.477 / 180.0 * PI;
+        let altitude = |time| Engine::new(time, latitude, 0.0).get_moon_position().0;
+        let mut rises = Vec::new();
+        for day in 0..30 {
+            let Some(passage) = get_moon_passage(&Calibration::default(), start + Duration::days(day), latitude, 0.0)
+            else {
+                continue;
+            };
+            if let Some(rise) = passage.rise {
+                assert!((altitude(rise) - MOONRISE_ALTITUDE).abs() < 1e-3);
+                rises.push(rise);
+            }
+            if let Some(transit) = passage.transit {
+                assert!(altitude(transit) > altitude(transit - Duration::minutes(30)));
+                assert!(altitude(transit) > altitude(transit + Duration::minutes(30)));
+            }
+        }
+        // a day a month goes without a moonrise, the rest rise later by about fifty minutes
+        assert!(rises.len() >= 28);
+        let delays: Vec<f64> = rises
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0] - Duration::days(1)).num_minutes() as f64)
+            .collect();
+        let average = delays.iter().sum::<f64>() / delays.len() as f64;
+        assert!((average - 50.0).abs() < 10.0);
+        // the midnight sun has no sunrise or sunset
+        let midsummer = DateTime::parse_from_rfc3339("2024-06-21T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let passage = get_sun_passage(&Calibration::default(), midsummer, 80.0 / 180.0 * PI, 0.0).unwrap();
+        assert!(passage.rise.is_none() && passage.set.is_none() && passage.transit.is_some());
+        assert!(get_sun_passage(&Calibration::default(), midsummer, -80.0 / 180.0 * PI, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_get_planet_passage() {
+        let jupiter = Planet {
+            name: "Jupiter".to_string(),
+            semimajor: 778.479,
+            sidereal: 374335689.6,
+            phase: 3.6,
+            inclination: 1.304 / 180.0 * PI,
+            incl_phase: 3.27,
+            abs_magnitude: -9.40,
+            texture: None,
+        };
+        let start = DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let latitude = 51.477 / 180.0 * PI;
+        let altitude = |time| Engine::new(time, latitude, 0.0).get_planet_position(&jupiter).0;
+        let passage = get_planet_passage(&Calibration::default(), start, latitude, 0.0, &jupiter).unwrap();
+        for time in [passage.rise.unwrap(), passage.set.unwrap()] {
+            assert!((altitude(time) - PLANETRISE_ALTITUDE).abs() < 1e-3);
+        }
+        // the culmination is as high as the declination allows
+        let transit = passage.transit.unwrap();
+        let (_, declination) = Engine::new(transit, latitude, 0.0).get_planet_equatorial(&jupiter);
+        assert!((altitude(transit) - (PI / 2.0 - latitude + declination)).abs() < 1e-2);
+        // jupiter is north of the equator, so it never sets near the north pole and never rises near the south one
+        assert!(declination > 0.1);
+        let passage = get_planet_passage(&Calibration::default(), start, 89.0 / 180.0 * PI, 0.0, &jupiter).unwrap();
+        assert!(passage.rise.is_none() && passage.set.is_none());
+        assert!(get_planet_passage(&Calibration::default(), start, -89.0 / 180.0 * PI, 0.0, &jupiter).is_none());
+    }
+
+    #[test]
+    fn test_mars() {
+        let time = DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        // the martian north pole points between deneb and alderamin
+        let engine = Engine::on_world(&MARS, time, PI / 2.0, 0.0);
+        let (alt, _) = engine.get_equatorial_position(317.681 / 180.0 * PI, 52.887 / 180.0 * PI);
+        assert!((alt - PI / 2.0).abs() < 1e-3);
+        // the stars come back after a martian sidereal day
+        let later = time + Duration::milliseconds((MARS.sidereal_day * 1000.0) as i64);
+        let position = |time| Engine::on_world(&MARS, time, 0.3, 1.0).get_equatorial_position(1.0, 0.2);
+        let ((alt0, az0), (alt1, az1)) = (position(time), position(later));
+        assert!((alt0 - alt1).abs() < 1e-3 && (az0 - az1).abs() < 1e-3);
+        let distance = engine.get_sun_distance();
+        assert!((1.38..1.67).contains(&distance));
+        // the Sun stays close to the ecliptic
+        let (_, declination) = engine.get_sun_equatorial();
+        let to_sun = engine.get_sun_direction();
+        assert!(to_sun.z.abs() < 0.035 && declination.abs() <= AXIAL_TILT + 0.035);
+        assert_eq!(engine.world().name, "Mars");
+        assert!(engine.get_earth_position().is_some());
+        assert!(engine.get_solar_eclipse().is_none());
+        assert!(Engine::new(time, 0.0, 0.0).get_earth_position().is_none());
+    }
+
+    #[test]
+    fn test_get_sun_distance() {
+        let perihelion = DateTime::parse_from_rfc3339("2024-01-03T00:39:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let aphelion = DateTime::parse_from_rfc3339("2024-07-05T05:06:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert!((Engine::new(perihelion, 0.0, 0.0).get_sun_distance() - 0.98333).abs() < 1e-4);
+        assert!((Engine::new(aphelion, 0.0, 0.0).get_sun_distance() - 1.01672).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_get_solar_eclipse() {
+        let start = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let (latitude, longitude) = (32.78 / 180.0 * PI, -96.80 / 180.0 * PI);
+        // the total eclipse of 2024-04-08 crossed dallas at 18:42
+        let expected = DateTime::parse_from_rfc3339("2024-04-08T18:42:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let eclipses = find_eclipses(
+            &Calibration::default(),
+            start,
+            start + Duration::days(366),
+            latitude,
+            longitude,
+        );
+        let eclipse = eclipses
+            .iter()
+            .find(|eclipse| (eclipse.time - expected).num_hours().abs() < 2)
+            .unwrap();
+        let separation = Engine::new(eclipse.time, latitude, longitude)
+            .get_solar_eclipse()
+            .unwrap();
+        assert!(separation < SUN_ANGULAR_RADIUS);
+        assert!(Engine::new(start, latitude, longitude).get_solar_eclipse().is_none());
+        // lunar eclipses don't count
+        let lunar = eclipses
+            .iter()
+            .find(|eclipse| eclipse.kind == EclipseKind::PartialLunar)
+            .unwrap();
+        assert!(Engine::new(lunar.time, latitude, longitude)
+            .get_solar_eclipse()
+            .is_none());
+    }
+
+    #[test]
+    fn test_find_eclipses() {
+        let start = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let latitude = 51.477 / 180.0 * PI;
+        let eclipses = find_eclipses(
+            &Calibration::default(),
+            start,
+            start + Duration::days(366),
+            latitude,
+            0.0,
+        );
+        // the partial lunar eclipse of 2024-09-18 02:44 was seen from europe
+        let expected = DateTime::parse_from_rfc3339("2024-09-18T02:44:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert!(eclipses.iter().any(
+            |eclipse| eclipse.kind == EclipseKind::PartialLunar && (eclipse.time - expected).num_hours().abs() < 6
+        ));
+        for eclipse in &eclipses {
+            let (kind, _) = Engine::new(eclipse.time, latitude, 0.0).get_eclipse().unwrap();
+            assert_eq!(kind, eclipse.kind);
+        }
+        // no year has more than seven eclipses anywhere on earth
+        assert!(eclipses.len() <= 7);
+    }
 }
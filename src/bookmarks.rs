@@ -0,0 +1,114 @@
+use std::f64::consts::PI;
+use std::fs::{read_to_string, write};
+use std::io;
+use std::path::Path;
+
+use antikythera::math::Projection;
+use chrono::{DateTime, Utc};
+
+use crate::View;
+
+pub struct Bookmark {
+    pub label: String,
+    pub time: DateTime<Utc>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub view: View,
+}
+
+pub fn load(path: &Path) -> Vec<Bookmark> {
+    // no file yet simply means no bookmarks
+    match read_to_string(path) {
+        Ok(content) => parse(&content),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn save(path: &Path, bookmarks: &[Bookmark]) -> io::Result<()> {
+    let content: String = bookmarks.iter().map(|bookmark| format_line(bookmark) + "\n").collect();
+    write(path, content)
+}
+
+fn parse(content: &str) -> Vec<Bookmark> {
+    let mut bookmarks = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_line(line) {
+            Some(bookmark) => bookmarks.push(bookmark),
+            None => eprintln!("Ignoring malformed bookmark line {}: {}", number + 1, line),
+        }
+    }
+    bookmarks
+}
+
+fn parse_line(line: &str) -> Option<Bookmark> {
+    // label, time, latitude, longitude, zoom, pan x, pan y, flipped separated by tabs
+    let parts: Vec<&str> = line.split('\t').collect();
+    let [label, time, latitude, longitude, zoom, pan_x, pan_y, flipped] = parts[..] else {
+        return None;
+    };
+    Some(Bookmark {
+        label: label.to_string(),
+        time: DateTime::parse_from_rfc3339(time).ok()?.with_timezone(&Utc),
+        latitude: latitude.parse::<f64>().ok()? / 180.0 * PI,
+        longitude: longitude.parse::<f64>().ok()? / 180.0 * PI,
+        view: View {
+            zoom: zoom.parse().ok()?,
+            pan: (pan_x.parse().ok()?, pan_y.parse().ok()?),
+            flipped: flipped.parse().ok()?,
+            projection: Projection::Stereographic,
+        },
+    })
+}
+
+fn format_line(bookmark: &Bookmark) -> String {
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        bookmark.label.replace(['\t', '\n'], " "),
+        bookmark.time.to_rfc3339(),
+        bookmark.latitude / PI * 180.0,
+        bookmark.longitude / PI * 180.0,
+        bookmark.view.zoom,
+        bookmark.view.pan.0,
+        bookmark.view.pan.1,
+        bookmark.view.flipped
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let bookmark = Bookmark {
+            label: "Eclipse\tover Greenwich".to_string(),
+            time: DateTime::parse_from_rfc3339("2026-08-12T17:46:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            latitude: 0.9,
+            longitude: 0.1,
+            view: View {
+                zoom: 2.5,
+                pan: (0.25, -0.5),
+                flipped: true,
+                projection: Projection::Stereographic,
+            },
+        };
+        let content = format!(
+            "{}\nnonsense\n\nlabel\tyesterday\t1\t2\t1\t0\t0\tfalse\n",
+            format_line(&bookmark)
+        );
+        let bookmarks = parse(&content);
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].label, "Eclipse over Greenwich");
+        assert_eq!(bookmarks[0].time, bookmark.time);
+        assert!((bookmarks[0].latitude - 0.9).abs() < 1e-12);
+        assert!((bookmarks[0].longitude - 0.1).abs() < 1e-12);
+        assert_eq!(bookmarks[0].view.zoom, 2.5);
+        assert_eq!(bookmarks[0].view.pan, (0.25, -0.5));
+        assert!(bookmarks[0].view.flipped);
+    }
+}
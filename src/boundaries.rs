@@ -0,0 +1,146 @@
+use std::f64::consts::PI;
+use std::fs::read_to_string;
+use std::path::Path;
+
+// a constellation outlined by its vertices in equatorial coordinates, the last joined back to the first,
+// the edges run along hour circles and parallels of declination
+pub struct Boundary {
+    pub name: String,
+    pub vertices: Vec<(f64, f64)>,
+}
+
+pub fn load(path: &Path) -> Result<Vec<Boundary>, String> {
+    // the catalog isn't bundled, without it there is simply nothing to draw
+    let Ok(content) = read_to_string(path) else {
+        return Ok(Vec::new());
+    };
+    parse(&content).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+fn parse(content: &str) -> Result<Vec<Boundary>, String> {
+    // right ascension in hours and declination in degrees for J2000, the frame the engine draws in, and the
+    // abbreviation on each line, the vertices of one constellation in order; further columns are left alone
+    let mut boundaries: Vec<Boundary> = Vec::new();
+    for line in content.lines() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (vertex, name) = parse_line(line)?;
+        match boundaries.last_mut() {
+            Some(boundary) if boundary.name == name => boundary.vertices.push(vertex),
+            _ => boundaries.push(Boundary {
+                name,
+                vertices: vec![vertex],
+            }),
+        }
+    }
+    Ok(boundaries)
+}
+
+fn parse_line(line: &str) -> Result<((f64, f64), String), String> {
+    let mut parts = line.split_whitespace();
+    let ascension: f64 = parse_field(parts.next(), "ascension", line)?;
+    let declination: f64 = parse_field(parts.next(), "declination", line)?;
+    let name = parts
+        .next()
+        .ok_or_else(|| format!("no constellation in {}", line.trim()))?;
+    Ok(((ascension / 12.0 * PI, declination / 180.0 * PI), name.to_uppercase()))
+}
+
+fn parse_field(value: Option<&str>, field: &str, line: &str) -> Result<f64, String> {
+    let value = value.ok_or_else(|| format!("no {} in {}", field, line.trim()))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {} {} in {}", field, value, line.trim()))
+}
+
+impl Boundary {
+    pub fn edges(&self) -> impl Iterator<Item = ((f64, f64), (f64, f64))> + '_ {
+        let next = self.vertices.iter().cycle().skip(1);
+        self.vertices.iter().copied().zip(next.copied())
+    }
+
+    pub fn contains(&self, ascension: f64, declination: f64) -> bool {
+        // count the edges crossed going north from the point, a boundary around the pole has it inside
+        let mut inside = self.encloses_pole();
+        for ((a1, d1), (a2, d2)) in self.edges() {
+            let span = ascension_difference(a1, a2);
+            let offset = ascension_difference(a1, ascension);
+            // half open, so a vertex right on the meridian is counted once
+            let crosses = if span > 0.0 {
+                (0.0..span).contains(&offset)
+            } else {
+                (span..0.0).contains(&offset)
+            };
+            if crosses && d1 + (d2 - d1) * offset / span > declination {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    fn encloses_pole(&self) -> bool {
+        // the north pole, a boundary going once around the sky south of it keeps the south pole inside instead
+        let winding: f64 = self
+            .edges()
+            .map(|((a1, _), (a2, _))| ascension_difference(a1, a2))
+            .sum();
+        let north = self.vertices.iter().map(|&(_, declination)| declination).sum::<f64>() > 0.0;
+        winding.abs() > PI && north
+    }
+}
+
+fn ascension_difference(from: f64, to: f64) -> f64 {
+    // the shorter way round, in (-pi, pi]
+    -(from - to + PI).rem_euclid(2.0 * PI) + PI
+}
+
+pub fn find(boundaries: &[Boundary], ascension: f64, declination: f64) -> Option<&Boundary> {
+    boundaries
+        .iter()
+        .find(|boundary| boundary.contains(ascension, declination))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let boundaries = parse("# ra dec name\n 1.0 10.0 ABC\n2.0 10.0 abc O\n\n3.0 -5.0 DEF\n").unwrap();
+        assert_eq!(boundaries.len(), 2);
+        assert_eq!(boundaries[0].name, "ABC");
+        assert_eq!(boundaries[0].vertices.len(), 2);
+        assert!((boundaries[0].vertices[1].0 - PI / 6.0).abs() < 1e-12);
+        assert!((boundaries[1].vertices[0].1 + PI / 36.0).abs() < 1e-12);
+
+        let error = parse("1.0 10.0 ABC\n1.0 20.0\n").err();
+        assert_eq!(error.as_deref(), Some("no constellation in 1.0 20.0"));
+        let error = parse("1.0 north ABC\n").err();
+        assert_eq!(error.as_deref(), Some("invalid declination north in 1.0 north ABC"));
+    }
+
+    #[test]
+    fn test_contains() {
+        // a box across 0h, and a cap around the north pole with a notch cut out of it
+        let boundaries = parse(
+            "23.0 -10.0 BOX\n23.0 10.0 BOX\n1.0 10.0 BOX\n1.0 -10.0 BOX\n\
+             0.0 80.0 CAP\n6.0 80.0 CAP\n12.0 80.0 CAP\n18.0 80.0 CAP\n18.0 85.0 CAP\n20.0 85.0 CAP\n20.0 80.0 CAP\n",
+        )
+        .unwrap();
+        let hours = |hours: f64| hours / 12.0 * PI;
+        let degrees = |degrees: f64| degrees / 180.0 * PI;
+        let name = |ascension: f64, declination: f64| {
+            find(&boundaries, hours(ascension), degrees(declination)).map(|boundary| boundary.name.as_str())
+        };
+        assert_eq!(name(23.5, 0.0), Some("BOX"));
+        assert_eq!(name(0.5, 5.0), Some("BOX"));
+        assert_eq!(name(2.0, 0.0), None);
+        assert_eq!(name(0.0, 20.0), None);
+        assert_eq!(name(3.0, 88.0), Some("CAP"));
+        assert_eq!(name(10.0, 82.0), Some("CAP"));
+        assert_eq!(name(19.0, 82.0), None);
+        assert_eq!(name(19.0, 87.0), Some("CAP"));
+        assert_eq!(name(10.0, 70.0), None);
+    }
+}
@@ -0,0 +1,89 @@
+use antikythera::Star;
+use sdl2::pixels::Color;
+
+// effective temperatures in kelvin representative of each spectral class
+const CLASS_TEMPERATURES: [(char, f64); 7] = [
+    ('O', 40000.0),
+    ('B', 20000.0),
+    ('A', 8750.0),
+    ('F', 6750.0),
+    ('G', 5600.0),
+    ('K', 4450.0),
+    ('M', 3200.0),
+];
+
+pub fn star_tint(star: &Star) -> Color {
+    // photometry first, the spectral class if that is all the catalog has, white otherwise
+    match (star.color_index, &star.spectral_type) {
+        (Some(index), _) => color_from_index(index),
+        (None, Some(spectral_type)) => color_from_spectral_type(spectral_type),
+        (None, None) => Color::WHITE,
+    }
+}
+
+pub fn color_from_index(index: f64) -> Color {
+    // Ballesteros' formula for the temperature of a black body with the given B-V
+    let temperature = 4600.0 * (1.0 / (0.92 * index + 1.7) + 1.0 / (0.92 * index + 0.62));
+    color_from_temperature(temperature)
+}
+
+pub fn color_from_spectral_type(spectral_type: &str) -> Color {
+    let class = spectral_type
+        .trim_start()
+        .chars()
+        .next()
+        .map(|class| class.to_ascii_uppercase());
+    CLASS_TEMPERATURES
+        .iter()
+        .find(|&&(letter, _)| Some(letter) == class)
+        .map_or(Color::WHITE, |&(_, temperature)| color_from_temperature(temperature))
+}
+
+fn color_from_temperature(temperature: f64) -> Color {
+    // a fit to the black body colors, in hundreds of kelvin
+    let t = temperature / 100.0;
+    let channel = |value: f64| value.clamp(0.0, 255.0).round() as u8;
+    let (r, g) = if t <= 66.0 {
+        (255.0, 99.4708025861 * t.ln() - 161.1195681661)
+    } else {
+        (
+            329.698727446 * (t - 60.0).powf(-0.1332047592),
+            288.1221695283 * (t - 60.0).powf(-0.0755148492),
+        )
+    };
+    let b = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.5177312231 * (t - 10.0).ln() - 305.0447927307
+    };
+    Color::RGB(channel(r), channel(g), channel(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_from_spectral_type() {
+        let hot = color_from_spectral_type("O9.5Ib");
+        assert!(hot.b == 255 && hot.r < hot.b);
+        let cool = color_from_spectral_type("M1.5Iab");
+        assert!(cool.r == 255 && cool.b < cool.g);
+        assert_eq!(color_from_spectral_type("g2v"), color_from_spectral_type("G8III"));
+        assert_eq!(color_from_spectral_type("DA2"), Color::WHITE);
+        assert_eq!(color_from_spectral_type(""), Color::WHITE);
+    }
+
+    #[test]
+    fn test_color_from_index() {
+        // Vega is bluish white, Betelgeuse orange, the Sun in between
+        let vega = color_from_index(0.0);
+        assert!(vega.b == 255 && vega.r < 230);
+        let betelgeuse = color_from_index(1.85);
+        assert!(betelgeuse.r == 255 && betelgeuse.g < 200 && betelgeuse.b < betelgeuse.g);
+        let sun = color_from_index(0.65);
+        assert!(sun.r == 255 && sun.g > 220 && sun.b > 180);
+    }
+}
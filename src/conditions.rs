@@ -0,0 +1,87 @@
+use crate::config::Config;
+
+// a bundle of the settings that decide how much of the sky a site shows
+#[derive(Clone, PartialEq, Debug)]
+pub struct Conditions {
+    pub name: String,
+    pub limit: f64,      // faintest magnitude seen at the zenith
+    pub extinction: f64, // magnitudes lost per airmass beyond the zenith
+    pub gamma: f64,      // above one the fainter stars lose contrast against the sky
+}
+
+const PRESETS: [(&str, f64, f64, f64); 3] = [
+    ("city", 4.0, 0.4, 1.6),
+    ("suburb", 5.0, 0.3, 1.3),
+    ("dark_site", 6.5, 0.2, 1.0),
+];
+
+pub fn load(config: &Config) -> Vec<Conditions> {
+    // the presets, changed or joined by the ones in the config in their order
+    let mut conditions: Vec<Conditions> = PRESETS
+        .iter()
+        .map(|&(name, limit, extinction, gamma)| Conditions {
+            name: name.to_string(),
+            limit,
+            extinction,
+            gamma,
+        })
+        .collect();
+    for (name, value) in config.entries("conditions") {
+        let Some((limit, extinction, gamma)) = parse_values(value) else {
+            eprintln!("Invalid sky conditions {} for conditions.{}", value, name);
+            continue;
+        };
+        let entry = Conditions {
+            name: name.clone(),
+            limit,
+            extinction,
+            gamma,
+        };
+        match conditions.iter_mut().find(|known| known.name == *name) {
+            Some(known) => *known = entry,
+            None => conditions.push(entry),
+        }
+    }
+    conditions
+}
+
+fn parse_values(value: &str) -> Option<(f64, f64, f64)> {
+    // limiting magnitude, extinction and gamma
+    let values: Vec<f64> = value
+        .split(',')
+        .map(|part| part.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    match values[..] {
+        [limit, extinction, gamma] if extinction >= 0.0 && gamma > 0.0 => Some((limit, extinction, gamma)),
+        _ => None,
+    }
+}
+
+pub fn apply_gamma(brightness: u8, gamma: f64) -> u8 {
+    (255.0 * (brightness as f64 / 255.0).powf(gamma)).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load() {
+        let config = Config::parse("[conditions]\ncity = 3.5, 0.5, 2\nbackyard = 5.5, 0.25, 1.1\nbogus = 1, 2\n");
+        let conditions = load(&config);
+        let names: Vec<&str> = conditions.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["city", "suburb", "dark_site", "backyard"]);
+        assert_eq!(conditions[0].limit, 3.5);
+        assert_eq!(conditions[3].gamma, 1.1);
+        assert_eq!(load(&Config::parse("")).len(), PRESETS.len());
+        assert_eq!(parse_values("5, -0.1, 1"), None);
+    }
+
+    #[test]
+    fn test_apply_gamma() {
+        assert_eq!(apply_gamma(255, 1.6), 255);
+        assert_eq!(apply_gamma(127, 1.0), 127);
+        assert!(apply_gamma(127, 2.0) < 70);
+        assert!(apply_gamma(127, 0.5) > 170);
+    }
+}
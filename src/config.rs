@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::Path;
+
+pub struct Config {
+    sections: HashMap<String, Vec<(String, String)>>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Self {
+        // a missing config is not an error, every setting has a default
+        match read_to_string(path) {
+            Ok(content) => Self::parse(&content),
+            Err(_) => Self::parse(""),
+        }
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut sections: HashMap<String, Vec<(String, String)>> = HashMap::new();
+        let mut section = String::new();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                section = name.trim().to_string();
+            } else if let Some((key, value)) = line.split_once('=') {
+                sections
+                    .entry(section.clone())
+                    .or_default()
+                    .push((key.trim().to_string(), value.trim().to_string()));
+            } else {
+                eprintln!("Ignoring malformed config line {}: {}", number + 1, line);
+            }
+        }
+        Self { sections }
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        // later entries override earlier ones
+        self.entries(section)
+            .iter()
+            .rev()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn entries(&self, section: &str) -> &[(String, String)] {
+        self.sections
+            .get(section)
+            .map(|entries| entries.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse(
+            "top = 1\n\
+             [theme]\n\
+             # comment\n\
+             preset = night # trailing comment\n\
+             text=255, 0, 0\n\
+             garbage\n\
+             preset = default\n",
+        );
+        assert_eq!(config.get("", "top"), Some("1"));
+        assert_eq!(config.get("theme", "preset"), Some("default"));
+        assert_eq!(config.get("theme", "text"), Some("255, 0, 0"));
+        assert_eq!(config.get("theme", "garbage"), None);
+        assert_eq!(config.get("keys", "text"), None);
+        assert_eq!(config.entries("theme").len(), 3);
+        assert!(config.entries("keys").is_empty());
+    }
+}
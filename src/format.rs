@@ -0,0 +1,82 @@
+use std::f64::consts::PI;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Notation {
+    Decimal,
+    Sexagesimal,
+}
+
+impl Notation {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "decimal" => Some(Notation::Decimal),
+            "sexagesimal" => Some(Notation::Sexagesimal),
+            _ => None,
+        }
+    }
+}
+
+fn sexagesimal(value: f64) -> (bool, u64, u64, u64) {
+    // sign, units, minutes and seconds, the sign only kept when something is left after rounding
+    let seconds = (value.abs() * 3600.0).round() as u64;
+    (
+        value < 0.0 && seconds > 0,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+    )
+}
+
+pub fn hours(angle: f64, notation: Notation, decimals: usize) -> String {
+    // right ascension and other angles measured in hours, wrapped into a day
+    let hours = (angle / PI * 12.0).rem_euclid(24.0);
+    match notation {
+        Notation::Decimal => format!("{:.*}h", decimals, hours),
+        Notation::Sexagesimal => {
+            let (_, hours, minutes, seconds) = sexagesimal(hours);
+            format!("{:02}:{:02}:{:02}", hours % 24, minutes, seconds)
+        }
+    }
+}
+
+pub fn degrees(angle: f64, notation: Notation, decimals: usize) -> String {
+    let degrees = angle / PI * 180.0;
+    match notation {
+        Notation::Decimal => format!("{:.*}", decimals, degrees),
+        Notation::Sexagesimal => {
+            let (negative, degrees, minutes, seconds) = sexagesimal(degrees);
+            let sign = if negative { "-" } else { "" };
+            format!("{}{:02}:{:02}:{:02}", sign, degrees, minutes, seconds)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hours() {
+        assert_eq!(hours(PI / 2.0, Notation::Decimal, 4), "6.0000h");
+        assert_eq!(hours(PI / 2.0, Notation::Sexagesimal, 4), "06:00:00");
+        assert_eq!(hours(13.5125 / 12.0 * PI, Notation::Sexagesimal, 4), "13:30:45");
+        // negative angles and rounding up to a full day wrap around
+        assert_eq!(hours(-PI / 2.0, Notation::Sexagesimal, 4), "18:00:00");
+        assert_eq!(hours(23.99999 / 12.0 * PI, Notation::Sexagesimal, 4), "00:00:00");
+    }
+
+    #[test]
+    fn test_degrees() {
+        assert_eq!(degrees(-PI / 4.0, Notation::Decimal, 2), "-45.00");
+        assert_eq!(degrees(-PI / 4.0, Notation::Sexagesimal, 2), "-45:00:00");
+        assert_eq!(degrees(12.5125 / 180.0 * PI, Notation::Sexagesimal, 2), "12:30:45");
+        // just south of the equator keeps its sign, nothing left after rounding drops it
+        assert_eq!(degrees(-0.5 / 180.0 * PI, Notation::Sexagesimal, 2), "-00:30:00");
+        assert_eq!(degrees(-0.0, Notation::Sexagesimal, 2), "00:00:00");
+        assert_eq!(
+            degrees(-0.1 / 3600.0 / 180.0 * PI, Notation::Sexagesimal, 2),
+            "00:00:00"
+        );
+        assert_eq!(degrees(59.99999 / 180.0 * PI, Notation::Sexagesimal, 2), "60:00:00");
+    }
+}
@@ -0,0 +1,276 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+// just enough json for the session file, objects keep their keys in order
+#[derive(Clone, PartialEq, Debug)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Json::Number(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Json::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn compact(&self) -> Compact<'_> {
+        Compact(self)
+    }
+
+    fn write(&self, f: &mut fmt::Formatter, indent: usize) -> fmt::Result {
+        let pad = |depth: usize| "  ".repeat(depth);
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            // json has no infinities, nor nans
+            Json::Number(value) if value.is_finite() => write!(f, "{}", value),
+            Json::Number(_) => write!(f, "null"),
+            Json::String(value) => write_string(f, value),
+            Json::Array(values) if values.is_empty() => write!(f, "[]"),
+            Json::Array(values) => {
+                writeln!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    write!(f, "{}", pad(indent + 1))?;
+                    value.write(f, indent + 1)?;
+                    writeln!(f, "{}", if index + 1 < values.len() { "," } else { "" })?;
+                }
+                write!(f, "{}]", pad(indent))
+            }
+            Json::Object(entries) if entries.is_empty() => write!(f, "{{}}"),
+            Json::Object(entries) => {
+                writeln!(f, "{{")?;
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    write!(f, "{}", pad(indent + 1))?;
+                    write_string(f, key)?;
+                    write!(f, ": ")?;
+                    value.write(f, indent + 1)?;
+                    writeln!(f, "{}", if index + 1 < entries.len() { "," } else { "" })?;
+                }
+                write!(f, "{}}}", pad(indent))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+// the same document on a single line, for protocols read line by line
+pub struct Compact<'a>(&'a Json);
+
+impl fmt::Display for Compact<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    write!(f, "{}{}", if index > 0 { "," } else { "" }, value.compact())?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(entries) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    write!(f, "{}", if index > 0 { "," } else { "" })?;
+                    write_string(f, key)?;
+                    write!(f, ":{}", value.compact())?;
+                }
+                write!(f, "}}")
+            }
+            value => value.write(f, 0),
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, value: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+pub fn parse(text: &str) -> Option<Json> {
+    let mut chars = text.chars().peekable();
+    let value = parse_value(&mut chars)?;
+    skip_whitespace(&mut chars);
+    // trailing garbage makes the whole document invalid
+    chars.peek().is_none().then_some(value)
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+fn expect_word(chars: &mut Peekable<Chars>, word: &str, value: Json) -> Option<Json> {
+    word.chars().all(|c| chars.next() == Some(c)).then_some(value)
+}
+
+fn parse_value(chars: &mut Peekable<Chars>) -> Option<Json> {
+    skip_whitespace(chars);
+    match *chars.peek()? {
+        'n' => expect_word(chars, "null", Json::Null),
+        't' => expect_word(chars, "true", Json::Bool(true)),
+        'f' => expect_word(chars, "false", Json::Bool(false)),
+        '"' => parse_string(chars).map(Json::String),
+        '[' => {
+            chars.next();
+            let mut values = Vec::new();
+            skip_whitespace(chars);
+            if chars.next_if_eq(&']').is_some() {
+                return Some(Json::Array(values));
+            }
+            loop {
+                values.push(parse_value(chars)?);
+                skip_whitespace(chars);
+                match chars.next()? {
+                    ',' => continue,
+                    ']' => return Some(Json::Array(values)),
+                    _ => return None,
+                }
+            }
+        }
+        '{' => {
+            chars.next();
+            let mut entries = Vec::new();
+            skip_whitespace(chars);
+            if chars.next_if_eq(&'}').is_some() {
+                return Some(Json::Object(entries));
+            }
+            loop {
+                skip_whitespace(chars);
+                let key = parse_string(chars)?;
+                skip_whitespace(chars);
+                chars.next_if_eq(&':')?;
+                entries.push((key, parse_value(chars)?));
+                skip_whitespace(chars);
+                match chars.next()? {
+                    ',' => continue,
+                    '}' => return Some(Json::Object(entries)),
+                    _ => return None,
+                }
+            }
+        }
+        _ => {
+            let mut number = String::new();
+            while let Some(c) = chars.next_if(|c| matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9')) {
+                number.push(c);
+            }
+            number.parse().ok().map(Json::Number)
+        }
+    }
+}
+
+fn parse_string(chars: &mut Peekable<Chars>) -> Option<String> {
+    chars.next_if_eq(&'"')?;
+    let mut value = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(value),
+            '\\' => value.push(match chars.next()? {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'b' => '\u{8}',
+                'f' => '\u{c}',
+                'u' => {
+                    let code: String = (0..4).filter_map(|_| chars.next()).collect();
+                    char::from_u32(u32::from_str_radix(&code, 16).ok()?)?
+                }
+                c => c,
+            }),
+            c => value.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let value = Json::Object(vec![
+            (
+                "name".to_string(),
+                Json::String("tab\tand \"quotes\" \u{1}".to_string()),
+            ),
+            ("zoom".to_string(), Json::Number(2.5e-7)),
+            (
+                "list".to_string(),
+                Json::Array(vec![Json::Bool(true), Json::Null, Json::Array(Vec::new())]),
+            ),
+            ("empty".to_string(), Json::Object(Vec::new())),
+        ]);
+        assert_eq!(parse(&value.to_string()), Some(value.clone()));
+        let line = value.compact().to_string();
+        assert!(!line.contains('\n'));
+        assert_eq!(parse(&line), Some(value.clone()));
+        assert_eq!(value.get("zoom").and_then(Json::as_f64), Some(2.5e-7));
+        assert_eq!(value.get("missing"), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        let value = parse(r#" {"a": [1, -2.5e1], "b": "é\/", "c": false} "#).unwrap();
+        assert_eq!(
+            value.get("a").and_then(Json::as_array),
+            Some(&[Json::Number(1.0), Json::Number(-25.0)][..])
+        );
+        assert_eq!(value.get("b").and_then(Json::as_str), Some("é/"));
+        assert_eq!(value.get("c").and_then(Json::as_bool), Some(false));
+        assert_eq!(parse("{\"a\": 1,}"), None);
+        assert_eq!(parse("[1, 2] 3"), None);
+        assert_eq!(parse("nul"), None);
+        assert_eq!(parse("\"open"), None);
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use sdl2::keyboard::Keycode;
+
+use crate::config::Config;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    StepBackward,
+    StepForward,
+    SetLatitude,
+    SetLongitude,
+    Aberration,
+    Moonlight,
+    DoubleMarkers,
+    FpsOverlay,
+    Theme,
+    Elongations,
+    GalacticGrid,
+    Crosshair,
+    DayLength,
+    Twinkle,
+    Glow,
+    Bookmark,
+    Bookmarks,
+    Eclipses,
+    InfoPanel,
+    Labels,
+    Split,
+    Focus,
+    ResetTime,
+    ResetView,
+    Mirror,
+    ZoomIn,
+    ZoomOut,
+    Minimap,
+    GoTo,
+    Libration,
+    TimeDisplay,
+    Reverse,
+    Atmosphere,
+    World,
+    LabelsBrighter,
+    LabelsFainter,
+    HourRing,
+    SaveSession,
+    LoadSession,
+    LunarNodes,
+    Trails,
+    Seeing,
+    Cycle,
+    Ruler,
+    Boundaries,
+    Conditions,
+    Refraction,
+    PoleTrip,
+    Highlight,
+    Reload,
+    Projection,
+    Gradient,
+    ExportSvg,
+    Leaders,
+    ZodiacalLight,
+    Scrub,
+    NextSunEvent,
+    NextMoonEvent,
+    RisePoints,
+    Compass,
+    Stats,
+    StarGlyph,
+    WalkNorth,
+    WalkSouth,
+    WalkEast,
+    WalkWest,
+}
+
+const DEFAULTS: [(Action, &str, &[Keycode]); 62] = [
+    (Action::StepBackward, "step_backward", &[Keycode::Left]),
+    (Action::StepForward, "step_forward", &[Keycode::Right]),
+    (Action::SetLatitude, "latitude", &[Keycode::A]),
+    (Action::SetLongitude, "longitude", &[Keycode::O]),
+    (Action::Aberration, "aberration", &[Keycode::B]),
+    (Action::Moonlight, "moonlight", &[Keycode::L]),
+    (Action::DoubleMarkers, "double_markers", &[Keycode::D]),
+    (Action::FpsOverlay, "fps", &[Keycode::F]),
+    (Action::Theme, "theme", &[Keycode::R]),
+    (Action::Elongations, "elongations", &[Keycode::E]),
+    (Action::GalacticGrid, "galactic_grid", &[Keycode::G]),
+    (Action::Crosshair, "crosshair", &[Keycode::C]),
+    (Action::DayLength, "day_length", &[Keycode::H]),
+    (Action::Twinkle, "twinkle", &[Keycode::K]),
+    (Action::Glow, "glow", &[Keycode::U]),
+    (Action::Bookmark, "bookmark", &[Keycode::P]),
+    (Action::Bookmarks, "bookmarks", &[Keycode::J]),
+    (Action::Eclipses, "eclipses", &[Keycode::Q]),
+    (Action::InfoPanel, "info_panel", &[Keycode::I]),
+    (Action::Labels, "labels", &[Keycode::N]),
+    (Action::Split, "split", &[Keycode::S]),
+    (Action::Focus, "focus", &[Keycode::X]),
+    (Action::ResetTime, "reset_time", &[Keycode::T]),
+    (Action::ResetView, "reset_view", &[Keycode::V]),
+    (Action::Mirror, "mirror", &[Keycode::M]),
+    (Action::ZoomIn, "zoom_in", &[Keycode::Equals, Keycode::KpPlus]),
+    (Action::ZoomOut, "zoom_out", &[Keycode::Minus, Keycode::KpMinus]),
+    (Action::Minimap, "minimap", &[Keycode::W]),
+    (Action::GoTo, "go_to", &[Keycode::Return]),
+    (Action::Libration, "libration", &[Keycode::Y]),
+    (Action::TimeDisplay, "time_display", &[Keycode::Z]),
+    (Action::Reverse, "reverse", &[Keycode::Down]),
+    (Action::Atmosphere, "atmosphere", &[Keycode::F2]),
+    (Action::World, "world", &[Keycode::F3]),
+    (Action::LabelsBrighter, "labels_brighter", &[Keycode::LeftBracket]),
+    (Action::LabelsFainter, "labels_fainter", &[Keycode::RightBracket]),
+    (Action::HourRing, "hour_ring", &[Keycode::F4]),
+    (Action::SaveSession, "save_session", &[Keycode::F8]),
+    (Action::LoadSession, "load_session", &[Keycode::F9]),
+    (Action::LunarNodes, "lunar_nodes", &[Keycode::F10]),
+    (Action::Trails, "trails", &[Keycode::F11]),
+    (Action::Seeing, "seeing", &[Keycode::F12]),
+    (Action::Cycle, "cycle", &[Keycode::Tab]),
+    (Action::Ruler, "ruler", &[Keycode::F1]),
+    (Action::Boundaries, "boundaries", &[Keycode::Semicolon]),
+    (Action::Conditions, "conditions", &[Keycode::Quote]),
+    (Action::Refraction, "refraction", &[Keycode::Backslash]),
+    (Action::PoleTrip, "pole_trip", &[Keycode::Slash]),
+    (Action::Highlight, "highlight", &[Keycode::Period]),
+    (Action::Reload, "reload", &[Keycode::Comma]),
+    (Action::Projection, "projection", &[Keycode::End]),
+    (Action::Gradient, "gradient", &[Keycode::Home]),
+    (Action::ExportSvg, "export_svg", &[Keycode::Insert]),
+    (Action::Leaders, "leaders", &[Keycode::PageUp]),
+    (Action::ZodiacalLight, "zodiacal_light", &[Keycode::PageDown]),
+    (Action::Scrub, "scrub", &[Keycode::Backquote]),
+    (Action::NextSunEvent, "next_sun_event", &[Keycode::Up]),
+    (Action::NextMoonEvent, "next_moon_event", &[Keycode::Delete]),
+    (Action::RisePoints, "rise_points", &[Keycode::Num0]),
+    (Action::Compass, "compass", &[Keycode::Num1]),
+    (Action::Stats, "stats", &[Keycode::Num2]),
+    (Action::StarGlyph, "star_glyph", &[Keycode::Num3]),
+];
+
+// pressed with Ctrl held, so they can share keys with the table above
+const CTRL_DEFAULTS: [(Action, &str, &[Keycode]); 4] = [
+    (Action::WalkNorth, "walk_north", &[Keycode::Up, Keycode::Kp8]),
+    (Action::WalkSouth, "walk_south", &[Keycode::Down, Keycode::Kp2]),
+    (Action::WalkEast, "walk_east", &[Keycode::Right, Keycode::Kp6]),
+    (Action::WalkWest, "walk_west", &[Keycode::Left, Keycode::Kp4]),
+];
+
+pub struct Bindings {
+    keys: HashMap<Keycode, Action>,
+    ctrl_keys: HashMap<Keycode, Action>,
+}
+
+impl Bindings {
+    pub fn load(config: &Config) -> Self {
+        Self::parse(config, Keycode::from_name)
+    }
+
+    fn parse(config: &Config, lookup: impl Fn(&str) -> Option<Keycode>) -> Self {
+        for (name, _) in config.entries("keys") {
+            if !DEFAULTS
+                .iter()
+                .chain(&CTRL_DEFAULTS)
+                .any(|&(_, action, _)| action == name)
+            {
+                eprintln!("Unknown key binding action {}", name);
+            }
+        }
+        Self {
+            keys: resolve(&DEFAULTS, config, &lookup),
+            ctrl_keys: resolve(&CTRL_DEFAULTS, config, &lookup),
+        }
+    }
+
+    pub fn action(&self, key: Keycode) -> Option<Action> {
+        self.keys.get(&key).copied()
+    }
+
+    pub fn ctrl_action(&self, key: Keycode) -> Option<Action> {
+        self.ctrl_keys.get(&key).copied()
+    }
+}
+
+fn resolve(
+    defaults: &[(Action, &str, &[Keycode])],
+    config: &Config,
+    lookup: impl Fn(&str) -> Option<Keycode>,
+) -> HashMap<Keycode, Action> {
+    let mut bindings: Vec<(Action, Vec<Keycode>)> = defaults
+        .iter()
+        .map(|&(action, _, keys)| (action, keys.to_vec()))
+        .collect();
+    let mut overridden = Vec::new();
+    for (name, value) in config.entries("keys") {
+        let Some(index) = defaults.iter().position(|&(_, action, _)| action == name) else {
+            continue;
+        };
+        let keys: Option<Vec<Keycode>> = value.split(',').map(|key| lookup(key.trim())).collect();
+        match keys {
+            Some(keys) if !keys.is_empty() => {
+                bindings[index].1 = keys;
+                overridden.push(index);
+            }
+            _ => eprintln!("Invalid keys {} for keys.{}", value, name),
+        }
+    }
+
+    // a key claimed by two actions sends the rebound ones back to their defaults
+    loop {
+        let mut owners: HashMap<Keycode, Vec<usize>> = HashMap::new();
+        for (index, (_, keys)) in bindings.iter().enumerate() {
+            for &key in keys {
+                owners.entry(key).or_default().push(index);
+            }
+        }
+        let conflicting: Vec<usize> = overridden
+            .iter()
+            .copied()
+            .filter(|index| {
+                bindings[*index]
+                    .1
+                    .iter()
+                    .any(|key| owners[key].iter().any(|owner| owner != index))
+            })
+            .collect();
+        if conflicting.is_empty() {
+            break;
+        }
+        for index in conflicting {
+            eprintln!("Conflicting binding for keys.{}, using default", defaults[index].1);
+            bindings[index].1 = defaults[index].2.to_vec();
+            overridden.retain(|&other| other != index);
+        }
+    }
+
+    bindings
+        .into_iter()
+        .flat_map(|(action, keys)| keys.into_iter().map(move |key| (key, action)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<Keycode> {
+        match name {
+            "A" => Some(Keycode::A),
+            "O" => Some(Keycode::O),
+            "F5" => Some(Keycode::F5),
+            "F6" => Some(Keycode::F6),
+            "F7" => Some(Keycode::F7),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse("[keys]\nlatitude = F5\nmirror = F6, F7\nbogus = A\nfps = nonsense\n");
+        let bindings = Bindings::parse(&config, lookup);
+        assert_eq!(bindings.action(Keycode::F5), Some(Action::SetLatitude));
+        assert_eq!(bindings.action(Keycode::A), None);
+        assert_eq!(bindings.action(Keycode::F6), Some(Action::Mirror));
+        assert_eq!(bindings.action(Keycode::F7), Some(Action::Mirror));
+        assert_eq!(bindings.action(Keycode::M), None);
+        assert_eq!(bindings.action(Keycode::F), Some(Action::FpsOverlay));
+        assert_eq!(bindings.action(Keycode::KpPlus), Some(Action::ZoomIn));
+    }
+
+    #[test]
+    fn test_conflicts() {
+        // latitude takes the longitude key, then crosshair takes the freed-up latitude key
+        let config = Config::parse("[keys]\nlatitude = O\ncrosshair = A\n");
+        let bindings = Bindings::parse(&config, lookup);
+        assert_eq!(bindings.action(Keycode::O), Some(Action::SetLongitude));
+        assert_eq!(bindings.action(Keycode::A), Some(Action::SetLatitude));
+        assert_eq!(bindings.action(Keycode::C), Some(Action::Crosshair));
+    }
+
+    #[test]
+    fn test_ctrl() {
+        // the walk shares the arrows with the plain actions and is rebound on its own
+        let config = Config::parse("[keys]\nwalk_north = F5\nwalk_south = A\n");
+        let bindings = Bindings::parse(&config, lookup);
+        assert_eq!(bindings.ctrl_action(Keycode::F5), Some(Action::WalkNorth));
+        assert_eq!(bindings.ctrl_action(Keycode::Up), None);
+        assert_eq!(bindings.ctrl_action(Keycode::Kp8), None);
+        assert_eq!(bindings.action(Keycode::Up), Some(Action::NextSunEvent));
+        assert_eq!(bindings.action(Keycode::F5), None);
+        assert_eq!(bindings.ctrl_action(Keycode::A), Some(Action::WalkSouth));
+        assert_eq!(bindings.action(Keycode::A), Some(Action::SetLatitude));
+        assert_eq!(bindings.ctrl_action(Keycode::Left), Some(Action::WalkWest));
+    }
+}
@@ -0,0 +1,429 @@
+// the astronomy behind antikythera, usable without the sdl front end
+pub mod astro;
+pub mod math;
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fs::read_to_string;
+use std::path::Path;
+use std::str::FromStr;
+
+pub const J2000: f64 = 2451545.0; // julian date, the epoch of the star catalog and of the frame the engine is fixed to
+
+// the columns of planets.dat when it has no header
+const PLANET_COLUMNS: [&str; 8] = [
+    "name",
+    "semimajor",
+    "sidereal",
+    "phase",
+    "inclination",
+    "incl_phase",
+    "abs_magnitude",
+    "texture",
+];
+
+pub struct Star {
+    pub hip: u32,
+    pub name: Option<String>,
+    pub designation: Option<String>,
+    pub ascension: f64,
+    pub declination: f64,
+    pub epoch: f64, // julian date of the equinox the position is given for
+    pub magnitude: f64,
+    pub variability: Option<Variability>,
+    pub double: bool,
+    pub companion: Option<Companion>,
+    pub color_index: Option<f64>, // B-V
+    pub spectral_type: Option<String>,
+}
+
+impl Star {
+    // a plain catalog entry, the optional catalogs fill in the rest
+    pub fn new(hip: u32, ascension: f64, declination: f64, magnitude: f64) -> Self {
+        Self {
+            hip,
+            name: None,
+            designation: None,
+            ascension,
+            declination,
+            epoch: J2000,
+            magnitude,
+            variability: None,
+            double: false,
+            companion: None,
+            color_index: None,
+            spectral_type: None,
+        }
+    }
+
+    pub fn peak_magnitude(&self) -> f64 {
+        // the brightest the star ever gets
+        self.variability.as_ref().map_or(self.magnitude, |variability| {
+            variability.max_magnitude.min(self.magnitude)
+        })
+    }
+}
+
+pub enum VariableKind {
+    Pulsating,
+    Eclipsing,
+}
+
+pub struct Variability {
+    pub kind: VariableKind,
+    pub max_magnitude: f64,
+    pub min_magnitude: f64,
+    pub period: f64,
+    pub epoch: f64,
+}
+
+pub struct Companion {
+    pub separation: f64,
+    pub position_angle: f64,
+    pub magnitude: f64,
+}
+
+pub struct Planet {
+    pub name: String,
+    pub semimajor: f64,
+    pub sidereal: f64,
+    pub phase: f64,
+    pub inclination: f64,
+    pub incl_phase: f64,
+    pub abs_magnitude: f64,
+    pub texture: Option<String>, // file name under resources/textures
+}
+
+// anything on the sky that can be picked, stars and planets by their index in the loaded catalogs
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SkyObject {
+    Star(usize),
+    Planet(usize),
+    Sun,
+    Moon,
+    DeepSky(usize),   // no catalog of these is loaded yet
+    Satellite(usize), // artificial, none are tracked yet
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ObjectKind {
+    Star,
+    Planet,
+    Sun,
+    Moon,
+    DeepSky,
+    Satellite,
+}
+
+impl SkyObject {
+    pub fn kind(self) -> ObjectKind {
+        match self {
+            SkyObject::Star(_) => ObjectKind::Star,
+            SkyObject::Planet(_) => ObjectKind::Planet,
+            SkyObject::Sun => ObjectKind::Sun,
+            SkyObject::Moon => ObjectKind::Moon,
+            SkyObject::DeepSky(_) => ObjectKind::DeepSky,
+            SkyObject::Satellite(_) => ObjectKind::Satellite,
+        }
+    }
+}
+
+impl ObjectKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ObjectKind::Star => "star",
+            ObjectKind::Planet => "planet",
+            ObjectKind::Sun => "sun",
+            ObjectKind::Moon => "moon",
+            ObjectKind::DeepSky => "deep-sky object",
+            ObjectKind::Satellite => "satellite",
+        }
+    }
+}
+
+pub fn parse_stars(content: &str) -> Result<Vec<Star>, String> {
+    // hipparcos number, right ascension, declination, magnitude and an optional name on each line
+    let mut stars = Vec::new();
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let mut parts = line.split_whitespace();
+        let hip = parse_field(parts.next(), "hip", line)?;
+        let ascension = parse_field(parts.next(), "ascension", line)?;
+        let declination = parse_field(parts.next(), "declination", line)?;
+        let magnitude = parse_field(parts.next(), "magnitude", line)?;
+        let name = parts.next();
+        stars.push(Star {
+            name: name.map(|name| name.to_string()),
+            ..Star::new(hip, ascension, declination, magnitude)
+        });
+    }
+    Ok(stars)
+}
+
+fn parse_field<T: FromStr>(value: Option<&str>, field: &str, line: &str) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("no {} in {}", field, line.trim()))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {} {} in {}", field, value, line.trim()))
+}
+
+pub fn sort_by_brightness(stars: &mut [Star]) {
+    // brightest first, so everything down to a given magnitude is a prefix
+    stars.sort_by(|a, b| a.peak_magnitude().total_cmp(&b.peak_magnitude()));
+}
+
+pub fn read_variables(path: &Path, stars: &mut [Star]) -> Result<(), String> {
+    // the variable star catalog is optional, stars keep a constant magnitude without it
+    let Ok(content) = read_to_string(path) else {
+        return Ok(());
+    };
+    parse_variables(&content, stars).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+pub fn parse_variables(content: &str, stars: &mut [Star]) -> Result<(), String> {
+    // hipparcos number, P or E for pulsating or eclipsing, the magnitude range, the period and the epoch in days
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let mut parts = line.split_whitespace();
+        let hip: u32 = parse_field(parts.next(), "hip", line)?;
+        let kind = match parse_field::<String>(parts.next(), "type", line)?.as_str() {
+            "P" => VariableKind::Pulsating,
+            "E" => VariableKind::Eclipsing,
+            kind => return Err(format!("invalid type {} in {}", kind, line.trim())),
+        };
+        let max_magnitude = parse_field(parts.next(), "maximum", line)?;
+        let min_magnitude = parse_field(parts.next(), "minimum", line)?;
+        let period = parse_field(parts.next(), "period", line)?;
+        let epoch = parse_field(parts.next(), "epoch", line)?;
+        if let Some(star) = stars.iter_mut().find(|star| star.hip == hip) {
+            star.variability = Some(Variability {
+                kind,
+                max_magnitude,
+                min_magnitude,
+                period,
+                epoch,
+            });
+        }
+    }
+    Ok(())
+}
+
+pub fn read_doubles(path: &Path, stars: &mut [Star]) -> Result<(), String> {
+    // the double star catalog is optional as well
+    let Ok(content) = read_to_string(path) else {
+        return Ok(());
+    };
+    parse_doubles(&content, stars).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+pub fn parse_doubles(content: &str, stars: &mut [Star]) -> Result<(), String> {
+    // hipparcos number, then the companion's separation in arcseconds, position angle and magnitude if known
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let mut parts = line.split_whitespace();
+        let hip: u32 = parse_field(parts.next(), "hip", line)?;
+        let companion = match parts.next() {
+            Some(separation) => Some(Companion {
+                separation: parse_field::<f64>(Some(separation), "separation", line)? / 3600.0 / 180.0 * PI,
+                position_angle: parse_field::<f64>(parts.next(), "position angle", line)? / 180.0 * PI,
+                magnitude: parse_field(parts.next(), "magnitude", line)?,
+            }),
+            None => None,
+        };
+        if let Some(star) = stars.iter_mut().find(|star| star.hip == hip) {
+            star.double = true;
+            star.companion = companion;
+        }
+    }
+    Ok(())
+}
+
+pub fn read_designations(path: &Path, stars: &mut [Star]) -> Result<(), String> {
+    // Bayer and Flamsteed designations, optional too
+    let Ok(content) = read_to_string(path) else {
+        return Ok(());
+    };
+    parse_designations(&content, stars).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+pub fn parse_designations(content: &str, stars: &mut [Star]) -> Result<(), String> {
+    // hipparcos number and the rest of the line as the designation
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let (hip, designation) = line.trim().split_once(char::is_whitespace).unwrap_or((line.trim(), ""));
+        let hip: u32 = parse_field(Some(hip), "hip", line)?;
+        let designation = designation.trim();
+        if designation.is_empty() {
+            return Err(format!("no designation in {}", line.trim()));
+        }
+        if let Some(star) = stars.iter_mut().find(|star| star.hip == hip) {
+            star.designation = Some(designation.to_string());
+        }
+    }
+    Ok(())
+}
+
+pub fn read_colors(path: &Path, stars: &mut [Star]) -> Result<(), String> {
+    // B-V color indices and spectral types, either may be missing as '-', the file too
+    let Ok(content) = read_to_string(path) else {
+        return Ok(());
+    };
+    parse_colors(&content, stars).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+pub fn parse_colors(content: &str, stars: &mut [Star]) -> Result<(), String> {
+    // hipparcos number, color index and spectral type
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let mut parts = line.split_whitespace();
+        let hip: u32 = parse_field(parts.next(), "hip", line)?;
+        let color_index = match parts.next().filter(|&index| index != "-") {
+            Some(index) => Some(parse_field(Some(index), "color index", line)?),
+            None => None,
+        };
+        let spectral_type = parts
+            .next()
+            .filter(|&spectral| spectral != "-")
+            .map(|spectral| spectral.to_string());
+        if let Some(star) = stars.iter_mut().find(|star| star.hip == hip) {
+            star.color_index = color_index;
+            star.spectral_type = spectral_type;
+        }
+    }
+    Ok(())
+}
+
+pub fn parse_planets(content: &str) -> Result<Vec<Planet>, String> {
+    // a header like "# name semimajor sidereal" names the columns of the lines below it, unknown columns are skipped
+    let mut columns = PLANET_COLUMNS.to_vec();
+    let mut planets = Vec::new();
+    for line in content.lines() {
+        if let Some(header) = line.trim_start().strip_prefix('#') {
+            columns = header.split_whitespace().collect();
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: HashMap<&str, &str> = columns.iter().copied().zip(line.split_whitespace()).collect();
+        let name = *fields
+            .get("name")
+            .ok_or_else(|| format!("no name for {}", line.trim()))?;
+        // only the orbit and the brightness are needed, the rest can be left out
+        let number = |field: &str, default: Option<f64>| match fields.get(field) {
+            Some(value) => value
+                .parse::<f64>()
+                .map_err(|_| format!("invalid {} {} for {}", field, value, name)),
+            None => default.ok_or_else(|| format!("no {} for {}", field, name)),
+        };
+        planets.push(Planet {
+            name: name.to_string(),
+            semimajor: number("semimajor", None)?,
+            sidereal: number("sidereal", None)?,
+            phase: number("phase", Some(0.0))?,
+            inclination: number("inclination", Some(0.0))? / 180.0 * PI,
+            incl_phase: number("incl_phase", Some(0.0))?,
+            abs_magnitude: number("abs_magnitude", None)?,
+            texture: fields
+                .get("texture")
+                .filter(|&&texture| texture != "null")
+                .map(|texture| texture.to_string()),
+        });
+    }
+    Ok(planets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_planets() {
+        // the columns in the default order without a header
+        let planets = parse_planets("Mars 227.956 59355072.0 0.03 1.848 4.0 -1.52 mars.png\n").unwrap();
+        assert_eq!(planets[0].name, "Mars");
+        assert!((planets[0].inclination - 1.848 / 180.0 * PI).abs() < 1e-12);
+        assert_eq!(planets[0].texture.as_deref(), Some("mars.png"));
+
+        let content = "# sidereal name abs_magnitude semimajor rings\n\n7600521.6 Mercury -0.60 57.909 none\n";
+        let planets = parse_planets(content).unwrap();
+        assert_eq!(planets[0].name, "Mercury");
+        assert_eq!(planets[0].semimajor, 57.909);
+        assert_eq!(planets[0].phase, 0.0);
+        assert_eq!(planets[0].texture, None);
+
+        let error = parse_planets("# name semimajor sidereal\nVenus 108.210 19414166.4\n").err();
+        assert_eq!(error.as_deref(), Some("no abs_magnitude for Venus"));
+        let error = parse_planets("Venus 108.210 soon\n").err();
+        assert_eq!(error.as_deref(), Some("invalid sidereal soon for Venus"));
+    }
+
+    #[test]
+    fn test_parse_variables() {
+        let mut stars = parse_stars("27989 1.550 0.129 0.45\n14576 0.821 0.714 2.09\n").unwrap();
+        parse_variables(
+            "27989 P 0.00 1.60 400.0 2458690.0\n\n14576 E 2.09 3.39 2.867 2445641.5\n",
+            &mut stars,
+        )
+        .unwrap();
+        assert!(stars.iter().all(|star| star.variability.is_some()));
+
+        let error = parse_variables("27989 P 0.00 1.60 400.0\n", &mut stars).err();
+        assert_eq!(error.as_deref(), Some("no epoch in 27989 P 0.00 1.60 400.0"));
+        let error = parse_variables("27989 X 0.00 1.60 400.0 2458690.0\n", &mut stars).err();
+        assert_eq!(
+            error.as_deref(),
+            Some("invalid type X in 27989 X 0.00 1.60 400.0 2458690.0")
+        );
+    }
+
+    #[test]
+    fn test_parse_doubles() {
+        let mut stars = parse_stars("65378 3.508 0.958 2.23\n91262 4.874 0.677 0.03\n").unwrap();
+        parse_doubles("65378 14.4 152.0 3.88\n91262\n", &mut stars).unwrap();
+        assert!(stars.iter().all(|star| star.double));
+        assert!(stars[0].companion.is_some() && stars[1].companion.is_none());
+
+        let error = parse_doubles("65378 14.4 152.0\n", &mut stars).err();
+        assert_eq!(error.as_deref(), Some("no magnitude in 65378 14.4 152.0"));
+        let error = parse_doubles("Mizar 14.4 152.0 3.88\n", &mut stars).err();
+        assert_eq!(error.as_deref(), Some("invalid hip Mizar in Mizar 14.4 152.0 3.88"));
+    }
+
+    #[test]
+    fn test_parse_designations() {
+        let mut stars = parse_stars("91262 4.874 0.677 0.03\n").unwrap();
+        parse_designations("\n 91262 α Lyr\n", &mut stars).unwrap();
+        assert_eq!(stars[0].designation.as_deref(), Some("α Lyr"));
+
+        let error = parse_designations("91262\n", &mut stars).err();
+        assert_eq!(error.as_deref(), Some("no designation in 91262"));
+        let error = parse_designations("Vega α Lyr\n", &mut stars).err();
+        assert_eq!(error.as_deref(), Some("invalid hip Vega in Vega α Lyr"));
+    }
+
+    #[test]
+    fn test_parse_colors() {
+        let mut stars = parse_stars("32349 1.767 -0.291 -1.44\n91262 4.874 0.677 0.03\n").unwrap();
+        parse_colors("32349 0.01 A1V\n91262 - -\n", &mut stars).unwrap();
+        assert_eq!(stars[0].color_index, Some(0.01));
+        assert_eq!(stars[0].spectral_type.as_deref(), Some("A1V"));
+        assert!(stars[1].color_index.is_none() && stars[1].spectral_type.is_none());
+
+        let error = parse_colors("32349 white A1V\n", &mut stars).err();
+        assert_eq!(error.as_deref(), Some("invalid color index white in 32349 white A1V"));
+    }
+
+    #[test]
+    fn test_parse_stars() {
+        let content = "32349 1.767 -0.291 -1.44 Sirius\n\n677 0.036 0.508 2.07\n  \n91262 4.874 0.677 0.03 Vega\n";
+        let stars = parse_stars(content).unwrap();
+        assert_eq!(stars.len(), 3);
+        assert_eq!(stars[0].name.as_deref(), Some("Sirius"));
+        assert_eq!(stars[2].hip, 91262);
+        assert_eq!(stars[2].epoch, J2000);
+
+        let error = parse_stars("677 0.036 0.508\n").err();
+        assert_eq!(error.as_deref(), Some("no magnitude in 677 0.036 0.508"));
+        let error = parse_stars("677 0.036 north 2.07\n").err();
+        assert_eq!(
+            error.as_deref(),
+            Some("invalid declination north in 677 0.036 north 2.07")
+        );
+    }
+}
@@ -1,61 +1,513 @@
-mod astro;
-mod math;
+mod bookmarks;
+mod boundaries;
+mod color;
+mod conditions;
+mod config;
+mod format;
+mod json;
+mod keys;
 mod painter;
+mod panel;
+mod server;
+mod session;
+mod textures;
+mod theme;
+mod trails;
 
 use std::cmp::min;
+use std::collections::VecDeque;
 use std::env;
 use std::f64::consts::PI;
-use std::fs::read_to_string;
+use std::fmt;
+use std::fs::{self, read_to_string};
+use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::process;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
-use astro::Engine;
-use chrono::Utc;
-use math::{circle_from_three_points, stereographic_projection};
-use painter::Painter;
+use antikythera::astro::{
+    find_eclipses, find_greatest_elongations, find_moon_crossing, find_sun_crossing, find_sun_longitude, get_airmass,
+    get_day_length, get_moon_azimuths, get_moon_passage, get_moon_window, get_planet_passage, get_planet_window,
+    get_star_window, get_sun_azimuths, get_sun_passage, is_circumpolar, refract, unrefract, Calibration, DarkWindow,
+    Engine, Passage, World, WorldId, CROSSING_SEARCH_DAYS, EARTH, LIGHT_TIME_PER_AU, WORLDS,
+};
+use antikythera::math::{
+    angular_distance, circle_from_three_points, great_circle_points, orthographic_projection, stereographic_projection,
+    Projection,
+};
+use antikythera::{
+    parse_planets, parse_stars, read_colors, read_designations, read_doubles, read_variables, sort_by_brightness,
+    Planet, SkyObject, Star,
+};
+use bookmarks::Bookmark;
+use boundaries::Boundary;
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use color::star_tint;
+use conditions::Conditions;
+use config::Config;
+use format::Notation;
+use json::Json;
+use keys::{Action, Bindings};
+use painter::{text_box, Glyph, Painter, Quality, Screen, SvgPainter};
+use panel::Field;
 use sdl2::event::{Event, WindowEvent};
 use sdl2::gfx::primitives::DrawRenderer;
-use sdl2::image::LoadTexture;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::MouseButton;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::render::{Texture, TextureCreator};
 use sdl2::ttf;
 use sdl2::ttf::Font;
+use server::Command;
+use session::Session;
+use textures::Textures;
+use theme::{mix_color, redden, Theme, PRESETS};
+use trails::Trails;
 
-pub struct Star {
-    name: Option<String>,
-    ascension: f64,
-    declination: f64,
-    magnitude: f64,
+struct Step {
+    name: &'static str,
+    value: i32,
 }
 
-pub struct Planet<'a> {
-    name: String,
-    semimajor: f64,
-    sidereal: f64,
-    phase: f64,
-    inclination: f64,
-    incl_phase: f64,
-    texture: Option<Texture<'a>>,
+#[derive(Clone, Copy)]
+struct View {
+    zoom: f64,
+    pan: (f64, f64),
+    flipped: bool,
+    projection: Projection,
 }
 
-struct Step {
-    name: &'static str,
-    value: i32,
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            pan: (0.0, 0.0),
+            flipped: false,
+            projection: Projection::Stereographic,
+        }
+    }
+}
+
+// the azimuthal grid, diameters through the zenith and circles of altitude
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Grid {
+    spokes: u32,  // diameters, evenly spread in azimuth
+    spacing: u32, // degrees between the altitude circles
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self {
+            spokes: GRID_SPOKES,
+            spacing: GRID_SPACING,
+        }
+    }
+}
+
+// how a pane is divided, the dome above the panel and inset from the pane's edge, in pixels
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Layout {
+    panel: u32,  // height of the panel under the dome
+    margin: u32, // between the horizon and the edge of the pane
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            panel: PANEL_SIZE,
+            margin: DOME_MARGIN,
+        }
+    }
+}
+
+impl View {
+    fn centered_on(&self, alt: f64, az: f64) -> Self {
+        let (x, y) = self.projection.project(alt, az);
+        Self {
+            zoom: self.zoom.max(GOTO_ZOOM),
+            pan: (x, y),
+            ..*self
+        }
+    }
+
+    fn with_projection(&self, projection: Projection) -> Self {
+        // looking at the same point of the sky as before
+        let (alt, az) = self.projection.unproject(self.pan.0, self.pan.1);
+        Self {
+            pan: projection.project(alt, az),
+            projection,
+            ..*self
+        }
+    }
+
+    fn zoom_by(&mut self, factor: f64) {
+        self.zoom = (self.zoom * factor).clamp(1.0, MAX_ZOOM);
+    }
+
+    fn drag(&mut self, dx: i32, dy: i32, size: (u32, u32), layout: Layout) {
+        let scale = canvas_scale(size, layout, self);
+        let dx = dx as f64 / scale * if self.flipped { -1.0 } else { 1.0 };
+        let (x, y) = (self.pan.0 + dx, self.pan.1 + dy as f64 / scale);
+        // keep the horizon in reach
+        let r = x.hypot(y).max(1.0);
+        self.pan = (x / r, y / r);
+    }
+}
+
+#[derive(Clone)]
+struct Flight {
+    from: View,
+    to: View,
+    start: Instant,
+}
+
+impl Flight {
+    fn view_at(&self, elapsed: f64) -> Option<View> {
+        // none once the flight is over
+        let t = elapsed / GOTO_DURATION;
+        if t >= 1.0 {
+            return None;
+        }
+        let eased = t * t * (3.0 - 2.0 * t);
+        let lerp = |a: f64, b: f64| a + (b - a) * eased;
+        Some(View {
+            // zooming geometrically feels even throughout
+            zoom: lerp(self.from.zoom.ln(), self.to.zoom.ln()).exp(),
+            pan: (
+                lerp(self.from.pan.0, self.to.pan.0),
+                lerp(self.from.pan.1, self.to.pan.1),
+            ),
+            flipped: self.to.flipped,
+            projection: self.to.projection,
+        })
+    }
+}
+
+#[derive(Clone)]
+struct LatitudeTrip {
+    from: f64,
+    to: f64,
+    start: Instant,
+}
+
+impl LatitudeTrip {
+    fn latitude_at(&self, elapsed: f64) -> Option<f64> {
+        // none once the trip is over
+        let t = elapsed / POLE_TRIP_DURATION;
+        if t >= 1.0 {
+            return None;
+        }
+        let eased = t * t * (3.0 - 2.0 * t);
+        Some(self.from + (self.to - self.from) * eased)
+    }
+}
+
+#[derive(Clone)]
+struct Pane {
+    latitude: f64,
+    longitude: f64,
+    time: DateTime<Utc>,
+    step: usize,
+    view: View,
+    flight: Option<Flight>,
+    trip: Option<LatitudeTrip>,
+    home_latitude: Option<f64>, // to return to from the pole
+    world: usize,               // index into WORLDS
+    trails: Trails,
+}
+
+impl Pane {
+    fn engine(&self, calibration: Calibration) -> Engine {
+        Engine::calibrated(
+            &WORLDS[self.world],
+            self.time,
+            self.latitude,
+            self.longitude,
+            calibration,
+        )
+    }
+}
+
+#[derive(Clone)]
+struct Display {
+    aberration: bool,
+    moonlight: bool,
+    double_markers: bool,
+    label_mode: LabelMode,
+    label_magnitude: f64,           // stars fainter than this go unlabeled at the default zoom
+    leaders: bool,                  // labels in the way move aside on a leader line
+    conditions: Option<Conditions>, // none leaves every star to the limiting magnitude alike
+    notation: Notation,             // of the coordinates shown
+    glyph: Glyph,                   // the mark drawn for each star
+    galactic_grid: bool,
+    boundaries: bool,
+    hour_ring: bool,
+    rise_points: bool, // where the Sun and the Moon rise and set today
+    compass: bool,
+    stats: bool, // counts of what the last frame drew
+    lunar_nodes: bool,
+    trails: bool,
+    info_panel: bool,
+    fps_overlay: bool,
+    crosshair: bool,
+    day_length_chart: bool,
+    twinkle: bool,
+    seeing: u32,             // none gives the exact positions
+    highlight_altitude: u32, // degrees, objects higher up are ringed, none for no rings
+    glow: bool,
+    atmosphere: bool, // reddening and the other effects of the air
+    zodiacal_light: bool,
+    refraction: bool,
+    gradient: bool, // a lighter dome towards the horizon, for looks
+    minimap: bool,
+    libration: bool,
+    time_display: TimeDisplay,
+}
+
+impl Display {
+    fn new(label_magnitude: f64, notation: Notation, glyph: Glyph) -> Self {
+        Self {
+            aberration: false,
+            moonlight: false,
+            double_markers: true,
+            label_mode: LabelMode::Proper,
+            label_magnitude,
+            leaders: false,
+            conditions: None,
+            notation,
+            glyph,
+            galactic_grid: false,
+            boundaries: false,
+            hour_ring: false,
+            rise_points: false,
+            compass: false,
+            stats: false,
+            lunar_nodes: false,
+            trails: false,
+            info_panel: true,
+            fps_overlay: false,
+            crosshair: false,
+            day_length_chart: false,
+            twinkle: false,
+            seeing: 0,
+            highlight_altitude: 0,
+            glow: true,
+            atmosphere: false,
+            zodiacal_light: false,
+            refraction: false,
+            gradient: false,
+            minimap: false,
+            libration: false,
+            time_display: TimeDisplay::Clock,
+        }
+    }
+}
+
+struct Scene<'s, 't> {
+    stars: &'s [Star],
+    planets: &'s [Planet],
+    boundaries: &'s [Boundary],
+    grid: Grid,
+    layout: Layout,
+    planet_textures: &'s [Option<Rc<Texture<'t>>>],
+    moon_phases: &'s [Rc<Texture<'t>>],
+    font: &'s Font<'s, 's>,
+    small_font: &'s Font<'s, 's>,
+    theme: &'s Theme,
+    selected: Option<SkyObject>,
+    clock: f64, // wall clock seconds, drives effects that run even while time is paused
+}
+
+// what the last frame of the focused dome went through, the stars bright enough to be considered at all
+#[derive(Default)]
+struct FrameStats {
+    stars: usize,
+    stars_above: usize,
+    stars_drawn: usize,
+    planets: usize,
+    planets_above: usize,
+}
+
+fn format_stats(stats: &FrameStats) -> [String; 2] {
+    [
+        format!(
+            "Stars: {} up, {} drawn, {} culled",
+            stats.stars_above,
+            stats.stars_drawn,
+            stats.stars - stats.stars_drawn
+        ),
+        format!("Planets: {} up of {}", stats.planets_above, stats.planets),
+    ]
+}
+
+struct FrameTimes {
+    times: VecDeque<f64>,
+}
+
+impl FrameTimes {
+    fn new() -> Self {
+        Self {
+            times: VecDeque::with_capacity(FRAME_WINDOW),
+        }
+    }
+
+    fn push(&mut self, seconds: f64) {
+        if self.times.len() == FRAME_WINDOW {
+            self.times.pop_front();
+        }
+        self.times.push_back(seconds);
+    }
+
+    fn average(&self) -> f64 {
+        self.times.iter().sum::<f64>() / self.times.len().max(1) as f64
+    }
+}
+
+#[derive(Clone, Copy)]
+enum LabelMode {
+    Proper,
+    Designation,
+    None,
+}
+
+#[derive(Clone, Copy)]
+enum TimeDisplay {
+    Clock,
+    Solar,
+    Sidereal,
 }
 
 enum Mode {
     Default,
     SetLatitude,
     SetLongitude,
+    SetBookmark,
+    Bookmarks,
+    SetEclipseRange,
+    Ruler,
+    Scrub(f64), // the ecliptic longitude the Sun is being dragged to
 }
 
+const CONFIG_FILE: &str = "antikythera.conf";
+const BOOKMARKS_FILE: &str = "antikythera.bookmarks";
+const SESSION_FILE: &str = "antikythera.session";
+const CHART_FILE: &str = "antikythera-%Y%m%d-%H%M%S.svg"; // named after the time shown
+const BOOKMARK_SLOTS: usize = 9; // recalled with the number keys
 const LAT: f64 = 51.477 / 180.0 * PI; // greenwich
 const LON: f64 = 0.0; // greenwich
 const INITIAL_SIZE: u32 = 960;
 const PANEL_SIZE: u32 = 30;
+const MIN_SIZE: u32 = 200;
+const MAX_SIZE: u32 = 16384; // the largest texture most renderers take
+const GRID_SPOKES: u32 = 12;
+const GRID_SPACING: u32 = 20; // degrees
+const DOME_MARGIN: u32 = 8; // pixels between the horizon and the edge of a pane, room for the labels there
+const DEFAULT_FPS: f64 = 60.0;
+const IDLE_FPS: f64 = 4.0; // while the window is in the background
 const STAR_LIMIT: usize = 2000;
+const RESOURCES_DIR: &str = "resources"; // without the variable, relative to the project root
+const STARS_FILE: &str = "data/hip2.dat";
+const PLANETS_FILE: &str = "data/planets.dat";
+const FONT_FILE: &str = "fonts/NotoSansMono-Light.ttf";
+const TEXTURES_DIR: &str = "textures";
+const TWILIGHT_DEPTH: f64 = 12.0 / 180.0 * PI;
+const LIMITING_MAGNITUDE: f64 = 6.0;
+const LABEL_MAGNITUDE: f64 = 2.5;
+const CIRCUMPOLAR_MAGNITUDE: f64 = 2.5; // faintest star listed as never setting
+const LABEL_MAGNITUDE_STEP: f64 = 0.5;
+const LABEL_ZOOM_RELAX: f64 = 1.0; // magnitudes per doubling of the zoom
+const DOUBLE_SPLIT: f64 = 4.0; // pixels between components before a pair is drawn split
+const MOONLIGHT_SKY: f64 = 1.5; // magnitudes lost over the whole sky under a full Moon at zenith
+const MOONLIGHT_HALO: f64 = 2.5; // additional magnitudes lost next to the Moon
+const MOONLIGHT_SPREAD: f64 = 20.0 / 180.0 * PI;
+const FRAME_WINDOW: usize = 60;
+const ELONGATION_SEARCH_DAYS: i64 = 730;
+const ECLIPSE_SEARCH_YEARS: i64 = 100; // the search runs within a frame, a few milliseconds a year
+const MESSAGE_DURATION: Duration = Duration::from_secs(5);
+const CULMINATION_FLASH: Duration = Duration::from_secs(3);
+const CULMINATION_MARKER: i16 = 16; // radius in pixels
+const NUDGE_STEP: f64 = 1.0 / 180.0 * PI; // ctrl and an arrow key
+const FINE_NUDGE_STEP: f64 = 0.1 / 180.0 * PI; // with shift as well
+const PICK_RADIUS: f64 = 10.0;
+const CARDINAL_INSET: f64 = 12.0;
+const HOUR_TICK_LENGTH: f64 = 8.0; // pixels inwards from the horizon
+const HOUR_LABEL_INSET: f64 = 28.0;
+const RISE_TICK_LENGTH: f64 = 16.0;
+const RISE_LABEL_INSET: f64 = 44.0; // clear of the hour ring labels
+const MAX_ZOOM: f64 = 20.0;
+const GOTO_ZOOM: f64 = 4.0; // least zoom after going to an object
+const GOTO_DURATION: f64 = 1.0; // seconds
+const POLE_TRIP_DURATION: f64 = 8.0; // seconds, slow enough to watch the sky tilt
+const ZOOM_STEP: f64 = 1.25;
+const DRAG_THRESHOLD: i32 = 3;
+const DISK_MAGNIFICATION: f64 = 12.0; // of the Sun and the Moon alike, until zoomed in that far
+const SUN_LIMB: f64 = 0.85; // brightness of the darkened rim against the core
+const CORONA_GLARE: f64 = 0.3; // corona strength with the disk uncovered
+const ECLIPTIC_SAMPLES: usize = 180; // along the curve when it isn't a circle
+const GRADIENT_HORIZON: f64 = 0.6; // of the way from the dome to the grid color
+const OVAL_POINTS: usize = 32; // around a refraction flattened disk
+const ATMOSPHERE_RING_WIDTH: u32 = 6; // pixels
+const ATMOSPHERE_RING_ALPHA: f64 = 70.0; // at the horizon, fading inwards
+const CROSSHAIR_SIZE: i16 = 10;
+const MINIMAP_RADIUS: i16 = 60;
+const MINIMAP_MAGNITUDE: f64 = 3.0;
+const MINIMAP_OUTLINE_SAMPLES: usize = 16; // per edge of the visible region
+const GLOW_MAGNITUDE: f64 = 1.0; // fainter objects get no glow
+const GLOW_RANGE: f64 = 5.0; // magnitudes above the threshold for the full glow
+const TWINKLE_ALTITUDE: f64 = 30.0 / 180.0 * PI; // no visible scintillation above
+const TWINKLE_DEPTH: f64 = 0.5; // brightness swing at the horizon
+const BELT_DEPTH: f64 = 6.0 / 180.0 * PI; // solar depression where the belt of venus is gone
+const BELT_WIDTH: f64 = 10.0 / 180.0 * PI;
+const BELT_SPREAD: f64 = 90.0 / 180.0 * PI; // on each side of the antisolar point
+const BELT_SAMPLING: f64 = 3.0; // degrees
+const BELT_ALPHA: f64 = 90.0;
+const ZODIACAL_TWILIGHT: f64 = -12.0 / 180.0 * PI; // solar altitude where the zodiacal light starts to show
+const ZODIACAL_NIGHT: f64 = -18.0 / 180.0 * PI; // and where it is at its best
+const ZODIACAL_START: f64 = 15.0 / 180.0 * PI; // elongation, closer in it is lost in the twilight glow
+const ZODIACAL_REACH: f64 = 90.0 / 180.0 * PI; // elongation where the cone runs out
+const ZODIACAL_WIDTH: f64 = 15.0 / 180.0 * PI; // on each side of the ecliptic at the base of the cone
+const ZODIACAL_SAMPLING: f64 = 3.0; // degrees
+const ZODIACAL_ALPHA: f64 = 40.0;
+const MAX_SEEING: u32 = 4;
+const HIGHLIGHT_STEP: u32 = 10; // degrees
+const MAX_HIGHLIGHT_ALTITUDE: u32 = 80;
+const HIGHLIGHT_GAP: i16 = 4; // pixels between an object and its ring
+const LABEL_OFFSET: i16 = 5; // pixels between a star and its label
+const LEADER_LENGTH: i32 = 12; // pixels from a star to a label moved aside
+const SCRUB_STEP: f64 = 0.5 / 180.0 * PI; // of ecliptic longitude per pixel dragged, about half a day
+const ZODIAC: [&str; 12] = [
+    "Aries",
+    "Taurus",
+    "Gemini",
+    "Cancer",
+    "Leo",
+    "Virgo",
+    "Libra",
+    "Scorpius",
+    "Sagittarius",
+    "Capricornus",
+    "Aquarius",
+    "Pisces",
+];
+const SEEING_JITTER: f64 = 0.6; // pixels of wander per step of seeing at the zenith
+const SEEING_BLUR: f64 = 0.2; // glow strength per step of seeing
+const PLANET_TWINKLE_ZOOM: f64 = 2.0; // planets stop twinkling once zoomed into disks
+const PLANET_TWINKLE_DEPTH: f64 = 0.1;
+const TELESCOPIC_DIM: f64 = 0.5; // brightness of the outline drawn for planets past the naked-eye limit
+const TELESCOPIC_NOTE: &str = "Too faint for the naked eye, needs optics";
+const TWINKLE_RATE: f64 = 12.0; // radians per second
+const CHART_WIDTH: i16 = 364;
+const CHART_HEIGHT: i16 = 144;
+const DAY_LENGTH_SAMPLES: i64 = 53;
+const INFO_PANEL_WIDTH: i16 = 260;
+const SUN_MAGNITUDE: f64 = -26.74;
+const TRAIL_LENGTH: usize = 300; // positions kept behind each moving object
+const TRAIL_ALPHA: f64 = 160.0; // of the latest position, older ones fade out
+const RULER_SAMPLES: usize = 90; // segments of the measured arc
+const GRID_SAMPLING: f64 = 2.0; // degrees between the points of projected grid lines
+const NEUTRAL_STEP: usize = 5;
 const STEPS: [Step; 11] = [
     Step {
         name: "-1 month",
@@ -82,126 +534,225 @@ const STEPS: [Step; 11] = [
         value: 1,
     },
     Step {
-        name: "10 minutes",
+        name: "+10 minutes",
         value: 600,
     },
     Step {
-        name: "1 hour",
+        name: "+1 hour",
         value: 3600,
     },
     Step {
-        name: "6 hours",
+        name: "+6 hours",
         value: 21600,
     },
     Step {
-        name: "1 day",
+        name: "+1 day",
         value: 86164,
     },
     Step {
-        name: "1 month",
+        name: "+1 month",
         value: 2360584,
     },
 ];
 
-fn read_stars(filename: &str, limit: usize) -> Vec<Star> {
-    let mut stars = Vec::new();
-    for line in read_to_string(filename)
-        .unwrap_or_else(|_| panic!("Couldn't find {}", filename))
-        .lines()
-    {
-        let mut parts = line.split_whitespace();
-        let _ = parts.next();
-        let ascension = parts.next().unwrap().parse::<f64>().unwrap();
-        let declination = parts.next().unwrap().parse::<f64>().unwrap();
-        let magnitude = parts.next().unwrap().parse::<f64>().unwrap();
-        let name = parts.next();
-        stars.push(Star {
-            name: name.map(|name| name.to_string()),
-            ascension,
-            declination,
-            magnitude,
-        });
-        if stars.len() >= limit {
-            break;
+fn advance_time(time: DateTime<Utc>, elapsed: Duration, rate: i32) -> DateTime<Utc> {
+    // in whole nanoseconds, so the real time step keeps in lockstep with the clock however the frames fall
+    let nanoseconds = (elapsed.as_nanos() as i64).saturating_mul(rate.into());
+    time + chrono::Duration::nanoseconds(nanoseconds)
+}
+
+fn crossed_meridian(previous: f64, current: f64) -> bool {
+    // hour angles of two frames on either side of zero, not of the lower meridian behind the pole,
+    // with time running either way
+    previous.signum() != current.signum() && (current - previous).abs() < PI
+}
+
+fn reverse_step(step: usize) -> usize {
+    // the same speed the other way, STEPS is symmetric around the neutral step
+    STEPS.len() - 1 - step
+}
+
+fn missing_resources(resources_path: &Path) -> Vec<&'static str> {
+    // the files nothing can be drawn without, the rest is optional or has a fallback
+    [STARS_FILE, PLANETS_FILE, FONT_FILE]
+        .into_iter()
+        .filter(|file| !resources_path.join(file).is_file())
+        .collect()
+}
+
+fn find_resources() -> PathBuf {
+    // checked once up front, so a misplaced directory is reported as a whole rather than by the first loader to trip
+    let (path, origin) = match env::var_os("RESOURCES_DIR") {
+        Some(path) => (PathBuf::from(path), "RESOURCES_DIR"),
+        None => (PathBuf::from(RESOURCES_DIR), "the working directory"),
+    };
+    let missing = missing_resources(&path);
+    if !missing.is_empty() {
+        eprintln!(
+            "The resources in {} from {} are incomplete, missing:",
+            path.display(),
+            origin
+        );
+        for file in missing {
+            eprintln!("  {}", file);
         }
+        eprintln!("Set RESOURCES_DIR to the resources directory of the project or run from the project root");
+        process::exit(1);
+    }
+    if !path.join(TEXTURES_DIR).is_dir() {
+        eprintln!(
+            "No {} in {}, the planets and the Moon are drawn as plain disks",
+            TEXTURES_DIR,
+            path.display()
+        );
     }
-    stars
+    path
 }
 
-fn read_planets<'a, T>(
-    texture_creator: &'a TextureCreator<T>,
-    filename: &str,
+fn load_stars(resources_path: &Path) -> Result<Vec<Star>, String> {
+    // the catalog with what the optional files add to it, brightest first
+    let path = resources_path.join(STARS_FILE);
+    let content = read_to_string(&path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    let mut stars = parse_stars(&content).map_err(|error| format!("{}: {}", path.display(), error))?;
+    read_variables(&resources_path.join("data/variables.dat"), &mut stars)?;
+    read_doubles(&resources_path.join("data/doubles.dat"), &mut stars)?;
+    read_designations(&resources_path.join("data/designations.dat"), &mut stars)?;
+    read_colors(&resources_path.join("data/colors.dat"), &mut stars)?;
+    sort_by_brightness(&mut stars);
+    // cut by the peak magnitudes, so a variable that brightens past the faintest kept star isn't lost
+    stars.truncate(STAR_LIMIT);
+    Ok(stars)
+}
+
+fn load_planets(resources_path: &Path) -> Result<Vec<Planet>, String> {
+    let path = resources_path.join(PLANETS_FILE);
+    let content = read_to_string(&path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    parse_planets(&content).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+fn load_planet_textures<'a, T>(
+    textures: &mut Textures<'a, T>,
+    planets: &[Planet],
     resources_path: &Path,
-) -> Vec<Planet<'a>> {
-    let mut planets = Vec::new();
-    for line in read_to_string(filename).expect("Couldn't find planets.dat").lines() {
-        let mut parts = line.split_whitespace();
-        let name = parts.next().unwrap().to_string();
-        let semimajor = parts.next().unwrap().parse::<f64>().unwrap();
-        let sidereal = parts.next().unwrap().parse::<f64>().unwrap();
-        let phase = parts.next().unwrap().parse::<f64>().unwrap();
-        let inclination = parts.next().unwrap().parse::<f64>().unwrap();
-        let incl_phase = parts.next().unwrap().parse::<f64>().unwrap();
-        let texture = parts.next().unwrap();
-        planets.push(Planet {
-            name,
-            semimajor,
-            sidereal,
-            phase,
-            inclination: inclination / 180.0 * PI,
-            incl_phase,
-            texture: match texture {
-                "null" => None,
-                _ => Some(
-                    texture_creator
-                        .load_texture(resources_path.join(format!("textures/{}", texture)).to_str().unwrap())
-                        .unwrap_or_else(|_| panic!("Couldn't find {}", texture)),
-                ),
-            },
-        });
-    }
+) -> Vec<Option<Rc<Texture<'a>>>> {
+    // planets without a texture are drawn as disks in the theme's color
     planets
+        .iter()
+        .map(|planet| {
+            planet
+                .texture
+                .as_ref()
+                .map(|texture| textures.load(&resources_path.join(TEXTURES_DIR).join(texture)))
+        })
+        .collect()
 }
 
-fn load_moon_phases<'a, T>(texture_creator: &'a TextureCreator<T>, resources_path: &Path) -> Vec<Texture<'a>> {
-    let mut moon_phases = Vec::new();
-    for i in 0..24 {
-        moon_phases.push(
-            texture_creator
-                .load_texture(
-                    resources_path
-                        .join(format!("textures/moon_phases/{:02}.png", i))
-                        .to_str()
-                        .unwrap(),
-                )
-                .expect("Couldn't find textures in moon_phases/"),
-        );
-    }
-    moon_phases
+fn load_moon_phases<'a, T>(textures: &mut Textures<'a, T>, resources_path: &Path) -> Vec<Rc<Texture<'a>>> {
+    (0..24)
+        .map(|i| {
+            textures.load(
+                &resources_path
+                    .join(TEXTURES_DIR)
+                    .join(format!("moon_phases/{:02}.png", i)),
+            )
+        })
+        .collect()
 }
 
-fn stereo_to_canvas(x: f64, y: f64, size: (u32, u32)) -> (i16, i16) {
-    let r = x.hypot(y);
+fn load_font<'a>(ttf_context: &'a ttf::Sdl2TtfContext, resources_path: &Path, size: u16) -> Font<'a, 'a> {
+    let path = resources_path.join(FONT_FILE);
+    ttf_context
+        .load_font(&path, size)
+        .unwrap_or_else(|error| startup_error(&format!("Could not load {}", path.display()), error))
+}
+
+fn startup_error(message: &str, error: impl fmt::Display) -> ! {
+    // what went wrong in words instead of a panic, it is usually the environment rather than a bug
+    eprintln!("{}: {}", message, error);
+    process::exit(1)
+}
+
+fn stereo_to_canvas_exact(x: f64, y: f64, size: (u32, u32), layout: Layout, view: &View) -> (f64, f64) {
+    let x = (x - view.pan.0) * view.zoom * if view.flipped { -1.0 } else { 1.0 };
+    let y = (y - view.pan.1) * view.zoom;
+    let (width, height) = dome_area(size, layout);
+    let radius = dome_radius(size, layout);
+    ((width / 2) as f64 - radius * x, (height / 2) as f64 - radius * y)
+}
+
+fn stereo_to_canvas(x: f64, y: f64, size: (u32, u32), layout: Layout, view: &View) -> (i16, i16) {
+    let r = (x - view.pan.0).hypot(y - view.pan.1) * view.zoom;
     if r < 30.0 {
-        let msize = min(size.0, size.1 - PANEL_SIZE);
-        let x = i16::try_from(size.0).unwrap() / 2 - (msize as f64 / 2.0 * x).round() as i16;
-        let y = i16::try_from(size.1 - PANEL_SIZE).unwrap() / 2 - (msize as f64 / 2.0 * y).round() as i16;
-        (x, y)
+        let (x, y) = stereo_to_canvas_exact(x, y, size, layout, view);
+        (x.round() as i16, y.round() as i16)
     } else {
         (-1, -1)
     }
 }
 
-fn horizontal_to_canvas(alt: f64, az: f64, size: (u32, u32)) -> (i16, i16) {
-    let (x, y) = stereographic_projection(alt, az);
+fn horizontal_to_canvas(alt: f64, az: f64, size: (u32, u32), layout: Layout, view: &View) -> (i16, i16) {
+    let (x, y) = view.projection.project(alt, az);
     let r = x.hypot(y);
     if r < 30.0 {
-        stereo_to_canvas(x, y, size)
+        stereo_to_canvas(x, y, size, layout, view)
     } else {
         (-1, -1)
     }
 }
 
+fn canvas_to_stereo(x: f64, y: f64, size: (u32, u32), layout: Layout, view: &View) -> (f64, f64) {
+    let scale = canvas_scale(size, layout, view);
+    let (width, height) = dome_area(size, layout);
+    let x = ((width / 2) as f64 - x) / scale * if view.flipped { -1.0 } else { 1.0 };
+    let y = ((height / 2) as f64 - y) / scale;
+    (x + view.pan.0, y + view.pan.1)
+}
+
+fn canvas_to_horizontal(x: f64, y: f64, size: (u32, u32), layout: Layout, view: &View) -> Option<(f64, f64)> {
+    // the inverse of horizontal_to_canvas, none beyond the horizon
+    let (x, y) = canvas_to_stereo(x, y, size, layout, view);
+    (x.hypot(y) <= 1.0).then(|| view.projection.unproject(x, y))
+}
+
+fn canvas_scale(size: (u32, u32), layout: Layout, view: &View) -> f64 {
+    // pixels per unit of the projected plane, the horizon has a radius of one unit
+    dome_radius(size, layout) * view.zoom
+}
+
+fn dome_radius(size: (u32, u32), layout: Layout) -> f64 {
+    // of the horizon at no zoom, inset by the margin as far as the pane allows
+    let (width, height) = dome_area(size, layout);
+    let half = min(width, height) as f64 / 2.0;
+    (half - layout.margin as f64).max(half / 2.0)
+}
+
+fn dome_area(size: (u32, u32), layout: Layout) -> (u32, u32) {
+    // the part of a pane above the panel, never empty so the projection stays finite
+    (size.0.max(1), size.1.saturating_sub(layout.panel).max(1))
+}
+
+fn circumpolar_names(engine: &Engine, stars: &[Star], planets: &[Planet], latitude: f64) -> Vec<String> {
+    // the bright named stars and the planets that never set, by the declinations of the Earth's sky
+    if !engine.world().is_earth() {
+        return Vec::new();
+    }
+    let stars = visible_stars(stars, CIRCUMPOLAR_MAGNITUDE)
+        .iter()
+        .filter(|star| is_circumpolar(engine.get_star_equatorial(star).1, latitude))
+        .filter_map(|star| star.name.clone());
+    let planets = planets
+        .iter()
+        .filter(|planet| !engine.is_observer(planet))
+        .filter(|planet| is_circumpolar(engine.get_planet_equatorial(planet).1, latitude))
+        .map(|planet| planet.name.clone());
+    planets.chain(stars).collect()
+}
+
+fn visible_stars(stars: &[Star], limit: f64) -> &[Star] {
+    // the stars are sorted by peak brightness, the faint rest costs nothing
+    &stars[..stars.partition_point(|star| star.peak_magnitude() <= limit)]
+}
+
 fn magnitude_to_size_and_brightness(magnitude: f64) -> (i16, u8) {
     if magnitude < -0.2 {
         (5, 255)
@@ -220,273 +771,2888 @@ fn magnitude_to_size_and_brightness(magnitude: f64) -> (i16, u8) {
     }
 }
 
-fn render_text<'a, T>(
-    font: &'a Font<'a, 'a>,
-    texture_creator: &'a TextureCreator<T>,
-    text: &'a str,
-) -> (Texture<'a>, u32, u32) {
-    let texture = font
-        .render(text)
-        .blended(Color::RGB(0, 255, 0))
-        .unwrap()
-        .as_texture(texture_creator)
-        .unwrap();
-    let (x, y) = font.size_of(text).unwrap();
-    (texture, x, y)
+fn daylight_factor(sun_alt: f64) -> f64 {
+    // stars fade in linearly between sunset and the end of nautical twilight
+    (-sun_alt / TWILIGHT_DEPTH).clamp(0.0, 1.0)
 }
 
-fn main() {
-    let resources_path = PathBuf::from(env::var("RESOURCES_DIR").expect("RESOURCES_DIR not set"));
-    let sdl_context = sdl2::init().unwrap();
-    let video_subsystem = sdl_context.video().unwrap();
+fn moonlight_extinction(moon_alt: f64, lunar_phase: f64, distance: f64) -> f64 {
+    if moon_alt <= 0.0 {
+        return 0.0;
+    }
+    let illumination = (1.0 - lunar_phase.cos()) / 2.0;
+    illumination * moon_alt.sin() * (MOONLIGHT_SKY + MOONLIGHT_HALO * (-distance / MOONLIGHT_SPREAD).exp())
+}
 
-    let window = video_subsystem
-        .window("Antikythera", INITIAL_SIZE, INITIAL_SIZE + PANEL_SIZE)
-        .resizable()
-        .position_centered()
-        .build()
-        .unwrap();
+fn moon_phase_frame(lunar_phase: f64, frames: usize) -> usize {
+    // frame i shows the Moon i / frames of a lunation after new, the waxing half lit on the right and turned towards
+    // the Sun along with the waning half by the moon angle; the nearest frame, the last half frame wraps to new
+    let position = lunar_phase.rem_euclid(2.0 * PI) / (2.0 * PI) * frames as f64;
+    position.round() as usize % frames
+}
 
-    let mut canvas = window.into_canvas().build().unwrap();
+fn planet_sprite_size(name: &str, zoom: f64) -> (i16, i16) {
+    // grows with the zoom so the disks are resolved, saturn is wider for its rings
+    let (width, height) = if name == "Saturn" { (35.0, 14.0) } else { (16.0, 16.0) };
+    ((width * zoom).round() as i16, (height * zoom).round() as i16)
+}
 
-    let stars = read_stars(resources_path.join("data/hip2.dat").to_str().unwrap(), STAR_LIMIT);
-    let texture_creator = canvas.texture_creator();
-    let moon_phases = load_moon_phases(&texture_creator, &resources_path);
-    let planets = read_planets(
-        &texture_creator,
-        resources_path.join("data/planets.dat").to_str().unwrap(),
-        &resources_path,
-    );
-    let ttf_context = ttf::init().unwrap();
-    let font = ttf_context
-        .load_font(
-            resources_path.join("fonts/NotoSansMono-Light.ttf").to_str().unwrap(),
-            20,
-        )
-        .expect("Couldn't find NotoSansMono-Light.ttf");
-    let small_font = ttf_context
-        .load_font(
-            resources_path.join("fonts/NotoSansMono-Light.ttf").to_str().unwrap(),
-            14,
-        )
-        .expect("Couldn't find NotoSansMono-Light.ttf");
+fn disk_size(angular_radius: f64, alt: f64, size: (u32, u32), layout: Layout, view: &View) -> i16 {
+    // radius in pixels, the stereographic projection stretches things twice as much at the horizon as at the zenith
+    let magnification = (DISK_MAGNIFICATION / view.zoom).max(1.0);
+    let radius = angular_radius * view.projection.magnification(alt) * canvas_scale(size, layout, view) * magnification;
+    (radius.round() as i16).max(1)
+}
 
-    canvas
-        .set_logical_size(INITIAL_SIZE, INITIAL_SIZE + PANEL_SIZE)
-        .unwrap();
-    let mut event_pump = sdl_context.event_pump().unwrap();
+fn label_limit(label_magnitude: f64, zoom: f64) -> f64 {
+    // zooming in spreads the stars out, leaving room for fainter labels
+    label_magnitude + LABEL_ZOOM_RELAX * zoom.log2().max(0.0)
+}
 
-    let mut real_time = Utc::now();
-    let mut current_time = real_time;
-    let mut step = 5;
-    let mut mode = Mode::Default;
-    let mut latitude = LAT;
-    let mut longitude = LON;
-    let mut buffer = String::new();
+fn sky_conditions(display: &Display) -> (f64, f64, f64) {
+    // limiting magnitude, extinction and gamma, an ideal sky without a preset
+    match &display.conditions {
+        Some(conditions) => (conditions.limit, conditions.extinction, conditions.gamma),
+        None => (LIMITING_MAGNITUDE, 0.0, 1.0),
+    }
+}
 
-    'running: loop {
-        canvas.set_draw_color(Color::RGB(12, 12, 12));
-        canvas.clear();
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Window {
-                    win_event: WindowEvent::Resized(width, height),
-                    ..
-                } => {
-                    canvas.set_logical_size(width as u32, height as u32).unwrap();
-                }
-                Event::Quit { .. } => break 'running,
-                Event::KeyDown {
-                    keycode: Some(keycode), ..
-                } => match mode {
-                    Mode::Default => match keycode {
-                        Keycode::Left => {
-                            step = if step > 0 { step - 1 } else { step };
-                        }
-                        Keycode::Right => {
-                            step = if step < STEPS.len() - 1 { step + 1 } else { step };
-                        }
-                        Keycode::A => {
-                            mode = Mode::SetLatitude;
-                            buffer = String::new();
-                        }
-                        Keycode::O => {
-                            mode = Mode::SetLongitude;
-                            buffer = String::new();
-                        }
-                        _ => {}
-                    },
-                    Mode::SetLatitude => match keycode {
-                        Keycode::Return => {
-                            if let Ok(new_latitude) = buffer.parse::<f64>() {
-                                if (-90.0..=90.0).contains(&new_latitude) {
-                                    latitude = new_latitude / 180.0 * PI
-                                }
-                            }
-                            mode = Mode::Default;
-                        }
-                        Keycode::Escape => {
-                            buffer = String::new();
-                            mode = Mode::Default;
-                        }
-                        _ => {
-                            buffer.push_str(&keycode.to_string());
-                        }
-                    },
-                    Mode::SetLongitude => match keycode {
-                        Keycode::Return => {
-                            if let Ok(new_longitude) = buffer.parse::<f64>() {
-                                if (0.0..=360.0).contains(&new_longitude) {
-                                    longitude = new_longitude / 180.0 * PI
-                                }
-                            }
-                            mode = Mode::Default;
-                        }
-                        Keycode::Escape => {
-                            buffer = String::new();
-                            mode = Mode::Default;
-                        }
-                        _ => {
-                            buffer.push_str(&keycode.to_string());
-                        }
-                    },
-                },
-                _ => {}
-            }
+fn is_telescopic(magnitude: f64, alt: f64, display: &Display) -> bool {
+    let (limit, extinction, _) = sky_conditions(display);
+    magnitude + extinction * (get_airmass(alt) - 1.0) > limit
+}
+
+fn glow_strength(magnitude: f64) -> f64 {
+    ((GLOW_MAGNITUDE - magnitude) / GLOW_RANGE).clamp(0.0, 1.0)
+}
+
+fn seed_phase(seed: u32) -> f64 {
+    // knuth's multiplicative hash spreads neighbouring catalogue numbers over the whole cycle
+    seed.wrapping_mul(2654435761) as f64 / u32::MAX as f64 * 2.0 * PI
+}
+
+fn twinkle_factor(alt: f64, seed: u32, clock: f64, depth: f64) -> f64 {
+    if alt >= TWINKLE_ALTITUDE {
+        return 1.0;
+    }
+    let phase = seed_phase(seed);
+    let strength = depth * (1.0 - alt.max(0.0) / TWINKLE_ALTITUDE);
+    let jitter = 0.6 * (TWINKLE_RATE * clock + phase).sin() + 0.4 * (2.7 * TWINKLE_RATE * clock + 2.0 * phase).sin();
+    1.0 + strength * jitter
+}
+
+fn seeing_offset(alt: f64, seed: u32, clock: f64, seeing: u32) -> (i16, i16) {
+    if seeing == 0 {
+        return (0, 0);
+    }
+    // the image wanders with the turbulence, seeing grows as the airmass to the power 0.6
+    let amplitude = seeing as f64 * SEEING_JITTER * get_airmass(alt.max(0.0)).powf(0.6);
+    let phase = seed_phase(seed);
+    let t = TWINKLE_RATE * clock;
+    let dx = 0.7 * (1.3 * t + phase).sin() + 0.3 * (3.1 * t + 2.0 * phase).sin();
+    let dy = 0.7 * (1.7 * t + 3.0 * phase).cos() + 0.3 * (2.3 * t + phase).cos();
+    ((amplitude * dx).round() as i16, (amplitude * dy).round() as i16)
+}
+
+fn pick_object(
+    engine: &Engine,
+    stars: &[Star],
+    planets: &[Planet],
+    size: (u32, u32),
+    layout: Layout,
+    view: &View,
+    (x, y): (i32, i32),
+) -> Option<SkyObject> {
+    let candidates = (0..stars.len())
+        .map(SkyObject::Star)
+        .chain(
+            (0..planets.len())
+                .filter(|&index| !engine.is_observer(&planets[index]))
+                .map(SkyObject::Planet),
+        )
+        .chain([SkyObject::Sun, SkyObject::Moon]);
+    // the click traced back onto the sky, there is nothing to pick outside the dome
+    let (alt, az) = canvas_to_horizontal(x.into(), y.into(), size, layout, view)?;
+    let (x, y) = view.projection.project(alt, az);
+    let scale = canvas_scale(size, layout, view);
+    let mut best = None;
+    let mut best_distance = PICK_RADIUS;
+    for candidate in candidates {
+        let Some((alt, az)) = engine.get_object_position(candidate, stars, planets) else {
+            continue;
+        };
+        if alt < 0.0 {
+            continue;
         }
+        let (cx, cy) = view.projection.project(alt, az);
+        let distance = (cx - x).hypot(cy - y) * scale;
+        // later candidates (planets, Sun, Moon) win ties over the stars behind them
+        if distance <= best_distance {
+            best = Some(candidate);
+            best_distance = distance;
+        }
+    }
+    best
+}
 
-        let now = Utc::now();
-        let elapsed = now - real_time;
-        real_time = now;
-        current_time += elapsed * STEPS[step].value;
-        let engine = Engine::new(current_time, latitude, longitude);
+fn cycle_selection(
+    current: Option<SkyObject>,
+    engine: &Engine,
+    planets: &[Planet],
+    backward: bool,
+) -> Option<SkyObject> {
+    // the planets, the Sun and the Moon that are up, the next one after the current selection
+    let visible: Vec<SkyObject> = (0..planets.len())
+        .filter(|&index| !engine.is_observer(&planets[index]))
+        .map(SkyObject::Planet)
+        .chain([SkyObject::Sun, SkyObject::Moon])
+        .filter(|&object| {
+            engine
+                .get_object_position(object, &[], planets)
+                .is_some_and(|(alt, _)| alt > 0.0)
+        })
+        .collect();
+    let count = visible.len();
+    let next = match visible.iter().position(|&object| Some(object) == current) {
+        Some(index) if backward => (index + count - 1) % count,
+        Some(index) => (index + 1) % count,
+        None if backward => count.checked_sub(1)?,
+        None => 0,
+    };
+    visible.get(next).copied()
+}
 
-        let (width, height) = canvas.logical_size();
-        let radius = min(width, height - PANEL_SIZE) / 2;
-        canvas.aa_filled_circle(
-            (width / 2).try_into().unwrap(),
-            ((height - PANEL_SIZE) / 2).try_into().unwrap(),
-            radius.try_into().unwrap(),
-            Color::RGB(0, 0, 0),
-        );
-
-        canvas.draw_azimuthal_grid();
-        let ecliptic_points = engine.get_ecliptic_points();
-        let ecliptic = circle_from_three_points(
-            stereographic_projection(ecliptic_points[0].0, ecliptic_points[0].1),
-            stereographic_projection(ecliptic_points[1].0, ecliptic_points[1].1),
-            stereographic_projection(ecliptic_points[2].0, ecliptic_points[2].1),
-        );
-        let (x, y) = stereo_to_canvas(ecliptic.0, ecliptic.1, canvas.logical_size());
-        let r = (radius as f64 * ecliptic.2).round() as i16;
-        // there's an issue with displaying large circles in sdl2, have to use arc instead
-        canvas.arc(x, y, r, 0, 180, Color::RGB(90, 0, 0)).unwrap();
-        canvas.arc(x, y, r, 180, 0, Color::RGB(90, 0, 0)).unwrap();
-
-        for star in &stars {
-            let (alt, az) = engine.get_star_position(star);
-            let (x, y) = horizontal_to_canvas(alt, az, canvas.logical_size());
-            let (size, brightness) = magnitude_to_size_and_brightness(star.magnitude);
-            match size {
-                0 => canvas
-                    .pixel(x, y, Color::RGB(brightness, brightness, brightness))
-                    .unwrap(),
-                _ => canvas.aa_filled_circle(x, y, size, Color::RGB(brightness, brightness, brightness)),
-            }
-            if let Some(name) = &star.name {
-                canvas.text(name, &small_font, x, y, 5)
-            }
+fn panel_field(
+    field: Field,
+    engine: &Engine,
+    pane: &Pane,
+    display: &Display,
+    center: Option<(f64, f64)>,
+    transits: &[String],
+    circumpolar: &[String],
+) -> Option<String> {
+    // none for a readout that is switched off or has nothing to say
+    let notation = display.notation;
+    match field {
+        Field::World => (!engine.world().is_earth()).then(|| engine.world().name.to_string()),
+        Field::Location => Some(format!(
+            "lat: {}; lon: {}",
+            format::degrees(pane.latitude, notation, 4),
+            format::degrees(pane.longitude, notation, 4)
+        )),
+        Field::Time => Some(engine.time.format("%Y-%b-%d %H:%M:%S %Z").to_string()),
+        Field::Step => Some(format!("Step: {}", STEPS[pane.step].name)),
+        Field::TimeDisplay => match display.time_display {
+            TimeDisplay::Clock => None,
+            TimeDisplay::Solar => Some(format!("Solar {}", format_hours(engine.get_solar_time()))),
+            TimeDisplay::Sidereal => Some(format!("Sidereal {}", format_hours(engine.get_sidereal_time()))),
+        },
+        Field::Sidereal => Some(format!("LST {}", format_hours(engine.get_sidereal_time()))),
+        Field::JulianDate => Some(format!("JD {:.5}", engine.get_julian_date())),
+        Field::MoonPhase => {
+            let (_, _, lunar_phase, _) = engine.get_moon_position();
+            let trend = if lunar_phase < PI { "waxing" } else { "waning" };
+            let illumination = (1.0 - lunar_phase.cos()) / 2.0 * 100.0;
+            Some(format!("Moon {:.0}% {}", illumination, trend))
         }
+        Field::Center => {
+            let (alt, az) = center.filter(|_| display.crosshair)?;
+            let (ascension, declination) = engine.horizontal_to_equatorial(alt, az);
+            Some(format!(
+                "Center: alt {}, az {}, RA {}, Dec {}",
+                format::degrees(alt, notation, 2),
+                format::degrees(az, notation, 2),
+                format::hours(ascension, notation, 2),
+                format::degrees(declination, notation, 2)
+            ))
+        }
+        Field::Libration => display.libration.then(|| {
+            let (longitude, latitude) = engine.get_moon_libration();
+            format!(
+                "Libration: {:+.1}, {:+.1}",
+                longitude / PI * 180.0,
+                latitude / PI * 180.0
+            )
+        }),
+        Field::Transits => (!transits.is_empty()).then(|| {
+            transits
+                .iter()
+                .map(|planet| format!("Transit of {}", planet))
+                .collect::<Vec<String>>()
+                .join("; ")
+        }),
+        Field::EclipseSeason => engine.is_eclipse_season().then(|| "Eclipse season".to_string()),
+        Field::Conditions => display
+            .conditions
+            .as_ref()
+            .map(|conditions| format!("Sky: {}", conditions.name)),
+        Field::Circumpolar => (!circumpolar.is_empty()).then(|| format!("Circumpolar: {}", circumpolar.join(", "))),
+        Field::Highlight => (display.highlight_altitude > 0).then(|| format!("Above {}°", display.highlight_altitude)),
+    }
+}
 
-        let (alt, az) = engine.get_sun_position();
-        let (x, y) = horizontal_to_canvas(alt, az, canvas.logical_size());
-        canvas.aa_filled_circle(x, y, 15, Color::RGB(255, 255, 255));
-        canvas.text("Sun", &small_font, x, y, 15);
-
-        for planet in &planets {
-            let (alt, az) = engine.get_planet_position(planet);
-            let (x, y) = horizontal_to_canvas(alt, az, canvas.logical_size());
-            let (size_x, size_y) = if planet.name == "Saturn" { (35, 14) } else { (16, 16) };
-            match planet.texture {
-                Some(_) => canvas
-                    .copy(
-                        planet.texture.as_ref().unwrap(),
-                        None,
-                        Rect::new(
-                            (x - size_x / 2).into(),
-                            (y - size_y / 2).into(),
-                            size_x.try_into().unwrap(),
-                            size_y.try_into().unwrap(),
-                        ),
-                    )
-                    .unwrap(),
-                None => canvas.aa_filled_circle(x, y, 6, Color::RGB(255, 255, 255)),
+fn selection_name(selection: SkyObject, stars: &[Star], planets: &[Planet]) -> String {
+    match selection {
+        SkyObject::Star(index) => {
+            let star = &stars[index];
+            star.name.clone().unwrap_or_else(|| format!("HIP {}", star.hip))
+        }
+        SkyObject::Planet(index) => planets[index].name.clone(),
+        SkyObject::Sun => "Sun".to_string(),
+        SkyObject::Moon => "Moon".to_string(),
+        SkyObject::DeepSky(index) => format!("Deep-sky object {}", index + 1),
+        SkyObject::Satellite(index) => format!("Satellite {}", index + 1),
+    }
+}
+
+fn find_object(name: &str, stars: &[Star], planets: &[Planet]) -> Option<SkyObject> {
+    // the other way round from selection_name, whatever the case
+    let name = name.to_lowercase();
+    let hip = name
+        .strip_prefix("hip ")
+        .and_then(|number| number.trim().parse::<u32>().ok());
+    match name.as_str() {
+        "sun" => Some(SkyObject::Sun),
+        "moon" => Some(SkyObject::Moon),
+        _ => planets
+            .iter()
+            .position(|planet| planet.name.to_lowercase() == name)
+            .map(SkyObject::Planet)
+            .or_else(|| {
+                stars
+                    .iter()
+                    .position(|star| {
+                        Some(star.hip) == hip || star.name.as_ref().is_some_and(|known| known.to_lowercase() == name)
+                    })
+                    .map(SkyObject::Star)
+            }),
+    }
+}
+
+fn answer_command(
+    command: Command,
+    pane: &mut Pane,
+    engine: &Engine,
+    stars: &[Star],
+    planets: &[Planet],
+) -> Result<Json, String> {
+    // angles in degrees but the right ascension in hours, at the time of the focused dome
+    let time = |pane: &Pane| ("time".to_string(), Json::String(pane.time.to_rfc3339()));
+    match command {
+        Command::Position(name) => {
+            let object = find_object(&name, stars, planets).ok_or(format!("unknown object {}", name))?;
+            if let SkyObject::Planet(index) = object {
+                if engine.is_observer(&planets[index]) {
+                    return Err(format!("the sky is seen from {}", planets[index].name));
+                }
             }
-            canvas.text(&planet.name, &small_font, x, y, 10);
+            let unknown = || format!("no place for {}", name);
+            let (alt, az) = engine.get_object_position(object, stars, planets).ok_or_else(unknown)?;
+            let (ascension, declination) = engine
+                .get_object_equatorial(object, stars, planets)
+                .ok_or_else(unknown)?;
+            Ok(Json::Object(vec![
+                ("name".to_string(), Json::String(selection_name(object, stars, planets))),
+                ("type".to_string(), Json::String(object.kind().name().to_string())),
+                time(pane),
+                ("alt".to_string(), Json::Number(alt / PI * 180.0)),
+                ("az".to_string(), Json::Number(az / PI * 180.0)),
+                ("ra".to_string(), Json::Number(ascension / PI * 12.0)),
+                ("dec".to_string(), Json::Number(declination / PI * 180.0)),
+            ]))
         }
+        Command::Time => Ok(Json::Object(vec![time(pane)])),
+        Command::GoTo(target) => {
+            pane.time = target;
+            pane.step = NEUTRAL_STEP;
+            Ok(Json::Object(vec![time(pane)]))
+        }
+    }
+}
 
-        let (alt, az, phase, angle) = engine.get_moon_position();
-        let (x, y) = horizontal_to_canvas(alt, az, canvas.logical_size());
-        canvas
-            .copy_ex(
-                &moon_phases[(phase / 2.0 / PI * 24.0).round() as usize % 24],
-                None,
-                Rect::new((x - 15).into(), (y - 15).into(), 30, 30),
-                angle / PI * 180.0,
-                None,
-                false,
-                false,
-            )
-            .unwrap();
-        canvas.text("Moon", &small_font, x, y, 15);
+fn format_hours(hours: f64) -> String {
+    let seconds = (hours * 3600.0).round() as u32 % 86400;
+    format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
 
+fn format_equatorial((ascension, declination): (f64, f64), notation: Notation) -> String {
+    format!(
+        "RA: {}; Dec: {}",
+        format::hours(ascension, notation, 4),
+        format::degrees(declination, notation, 4)
+    )
+}
+
+fn local_midnight(time: DateTime<Utc>, longitude: f64) -> DateTime<Utc> {
+    // the start of the local mean solar day containing the time
+    let offset = chrono::Duration::seconds((longitude / (2.0 * PI) * 86400.0) as i64);
+    (time + offset).date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc() - offset
+}
+
+fn local_noon(time: DateTime<Utc>, longitude: f64) -> DateTime<Utc> {
+    // the start of the night ahead, or of the one still going on in the small hours
+    local_midnight(time - chrono::Duration::hours(12), longitude) + chrono::Duration::hours(12)
+}
+
+fn format_passage(passage: Option<Passage>) -> String {
+    let Some(passage) = passage else {
+        return "Below the horizon all day".to_string();
+    };
+    if passage.rise.is_none() && passage.set.is_none() {
+        return "Above the horizon all day".to_string();
+    }
+    let format = |time: Option<DateTime<Utc>>| time.map_or("--".to_string(), |time| time.format("%H:%M").to_string());
+    format!(
+        "Rise: {}; Transit: {}; Set: {}",
+        format(passage.rise),
+        format(passage.transit),
+        format(passage.set)
+    )
+}
+
+fn format_zodiac(longitude: f64) -> String {
+    // tropical, whole degrees into the sign
+    let degrees = (longitude / PI * 180.0).rem_euclid(360.0).floor() as usize;
+    format!("{}° {}", degrees % 30, ZODIAC[degrees / 30])
+}
+
+fn scrub_time(pane: &Pane, calibration: &Calibration, longitude: f64) -> DateTime<Utc> {
+    // the day the Sun reaches the longitude at the same clock time, so the sky only moves with the season
+    let found = find_sun_longitude(&WORLDS[pane.world], calibration, pane.time, longitude);
+    let days = ((found - pane.time).num_seconds() as f64 / 86400.0).round() as i64;
+    pane.time + chrono::Duration::days(days)
+}
+
+fn jump_to_crossing(pane: &mut Pane, calibration: &Calibration, moon: bool, rising: bool) -> String {
+    // to the next rise or set of the Sun or the Moon, naming the event reached
+    let event = match (moon, rising) {
+        (false, true) => "sunrise",
+        (false, false) => "sunset",
+        (true, true) => "moonrise",
+        (true, false) => "moonset",
+    };
+    if !WORLDS[pane.world].is_earth() {
+        return "Rise and set times are only worked out for the Earth".to_string();
+    }
+    // a minute on, so the event just reached isn't found again
+    let start = pane.time + chrono::Duration::minutes(1);
+    let found = if moon {
+        find_moon_crossing(calibration, start, pane.latitude, pane.longitude, rising)
+    } else {
+        find_sun_crossing(calibration, start, pane.latitude, pane.longitude, rising)
+    };
+    match found {
+        Some(time) => {
+            pane.time = time;
+            pane.step = NEUTRAL_STEP;
+            format!("Next {} at {}", event, time.format("%Y-%m-%d %H:%M UTC"))
+        }
+        None => format!("No {} within {} days", event, CROSSING_SEARCH_DAYS),
+    }
+}
+
+fn format_dark_window(window: Option<DarkWindow>) -> String {
+    let Some(window) = window else {
+        return "Tonight: not visible".to_string();
+    };
+    let best = window.best.format("%H:%M");
+    if window.all_night {
+        return format!("Tonight: all night, best at {}", best);
+    }
+    format!(
+        "Tonight: {}-{}, best at {}",
+        window.start.format("%H:%M"),
+        window.end.format("%H:%M"),
+        best
+    )
+}
+
+fn describe_selection(
+    selection: SkyObject,
+    engine: &Engine,
+    stars: &[Star],
+    planets: &[Planet],
+    latitude: f64,
+    display: &Display,
+    times: &[String],
+) -> Vec<String> {
+    let notation = display.notation;
+    let Some((alt, az)) = engine.get_object_position(selection, stars, planets) else {
+        // the layers without a catalog have nothing more to show than what they are
+        let kind = format!("Type: {}", selection.kind().name());
+        return vec![selection_name(selection, stars, planets), kind];
+    };
+    let mut lines = Vec::new();
+    match selection {
+        SkyObject::Star(index) => {
+            let star = &stars[index];
+            let name = selection_name(selection, stars, planets);
+            match &star.designation {
+                Some(designation) => lines.push(format!("{} ({})", name, designation)),
+                None => lines.push(name),
+            }
+            lines.push(format!("Type: {}", selection.kind().name()));
+            lines.push(format!("Magnitude: {:.2}", engine.get_star_magnitude(star)));
+            if let Some(spectral_type) = &star.spectral_type {
+                lines.push(format!("Spectral type: {}", spectral_type));
+            }
+            if let Some(index) = star.color_index {
+                lines.push(format!("B-V: {:+.2}", index));
+            }
+            lines.push(format_equatorial(engine.get_star_equatorial(star), notation));
+            if engine.world().is_earth() {
+                let (_, declination) = engine.get_star_equatorial(star);
+                if is_circumpolar(declination, latitude) {
+                    lines.push("Circumpolar, never sets".to_string());
+                } else if is_circumpolar(-declination, latitude) {
+                    lines.push("Never rises".to_string());
+                }
+            }
+        }
+        SkyObject::Planet(index) => {
+            let planet = &planets[index];
+            lines.push(planet.name.clone());
+            lines.push(format!("Type: {}", selection.kind().name()));
+            lines.push(format!("Magnitude: {:.2}", engine.get_planet_magnitude(planet)));
+            let (_, _, distance) = engine.get_planet_position(planet);
+            if is_telescopic(engine.get_planet_magnitude(planet), alt, display) {
+                lines.push(TELESCOPIC_NOTE.to_string());
+            }
+            let light_time = (distance * LIGHT_TIME_PER_AU).round() as u32;
+            lines.push(format!("Distance: {:.3} au", distance));
+            lines.push(format!("Light time: {}m {:02}s", light_time / 60, light_time % 60));
+            lines.push(format_equatorial(engine.get_planet_equatorial(planet), notation));
+        }
+        SkyObject::Sun => {
+            lines.push("Sun".to_string());
+            lines.push(format!("Type: {}", selection.kind().name()));
+            lines.push(format!("Magnitude: {:.2}", SUN_MAGNITUDE));
+            lines.push(format_equatorial(engine.get_sun_equatorial(), notation));
+        }
+        SkyObject::Moon => {
+            let (_, _, lunar_phase, _) = engine.get_moon_position();
+            lines.push("Moon".to_string());
+            lines.push(format!("Type: {}", selection.kind().name()));
+            lines.push(format!("Illumination: {:.0}%", (1.0 - lunar_phase.cos()) / 2.0 * 100.0));
+            let (libration_lon, libration_lat) = engine.get_moon_libration();
+            lines.push(format!(
+                "Libration: lon {:+.2}; lat {:+.2}",
+                libration_lon / PI * 180.0,
+                libration_lat / PI * 180.0
+            ));
+            lines.push(format_equatorial(engine.get_moon_equatorial(), notation));
+        }
+        SkyObject::DeepSky(_) | SkyObject::Satellite(_) => {}
+    }
+    lines.extend_from_slice(times);
+    lines.push(format!(
+        "Alt: {}; Az: {}",
+        format::degrees(alt, notation, 4),
+        format::degrees(az, notation, 4)
+    ));
+    lines.push(format!(
+        "Parallactic angle: {}",
+        format::degrees(engine.get_parallactic_angle(alt, az), notation, 2)
+    ));
+    lines
+}
+
+// the selection and its world, the local midnight and noon, and the place
+type SelectionTimesKey = (SkyObject, WorldId, DateTime<Utc>, DateTime<Utc>, f64, f64);
+
+fn get_selection_times(
+    selection: SkyObject,
+    engine: &Engine,
+    stars: &[Star],
+    planets: &[Planet],
+    latitude: f64,
+    longitude: f64,
+) -> Vec<String> {
+    // the rise and set and the dark window, searched over a day so only worked out again for a new day or place
+    if !engine.world().is_earth() {
+        return Vec::new();
+    }
+    let calibration = &engine.calibration();
+    let midnight = local_midnight(engine.time, longitude);
+    let noon = local_noon(engine.time, longitude);
+    match selection {
+        SkyObject::Star(index) => vec![format_dark_window(get_star_window(
+            calibration,
+            noon,
+            latitude,
+            longitude,
+            &stars[index],
+        ))],
+        SkyObject::Planet(index) => {
+            let planet = &planets[index];
+            vec![
+                format_passage(get_planet_passage(calibration, midnight, latitude, longitude, planet)),
+                format_dark_window(get_planet_window(calibration, noon, latitude, longitude, planet)),
+            ]
+        }
+        SkyObject::Sun => vec![format_passage(get_sun_passage(
+            calibration,
+            midnight,
+            latitude,
+            longitude,
+        ))],
+        SkyObject::Moon => vec![
+            format_passage(get_moon_passage(calibration, midnight, latitude, longitude)),
+            format_dark_window(get_moon_window(calibration, noon, latitude, longitude)),
+        ],
+        SkyObject::DeepSky(_) | SkyObject::Satellite(_) => Vec::new(),
+    }
+}
+
+fn belt_of_venus_strength(alt: f64, az_offset: f64, sun_alt: f64) -> f64 {
+    // az_offset is measured from the antisolar azimuth, the band sits on the Earth's shadow,
+    // which rises about as high as the Sun sinks, and fades as the twilight deepens
+    let depression = -sun_alt;
+    if depression <= 0.0 || depression >= BELT_DEPTH || az_offset.abs() >= BELT_SPREAD {
+        return 0.0;
+    }
+    let height = alt - depression;
+    if height <= 0.0 || height >= BELT_WIDTH {
+        return 0.0;
+    }
+    let fade = 1.0 - depression / BELT_DEPTH;
+    let band = (PI * height / BELT_WIDTH).sin();
+    let spread = (az_offset / BELT_SPREAD * PI / 2.0).cos();
+    fade * band * spread
+}
+
+fn draw_atmosphere_ring(canvas: &mut Screen, size: (u32, u32), layout: Layout, view: &View, theme: &Theme) {
+    // a faint haze just inside the horizon, where the air bends the light the most
+    let center = stereo_to_canvas_exact(0.0, 0.0, size, layout, view);
+    let radius = canvas_scale(size, layout, view);
+    for i in 0..ATMOSPHERE_RING_WIDTH {
+        let alpha = ATMOSPHERE_RING_ALPHA * (1.0 - i as f64 / ATMOSPHERE_RING_WIDTH as f64);
+        let color = Color::RGBA(theme.belt.r, theme.belt.g, theme.belt.b, alpha.round() as u8);
+        canvas.clipped_circle(center, radius - i as f64, dome_area(size, layout), color);
+    }
+}
+
+fn refraction_flattening(alt: f64, angular_radius: f64) -> f64 {
+    // the lower limb is lifted more than the upper one, squashing a disk low down into an oval
+    let true_alt = unrefract(alt);
+    (refract(true_alt + angular_radius) - refract(true_alt - angular_radius)) / (2.0 * angular_radius)
+}
+
+fn draw_oval(canvas: &mut Screen, center: (i16, i16), radius: i16, toward: (f64, f64), flattening: f64, color: Color) {
+    // a disk squashed by flattening along the direction to the point toward
+    if flattening > 0.99 {
+        canvas.aa_filled_circle(center.0, center.1, radius, color);
+        return;
+    }
+    let (x, y) = (center.0 as f64, center.1 as f64);
+    let (dx, dy) = (toward.0 - x, toward.1 - y);
+    let length = dx.hypot(dy).max(f64::EPSILON);
+    let (ux, uy) = (dx / length, dy / length);
+    let (xs, ys): (Vec<i16>, Vec<i16>) = (0..OVAL_POINTS)
+        .map(|i| {
+            let angle = 2.0 * PI * i as f64 / OVAL_POINTS as f64;
+            let across = radius as f64 * angle.cos();
+            let along = radius as f64 * flattening * angle.sin();
+            (
+                (x - across * uy + along * ux).round() as i16,
+                (y + across * ux + along * uy).round() as i16,
+            )
+        })
+        .unzip();
+    canvas.filled_polygon(&xs, &ys, color).unwrap();
+}
+
+fn draw_belt_of_venus(
+    canvas: &mut Screen,
+    size: (u32, u32),
+    layout: Layout,
+    view: &View,
+    sun_alt: f64,
+    sun_az: f64,
+    theme: &Theme,
+) {
+    // translucent cells over the horizon opposite the Sun
+    let step = BELT_SAMPLING / 180.0 * PI;
+    let columns = (2.0 * BELT_SPREAD / step).round() as usize;
+    let rows = ((BELT_DEPTH + BELT_WIDTH) / step).round() as usize;
+    for column in 0..columns {
+        let az_offset = -BELT_SPREAD + column as f64 * step;
+        for row in 0..rows {
+            let alt = row as f64 * step;
+            let strength = belt_of_venus_strength(alt + step / 2.0, az_offset + step / 2.0, sun_alt);
+            if strength <= 0.0 {
+                continue;
+            }
+            let az = sun_az + PI + az_offset;
+            let corners = [(alt, az), (alt, az + step), (alt + step, az + step), (alt + step, az)]
+                .map(|(alt, az)| horizontal_to_canvas(alt, az, size, layout, view));
+            let Color { r, g, b, .. } = theme.belt;
+            canvas
+                .filled_polygon(
+                    &corners.map(|corner| corner.0),
+                    &corners.map(|corner| corner.1),
+                    Color::RGBA(r, g, b, (strength * BELT_ALPHA).round() as u8),
+                )
+                .unwrap();
+        }
+    }
+}
+
+fn zodiacal_light_strength(elongation: f64, latitude: f64, sun_alt: f64) -> f64 {
+    // a cone standing on the ecliptic with its base by the Sun, only seen once the twilight has nearly gone,
+    // elongation either way from the Sun and latitude off the ecliptic
+    let night = ((ZODIACAL_TWILIGHT - sun_alt) / (ZODIACAL_TWILIGHT - ZODIACAL_NIGHT)).clamp(0.0, 1.0);
+    let elongation = elongation.abs();
+    if night == 0.0 || !(ZODIACAL_START..ZODIACAL_REACH).contains(&elongation) {
+        return 0.0;
+    }
+    let taper = 1.0 - (elongation - ZODIACAL_START) / (ZODIACAL_REACH - ZODIACAL_START);
+    let width = ZODIACAL_WIDTH * taper;
+    if latitude.abs() >= width {
+        return 0.0;
+    }
+    night * taper * (1.0 - latitude.abs() / width)
+}
+
+fn draw_zodiacal_light(
+    canvas: &mut Screen,
+    size: (u32, u32),
+    layout: Layout,
+    view: &View,
+    engine: &Engine,
+    theme: &Theme,
+) {
+    // translucent cells laid out along the ecliptic rather than the horizon, on both sides of the Sun,
+    // the evening cone stands in the west and the morning one in the east
+    let (sun_alt, _) = engine.get_sun_position();
+    let step = ZODIACAL_SAMPLING / 180.0 * PI;
+    let columns = ((ZODIACAL_REACH - ZODIACAL_START) / step).round() as usize;
+    let rows = (2.0 * ZODIACAL_WIDTH / step).round() as usize;
+    let Color { r, g, b, .. } = theme.star;
+    for side in [1.0, -1.0] {
+        for column in 0..columns {
+            let elongation = side * (ZODIACAL_START + column as f64 * step);
+            for row in 0..rows {
+                let latitude = -ZODIACAL_WIDTH + row as f64 * step;
+                let strength = zodiacal_light_strength(elongation + side * step / 2.0, latitude + step / 2.0, sun_alt);
+                if strength <= 0.0 {
+                    continue;
+                }
+                let corners = [
+                    (elongation, latitude),
+                    (elongation + side * step, latitude),
+                    (elongation + side * step, latitude + step),
+                    (elongation, latitude + step),
+                ]
+                .map(|(elongation, latitude)| engine.get_ecliptic_offset(elongation, latitude));
+                if corners.iter().any(|&(alt, _)| alt < 0.0) {
+                    continue;
+                }
+                let corners = corners.map(|(alt, az)| horizontal_to_canvas(alt, az, size, layout, view));
+                canvas
+                    .filled_polygon(
+                        &corners.map(|corner| corner.0),
+                        &corners.map(|corner| corner.1),
+                        Color::RGBA(r, g, b, (strength * ZODIACAL_ALPHA).round() as u8),
+                    )
+                    .unwrap();
+            }
+        }
+    }
+}
+
+fn inset_horizon_point(az: f64, inset: f64, size: (u32, u32), layout: Layout, view: &View) -> (f64, f64) {
+    // the horizon point at az moved towards the center of the dome by inset pixels
+    let (center_x, center_y) = stereo_to_canvas_exact(0.0, 0.0, size, layout, view);
+    let (x, y) = view.projection.project(0.0, az);
+    let (x, y) = stereo_to_canvas_exact(x, y, size, layout, view);
+    let factor = 1.0 - inset / canvas_scale(size, layout, view);
+    (center_x + (x - center_x) * factor, center_y + (y - center_y) * factor)
+}
+
+fn draw_hour_ring(
+    canvas: &mut Screen,
+    size: (u32, u32),
+    layout: Layout,
+    view: &View,
+    engine: &Engine,
+    font: &Font,
+    theme: &Theme,
+) {
+    // ticks where the hour circles of right ascension meet the horizon, turning with the sidereal time
+    let half_height = font.height() as i16 / 2;
+    for (hour, az) in engine.get_horizon_hours() {
+        let (x0, y0) = inset_horizon_point(az, 0.0, size, layout, view);
+        let (x1, y1) = inset_horizon_point(az, HOUR_TICK_LENGTH, size, layout, view);
+        canvas
+            .aa_line(
+                x0.round() as i16,
+                y0.round() as i16,
+                x1.round() as i16,
+                y1.round() as i16,
+                theme.text,
+            )
+            .unwrap();
+        let (x, y) = inset_horizon_point(az, HOUR_LABEL_INSET, size, layout, view);
         canvas.text(
-            "N",
-            &font,
-            (width / 2).try_into().unwrap(),
-            ((height - PANEL_SIZE) / 2 - radius + 25).try_into().unwrap(),
-            0,
-        );
-        canvas.text(
-            "E",
-            &font,
-            (width / 2 - radius + 10).try_into().unwrap(),
-            ((height - PANEL_SIZE) / 2 + 14).try_into().unwrap(),
-            0,
+            &format!("{}h", hour),
+            font,
+            x.round() as i16,
+            y.round() as i16,
+            -half_height,
+            theme.text,
         );
-        canvas.text(
-            "S",
-            &font,
-            (width / 2).try_into().unwrap(),
-            ((height - PANEL_SIZE) / 2 + radius).try_into().unwrap(),
-            0,
+    }
+}
+
+type RisePoints = [(&'static str, Option<f64>); 4];
+type RisePointsKey = (DateTime<Utc>, f64, f64); // the local midnight and the place
+
+fn get_rise_points(calibration: &Calibration, time: DateTime<Utc>, latitude: f64, longitude: f64) -> RisePoints {
+    // the azimuths on the local day, none for an event that doesn't happen
+    let midnight = local_midnight(time, longitude);
+    let (sunrise, sunset) = get_sun_azimuths(calibration, midnight, latitude, longitude);
+    let (moonrise, moonset) = get_moon_azimuths(calibration, midnight, latitude, longitude);
+    [
+        ("Sunrise", sunrise),
+        ("Sunset", sunset),
+        ("Moonrise", moonrise),
+        ("Moonset", moonset),
+    ]
+}
+
+fn draw_rise_points(
+    canvas: &mut Screen,
+    size: (u32, u32),
+    layout: Layout,
+    view: &View,
+    points: &RisePoints,
+    font: &Font,
+    theme: &Theme,
+) {
+    let half_height = font.height() as i16 / 2;
+    for &(label, az) in points {
+        let Some(az) = az else {
+            continue;
+        };
+        let color = if label.starts_with("Sun") {
+            theme.sun
+        } else {
+            theme.text
+        };
+        let (x0, y0) = inset_horizon_point(az, 0.0, size, layout, view);
+        let (x1, y1) = inset_horizon_point(az, RISE_TICK_LENGTH, size, layout, view);
+        canvas
+            .aa_line(
+                x0.round() as i16,
+                y0.round() as i16,
+                x1.round() as i16,
+                y1.round() as i16,
+                color,
+            )
+            .unwrap();
+        let (x, y) = inset_horizon_point(az, RISE_LABEL_INSET, size, layout, view);
+        canvas.text(label, font, x.round() as i16, y.round() as i16, -half_height, color);
+    }
+}
+
+fn trail_positions(engine: &Engine, planets: &[Planet]) -> Vec<Option<(f64, f64)>> {
+    // the planets in catalog order, then the Moon
+    planets
+        .iter()
+        .map(|planet| (!engine.is_observer(planet)).then(|| engine.get_planet_equatorial(planet)))
+        .chain([Some(engine.get_moon_equatorial())])
+        .collect()
+}
+
+fn draw_trails(
+    canvas: &mut Screen,
+    size: (u32, u32),
+    layout: Layout,
+    view: &View,
+    engine: &Engine,
+    trails: &Trails,
+    theme: &Theme,
+) {
+    for track in trails.tracks() {
+        for (i, &(ascension, declination)) in track.iter().enumerate() {
+            let (alt, az) = engine.get_equatorial_position(ascension, declination);
+            if alt < 0.0 {
+                continue;
+            }
+            let (x, y) = horizontal_to_canvas(alt, az, size, layout, view);
+            let alpha = TRAIL_ALPHA * (i + 1) as f64 / track.len() as f64;
+            let color = Color::RGBA(theme.planet.r, theme.planet.g, theme.planet.b, alpha.round() as u8);
+            canvas.filled_circle(x, y, 1, color).unwrap();
+        }
+    }
+}
+
+fn draw_ruler(
+    canvas: &mut Screen,
+    size: (u32, u32),
+    view: &View,
+    ends: &[(f64, f64)],
+    notation: Notation,
+    scene: &Scene,
+) {
+    let (layout, font, theme) = (scene.layout, scene.small_font, scene.theme);
+    if let [a, b] = *ends {
+        canvas.horizontal_curve(
+            &great_circle_points(a, b, RULER_SAMPLES),
+            size,
+            layout,
+            view,
+            theme.highlight,
+        );
+    }
+    for &(alt, az) in ends {
+        let (x, y) = horizontal_to_canvas(alt, az, size, layout, view);
+        canvas.circle(x, y, 3, theme.highlight).unwrap();
+    }
+    if let [a, b] = *ends {
+        // the distance next to the middle of the arc
+        let (alt, az) = great_circle_points(a, b, 2)[1];
+        let (x, y) = horizontal_to_canvas(alt, az, size, layout, view);
+        let distance = format::degrees(angular_distance(a, b), notation, 2);
+        canvas.text(&distance, font, x, y, 4, theme.highlight);
+    }
+}
+
+fn draw_boundaries(
+    canvas: &mut Screen,
+    size: (u32, u32),
+    layout: Layout,
+    view: &View,
+    engine: &Engine,
+    boundaries: &[Boundary],
+    theme: &Theme,
+) {
+    let step = GRID_SAMPLING / 180.0 * PI;
+    for boundary in boundaries {
+        for ((a1, d1), (a2, d2)) in boundary.edges() {
+            // edges follow parallels and hour circles, so they are straight in equatorial coordinates
+            let span = (a2 - a1 + PI).rem_euclid(2.0 * PI) - PI;
+            let count = (span.abs().max((d2 - d1).abs()) / step).ceil().max(1.0) as usize;
+            let points: Vec<_> = (0..=count)
+                .map(|i| {
+                    let t = i as f64 / count as f64;
+                    engine.get_equatorial_position(a1 + span * t, d1 + (d2 - d1) * t)
+                })
+                .collect();
+            canvas.horizontal_curve(&points, size, layout, view, theme.boundary);
+        }
+    }
+}
+
+fn draw_galactic_grid(
+    canvas: &mut Screen,
+    size: (u32, u32),
+    layout: Layout,
+    view: &View,
+    engine: &Engine,
+    font: &Font,
+    theme: &Theme,
+) {
+    let step = GRID_SAMPLING / 180.0 * PI;
+    for latitude in (-60..=60).step_by(30) {
+        let latitude = latitude as f64 / 180.0 * PI;
+        let points: Vec<_> = (0..=(2.0 * PI / step).round() as usize)
+            .map(|i| engine.get_galactic_position(i as f64 * step, latitude))
+            .collect();
+        canvas.horizontal_curve(&points, size, layout, view, theme.galactic);
+    }
+    for longitude in (0..360).step_by(30) {
+        let longitude = longitude as f64 / 180.0 * PI;
+        let points: Vec<_> = (0..=(PI / step).round() as usize)
+            .map(|i| engine.get_galactic_position(longitude, i as f64 * step - PI / 2.0))
+            .collect();
+        canvas.horizontal_curve(&points, size, layout, view, theme.galactic);
+    }
+    for (name, longitude, latitude) in [("GC", 0.0, 0.0), ("NGP", 0.0, PI / 2.0), ("SGP", 0.0, -PI / 2.0)] {
+        let (alt, az) = engine.get_galactic_position(longitude, latitude);
+        if alt >= 0.0 {
+            let (x, y) = horizontal_to_canvas(alt, az, size, layout, view);
+            canvas.aa_circle(x, y, 4, theme.galactic).unwrap();
+            canvas.text(name, font, x, y, 4, theme.galactic);
+        }
+    }
+}
+
+fn get_day_lengths(calibration: &Calibration, year: i32, latitude: f64, longitude: f64) -> Vec<f64> {
+    // weekly samples starting from the local midnight of new year's day
+    let start = Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).unwrap()
+        - chrono::Duration::seconds((longitude / (2.0 * PI) * 86400.0) as i64);
+    (0..DAY_LENGTH_SAMPLES)
+        .map(|week| {
+            let length = get_day_length(calibration, start + chrono::Duration::weeks(week), latitude, longitude);
+            length.num_seconds() as f64 / 3600.0
+        })
+        .collect()
+}
+
+fn draw_day_length_chart(
+    canvas: &mut Screen,
+    origin: (i16, i16),
+    day_lengths: &[f64],
+    latitude: f64,
+    font: &Font,
+    theme: &Theme,
+) {
+    let (left, top) = origin;
+    let (right, bottom) = (left + CHART_WIDTH, top + CHART_HEIGHT);
+    let to_x = |day: f64| left + (day / 365.0 * CHART_WIDTH as f64).round() as i16;
+    let to_y = |hours: f64| bottom - (hours / 24.0 * CHART_HEIGHT as f64).round() as i16;
+
+    canvas.box_(left, top, right, bottom, theme.panel).unwrap();
+    for hours in [6.0, 12.0, 18.0] {
+        canvas.hline(left, right, to_y(hours), theme.grid).unwrap();
+    }
+    for (label, day) in [("Jun", 172.0), ("Dec", 355.0)] {
+        let x = to_x(day);
+        canvas.vline(x, top, bottom, theme.ecliptic).unwrap();
+        canvas.text(label, font, x, bottom, -font.height() as i16, theme.text);
+    }
+    for (i, pair) in day_lengths.windows(2).enumerate() {
+        let (x1, x2) = (to_x(i as f64 * 7.0), to_x((i + 1) as f64 * 7.0));
+        canvas
+            .aa_line(x1, to_y(pair[0]), x2, to_y(pair[1]), theme.highlight)
+            .unwrap();
+    }
+    canvas.rectangle(left, top, right, bottom, theme.grid).unwrap();
+    let title = format!("Day length at {:.1}°", latitude / PI * 180.0);
+    canvas.text(&title, font, (left + right) / 2, top, 2, theme.text);
+}
+
+fn parse_date_range(text: &str) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut dates = text
+        .split_whitespace()
+        .map(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+    match (dates.next(), dates.next(), dates.next()) {
+        (Some(Some(start)), Some(Some(end)), None) if start < end => Some((
+            start.and_hms_opt(0, 0, 0)?.and_utc(),
+            end.and_hms_opt(0, 0, 0)?.and_utc(),
+        )),
+        _ => None,
+    }
+}
+
+fn report_eclipses(
+    world: &World,
+    calibration: &Calibration,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+) -> String {
+    if !world.is_earth() {
+        return "Eclipses are only worked out for the Earth".to_string();
+    }
+    if end - start > chrono::Duration::days(ECLIPSE_SEARCH_YEARS * 366) {
+        return format!("Search at most {} years at a time", ECLIPSE_SEARCH_YEARS);
+    }
+    let eclipses = find_eclipses(calibration, start, end, latitude, longitude);
+    for eclipse in &eclipses {
+        eprintln!(
+            "{} eclipse: {}",
+            eclipse.kind.name(),
+            eclipse.time.format("%Y-%b-%d %H:%M %Z")
+        );
+    }
+    match eclipses.first() {
+        Some(eclipse) => format!(
+            "{} eclipses, first {} {}",
+            eclipses.len(),
+            eclipse.kind.name(),
+            eclipse.time.format("%Y-%b-%d %H:%M")
+        ),
+        None => "No eclipses visible".to_string(),
+    }
+}
+
+fn report_elongations(world: &World, calibration: &Calibration, planets: &[Planet], time: DateTime<Utc>) -> String {
+    let mut summary = Vec::new();
+    // only planets inside the observer's orbit have a greatest elongation
+    for planet in planets.iter().filter(|planet| planet.semimajor < world.semimajor) {
+        let elongations = find_greatest_elongations(world, calibration, planet, time, ELONGATION_SEARCH_DAYS);
+        for elongation in &elongations {
+            eprintln!(
+                "{} greatest {} elongation: {} ({:.1}°)",
+                planet.name,
+                if elongation.angle > 0.0 { "eastern" } else { "western" },
+                elongation.time.format("%Y-%b-%d %H:%M %Z"),
+                elongation.angle.abs() / PI * 180.0
+            );
+        }
+        if let Some(elongation) = elongations.first() {
+            summary.push(format!(
+                "{} {} {} ({:.1}°)",
+                planet.name,
+                if elongation.angle > 0.0 { "E" } else { "W" },
+                elongation.time.format("%Y-%b-%d"),
+                elongation.angle.abs() / PI * 180.0
+            ));
+        }
+    }
+    summary.join("; ")
+}
+
+fn render_text<'a, T>(
+    font: &'a Font<'a, 'a>,
+    texture_creator: &'a TextureCreator<T>,
+    text: &'a str,
+    color: Color,
+) -> (Texture<'a>, u32, u32) {
+    let texture = font
+        .render(text)
+        .blended(color)
+        .unwrap()
+        .as_texture(texture_creator)
+        .unwrap();
+    let (x, y) = font.size_of(text).unwrap();
+    (texture, x, y)
+}
+
+fn cli_option(name: &str) -> Option<String> {
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return Some(
+                args.next()
+                    .unwrap_or_else(|| startup_error("Missing a value for", name)),
+            );
+        }
+    }
+    None
+}
+
+fn config_path() -> PathBuf {
+    PathBuf::from(cli_option("--config").unwrap_or(CONFIG_FILE.to_string()))
+}
+
+fn load_grid(config: &Config) -> Grid {
+    // no spokes leaves just the circles, the spacing has to leave at least one circle below the zenith
+    let mut grid = Grid::default();
+    match config.get("", "grid_spokes").map(str::parse::<u32>) {
+        Some(Ok(spokes)) => grid.spokes = spokes,
+        Some(Err(_)) => eprintln!("Invalid grid_spokes, using {}", GRID_SPOKES),
+        None => {}
+    }
+    match config.get("", "grid_spacing").map(str::parse::<u32>) {
+        Some(Ok(spacing)) if (1..90).contains(&spacing) => grid.spacing = spacing,
+        Some(_) => eprintln!("Invalid grid_spacing, using {}", GRID_SPACING),
+        None => {}
+    }
+    grid
+}
+
+fn load_calibration(config: &Config) -> Calibration {
+    // phases at the unix epoch in radians, to check the model against another reference
+    let mut calibration = Calibration::default();
+    let phases = [
+        ("solar_phase", &mut calibration.solar_phase),
+        ("solar_anomaly", &mut calibration.solar_anomaly),
+        ("daily_phase", &mut calibration.daily_phase),
+        ("moon_phase", &mut calibration.moon_phase),
+        ("moon_anomaly", &mut calibration.moon_anomaly),
+        ("nodal_phase", &mut calibration.nodal_phase),
+    ];
+    for (key, phase) in phases {
+        match config.get("calibration", key).map(str::parse::<f64>) {
+            Some(Ok(value)) if value.is_finite() => *phase = value,
+            Some(_) => eprintln!("Invalid calibration.{}, using {}", key, phase),
+            None => {}
+        }
+    }
+    calibration
+}
+
+fn parse_frame_period(value: &str) -> Option<Option<Duration>> {
+    // frames per second, or uncapped to render as fast as possible
+    if value == "uncapped" {
+        return Some(None);
+    }
+    match value.parse::<f64>() {
+        // a rate so low the period overflows a duration is as invalid as a negative one
+        Ok(fps) if fps > 0.0 && fps.is_finite() => Duration::try_from_secs_f64(1.0 / fps).ok().map(Some),
+        _ => None,
+    }
+}
+
+fn size_setting(config: &Config, option: &str, key: &str, range: RangeInclusive<u32>, default: u32) -> u32 {
+    // in pixels, the command line wins over the config file
+    let Some(value) = cli_option(option).or(config.get("", key).map(str::to_string)) else {
+        return default;
+    };
+    match value.parse::<u32>() {
+        Ok(size) if range.contains(&size) => size,
+        _ => {
+            eprintln!("Invalid {} {}, using {}", key, value, default);
+            default
+        }
+    }
+}
+
+fn load_layout(config: &Config) -> Layout {
+    // the window is sized from these, so they are capped well short of overflowing it
+    let mut layout = Layout {
+        panel: size_setting(config, "--panel-size", "panel_size", 0..=MAX_SIZE, PANEL_SIZE),
+        ..Layout::default()
+    };
+    match config.get("", "dome_margin").map(str::parse::<u32>) {
+        Some(Ok(margin)) if margin <= MAX_SIZE => layout.margin = margin,
+        Some(_) => eprintln!("Invalid dome_margin, using {}", DOME_MARGIN),
+        None => {}
+    }
+    layout
+}
+
+fn frame_period(config: &Config) -> Option<Duration> {
+    // the command line wins over the config file
+    let value = cli_option("--fps").or(config.get("", "fps").map(str::to_string));
+    let Some(value) = value else {
+        return Some(Duration::from_secs_f64(1.0 / DEFAULT_FPS));
+    };
+    parse_frame_period(&value).unwrap_or_else(|| {
+        eprintln!("Invalid fps {}, using {}", value, DEFAULT_FPS);
+        Some(Duration::from_secs_f64(1.0 / DEFAULT_FPS))
+    })
+}
+
+fn idle_period(config: &Config) -> Duration {
+    // a frame rate to fall back to while the window isn't focused, never uncapped
+    let default = Duration::from_secs_f64(1.0 / IDLE_FPS);
+    match config.get("", "idle_fps") {
+        Some(value) => match parse_frame_period(value) {
+            Some(Some(period)) => period,
+            _ => {
+                eprintln!("Invalid idle_fps {}, using {}", value, IDLE_FPS);
+                default
+            }
+        },
+        None => default,
+    }
+}
+
+fn render_frame(
+    canvas: &mut Screen,
+    size: (u32, u32),
+    view: &View,
+    engine: &Engine,
+    scene: &Scene,
+    display: &Display,
+    trails: &Trails,
+) -> (Vec<String>, FrameStats) {
+    let (theme, layout) = (scene.theme, scene.layout);
+    let mut stats = FrameStats::default();
+    let radius = canvas_scale(size, layout, view);
+    let (center_x, center_y) = stereo_to_canvas(0.0, 0.0, size, layout, view);
+    canvas.aa_filled_circle(center_x, center_y, radius.round() as i16, theme.dome);
+    if display.gradient {
+        // the dome color at the zenith, brightening a little towards the grid color at the horizon
+        let horizon = mix_color(theme.dome, theme.grid, GRADIENT_HORIZON);
+        canvas.radial_gradient(center_x, center_y, radius.round() as i16, theme.dome, horizon);
+    }
+
+    canvas.draw_azimuthal_grid(size, layout, view, scene.grid, theme.grid);
+    if display.refraction {
+        draw_atmosphere_ring(canvas, size, layout, view, theme);
+    }
+    draw_ecliptic(canvas, size, layout, view, engine, theme.ecliptic);
+
+    if display.galactic_grid {
+        draw_galactic_grid(canvas, size, layout, view, engine, scene.small_font, theme);
+    }
+
+    if display.boundaries {
+        draw_boundaries(canvas, size, layout, view, engine, scene.boundaries, theme);
+    }
+
+    if display.trails {
+        draw_trails(canvas, size, layout, view, engine, trails, theme);
+    }
+
+    if let (true, Some(nodes)) = (display.lunar_nodes, engine.get_lunar_nodes()) {
+        for ((alt, az), name) in nodes.into_iter().zip(["AN", "DN"]) {
+            if alt >= 0.0 {
+                let (x, y) = horizontal_to_canvas(alt, az, size, layout, view);
+                canvas.aa_circle(x, y, 4, theme.ecliptic).unwrap();
+                canvas.text(name, scene.small_font, x, y, 4, theme.ecliptic);
+            }
+        }
+    }
+
+    let (sun_alt, sun_az) = engine.get_sun_position();
+    if display.atmosphere {
+        draw_belt_of_venus(canvas, size, layout, view, sun_alt, sun_az, theme);
+    }
+    if display.zodiacal_light {
+        draw_zodiacal_light(canvas, size, layout, view, engine, theme);
+    }
+    let daylight = daylight_factor(sun_alt);
+    let (moon_alt, moon_az, lunar_phase, moon_angle) = engine.get_moon_position();
+    // low objects redden through the thicker air
+    let redden_at = |alt: f64, color: Color| {
+        if display.atmosphere {
+            redden(color, get_airmass(alt))
+        } else {
+            color
+        }
+    };
+
+    // well placed, above the altitude picked for planning
+    let high = |alt: f64| display.highlight_altitude > 0 && alt > (display.highlight_altitude as f64).to_radians();
+
+    let (limit, extinction, gamma) = sky_conditions(display);
+    let mut labels = Vec::new();
+    let mut glyphs = Vec::new();
+    let candidates = visible_stars(scene.stars, limit);
+    stats.stars = candidates.len();
+    for star in candidates {
+        let (alt, az) = engine.get_star_position(star);
+        if alt > 0.0 {
+            stats.stars_above += 1;
+        }
+        // the limit holds at the zenith, lower down the air takes its toll
+        let magnitude = engine.get_star_magnitude(star) + extinction * (get_airmass(alt) - 1.0);
+        let magnitude = if display.moonlight {
+            let distance = angular_distance((alt, az), (moon_alt, moon_az));
+            magnitude + moonlight_extinction(moon_alt, lunar_phase, distance)
+        } else {
+            magnitude
+        };
+        if magnitude > limit {
+            continue;
+        }
+        let (x, y) = horizontal_to_canvas(alt, az, size, layout, view);
+        let (size_px, brightness) = magnitude_to_size_and_brightness(magnitude);
+        let brightness = conditions::apply_gamma(brightness, gamma);
+        let twinkle = if display.twinkle {
+            twinkle_factor(alt, star.hip, scene.clock, TWINKLE_DEPTH)
+        } else {
+            1.0
+        };
+        let brightness = (brightness as f64 * daylight * twinkle).round().min(255.0) as u8;
+        if brightness == 0 {
+            continue;
+        }
+        let tint = star_tint(star);
+        // only the star itself dances in poor seeing, its label and companion stay put
+        let (dx, dy) = seeing_offset(alt, star.hip, scene.clock, display.seeing);
+        let (spot_x, spot_y) = (x + dx, y + dy);
+        if display.glow && magnitude < GLOW_MAGNITUDE {
+            canvas.glow(
+                spot_x,
+                spot_y,
+                size_px,
+                glow_strength(magnitude) * daylight,
+                redden_at(alt, theme.star_tint(tint)),
+            );
+        }
+        if display.seeing > 0 {
+            let blur = (SEEING_BLUR * display.seeing as f64).min(1.0) * brightness as f64 / 255.0;
+            canvas.glow(spot_x, spot_y, size_px, blur, redden_at(alt, theme.star_tint(tint)));
+        }
+        canvas.glyph(
+            display.glyph,
+            spot_x,
+            spot_y,
+            size_px,
+            redden_at(alt, theme.star_color(tint, brightness)),
         );
+        stats.stars_drawn += 1;
+        glyphs.push(glyph_box(spot_x, spot_y, size_px));
+        if high(alt) {
+            canvas
+                .aa_circle(x, y, size_px + HIGHLIGHT_GAP, theme.highlight)
+                .unwrap();
+        }
+        if star.double {
+            let companion = engine
+                .get_companion_position(star)
+                .map(|(alt, az)| horizontal_to_canvas(alt, az, size, layout, view))
+                .filter(|&(cx, cy)| ((cx - x) as f64).hypot((cy - y) as f64) >= DOUBLE_SPLIT);
+            match (companion, &star.companion) {
+                (Some((cx, cy)), Some(companion)) => {
+                    let (size_px, brightness) = magnitude_to_size_and_brightness(companion.magnitude);
+                    let brightness = (brightness as f64 * daylight).round() as u8;
+                    canvas.glyph(
+                        display.glyph,
+                        cx,
+                        cy,
+                        size_px,
+                        theme.star_color(Color::WHITE, brightness),
+                    );
+                }
+                _ if display.double_markers => {
+                    canvas
+                        .aa_circle(x, y, size_px + 3, mix_color(theme.dome, theme.double_marker, daylight))
+                        .unwrap();
+                }
+                _ => {}
+            }
+        }
+        if let Some(label) = star_label(star, magnitude, display, view) {
+            labels.push(StarLabel {
+                text: label,
+                x,
+                y,
+                radius: size_px,
+            });
+        }
+    }
+    draw_star_labels(
+        canvas,
+        &labels,
+        &glyphs,
+        dome_area(size, layout),
+        scene.small_font,
+        display.leaders,
+        theme.text,
+    );
+
+    let (x, y) = horizontal_to_canvas(sun_alt, sun_az, size, layout, view);
+    let (sun_sx, sun_sy) = view.projection.project(sun_alt, sun_az);
+    // a closer Sun looks bigger, by a few percent over the year
+    let sun_radius = engine.get_sun_angular_radius();
+    let moon_radius = engine.get_moon_angular_radius();
+    let sun_size = disk_size(sun_radius, sun_alt, size, layout, view);
+    let solar_eclipse = engine.get_solar_eclipse();
+    // the corona comes out as the Moon covers the disk
+    let covered = solar_eclipse.map_or(0.0, |separation| {
+        ((sun_radius + moon_radius - separation) / (2.0 * sun_radius)).clamp(0.0, 1.0)
+    });
+    let sun_color = redden_at(sun_alt, theme.sun);
+    canvas.corona(x, y, sun_size, CORONA_GLARE + (1.0 - CORONA_GLARE) * covered, sun_color);
+    // flattened towards the zenith by refraction when it is low
+    let zenith = stereo_to_canvas_exact(0.0, 0.0, size, layout, view);
+    let flattening = if display.refraction {
+        refraction_flattening(sun_alt, sun_radius)
+    } else {
+        1.0
+    };
+    draw_oval(
+        canvas,
+        (x, y),
+        sun_size,
+        zenith,
+        flattening,
+        mix_color(theme.dome, sun_color, SUN_LIMB),
+    );
+    draw_oval(canvas, (x, y), sun_size * 2 / 3, zenith, flattening, sun_color);
+    if let Some(separation) = solar_eclipse {
+        // the dark side of the Moon, offset towards it like a transit
+        let (sx, sy) = view.projection.project(moon_alt, moon_az);
+        let (dx, dy) = (sx - sun_sx, sy - sun_sy);
+        let length = dx.hypot(dy).max(f64::EPSILON);
+        let offset = separation / sun_radius * sun_size as f64;
+        let radius = (moon_radius / sun_radius * sun_size as f64).round() as i16;
+        let moon_x = x + (dx / length * offset).round() as i16;
+        let moon_y = y + (dy / length * offset).round() as i16;
+        canvas.aa_filled_circle(moon_x, moon_y, radius, theme.dome);
+    }
+    if high(sun_alt) {
+        canvas
+            .aa_circle(x, y, sun_size + HIGHLIGHT_GAP, theme.highlight)
+            .unwrap();
+    }
+    canvas.text("Sun", scene.small_font, x, y, sun_size, theme.text);
+    let (sun_x, sun_y) = (x, y);
+    let mut transits = Vec::new();
+
+    if let Some((alt, az)) = engine.get_earth_position() {
+        let (x, y) = horizontal_to_canvas(alt, az, size, layout, view);
+        canvas.aa_filled_circle(x, y, 6, redden_at(alt, theme.planet));
+        canvas.text("Earth", scene.small_font, x, y, 10, theme.text);
+    }
+
+    for (i, planet) in scene.planets.iter().enumerate() {
+        if engine.is_observer(planet) {
+            continue;
+        }
+        let (alt, az, _) = engine.get_planet_position(planet);
+        stats.planets += 1;
+        if alt > 0.0 {
+            stats.planets_above += 1;
+        }
+        let (x, y) = horizontal_to_canvas(alt, az, size, layout, view);
+        if let Some(separation) = engine.get_solar_transit(planet) {
+            // drawn as a silhouette on the solar disk, offset from the center in the direction of the planet
+            let (sx, sy) = view.projection.project(alt, az);
+            let (dx, dy) = (sx - sun_sx, sy - sun_sy);
+            let length = dx.hypot(dy).max(f64::EPSILON);
+            let offset = separation / sun_radius * sun_size as f64;
+            let x = sun_x - (dx / length * offset).round() as i16;
+            let y = sun_y - (dy / length * offset).round() as i16;
+            canvas.aa_filled_circle(x, y, 2, theme.dome);
+            transits.push(planet.name.clone());
+            continue;
+        }
+        let (size_x, size_y) = planet_sprite_size(&planet.name, view.zoom);
+        let (label_x, label_y) = (x, y);
+        if is_telescopic(engine.get_planet_magnitude(planet), alt, display) {
+            // only an outline, there is nothing to see by eye
+            let color = redden_at(alt, mix_color(theme.dome, theme.planet, TELESCOPIC_DIM));
+            canvas.aa_circle(x, y, size_y * 3 / 8, color).unwrap();
+            let label = format!("{} (telescopic)", planet.name);
+            canvas.text(&label, scene.small_font, x, y, size_y / 2 + 2, theme.text);
+            continue;
+        }
+        let (dx, dy) = seeing_offset(alt, i as u32, scene.clock, display.seeing);
+        let (x, y) = (x + dx, y + dy);
+        if display.glow {
+            let strength = glow_strength(engine.get_planet_magnitude(planet));
+            if strength > 0.0 {
+                canvas.glow(x, y, size_y / 2, strength, redden_at(alt, theme.planet));
+            }
+        }
+        match &scene.planet_textures[i] {
+            Some(texture) => {
+                let rect = Rect::new(
+                    (x - size_x / 2).into(),
+                    (y - size_y / 2).into(),
+                    size_x.try_into().unwrap(),
+                    size_y.try_into().unwrap(),
+                );
+                canvas.copy(texture, None, rect).unwrap();
+                if display.atmosphere {
+                    canvas.tint(rect, redden_at(alt, Color::WHITE));
+                }
+            }
+            None => canvas.aa_filled_circle(x, y, size_y * 3 / 8, redden_at(alt, theme.planet)),
+        }
+        // a disk big enough to see doesn't scintillate
+        if display.twinkle && view.zoom < PLANET_TWINKLE_ZOOM {
+            // planets can only dim, through a translucent veil in the dome color
+            let twinkle = twinkle_factor(alt, i as u32, scene.clock, PLANET_TWINKLE_DEPTH);
+            let veil = ((1.0 - twinkle).max(0.0) * 255.0).round() as u8;
+            let Color { r, g, b, .. } = theme.dome;
+            canvas
+                .filled_circle(x, y, size_x / 2, Color::RGBA(r, g, b, veil))
+                .unwrap();
+        }
+        if high(alt) {
+            canvas
+                .aa_circle(label_x, label_y, size_y / 2 + HIGHLIGHT_GAP, theme.highlight)
+                .unwrap();
+        }
         canvas.text(
-            "W",
-            &font,
-            (width / 2 + radius - 10).try_into().unwrap(),
-            ((height - PANEL_SIZE) / 2 + 14).try_into().unwrap(),
-            0,
+            &planet.name,
+            scene.small_font,
+            label_x,
+            label_y,
+            size_y / 2 + 2,
+            theme.text,
         );
+    }
+
+    let (x, y) = horizontal_to_canvas(moon_alt, moon_az, size, layout, view);
+    let moon_size = disk_size(moon_radius, moon_alt, size, layout, view);
+    let moon_rect = Rect::new(
+        (x - moon_size).into(),
+        (y - moon_size).into(),
+        (2 * moon_size) as u32,
+        (2 * moon_size) as u32,
+    );
+    canvas
+        .copy_ex(
+            &scene.moon_phases[moon_phase_frame(lunar_phase, scene.moon_phases.len())],
+            None,
+            moon_rect,
+            moon_angle / PI * 180.0,
+            None,
+            false,
+            false,
+        )
+        .unwrap();
+    if display.atmosphere {
+        canvas.tint(moon_rect, redden_at(moon_alt, Color::WHITE));
+    }
+    if high(moon_alt) {
+        canvas
+            .aa_circle(x, y, moon_size + HIGHLIGHT_GAP, theme.highlight)
+            .unwrap();
+    }
+    if display.libration {
+        // the mean center of the visible face, pushed off the disk center by the libration
+        let (longitude, latitude) = engine.get_moon_libration();
+        let (dx, dy) = (-moon_size as f64 * longitude.sin(), -moon_size as f64 * latitude.sin());
+        let (sin, cos) = moon_angle.sin_cos();
+        let marker_x = x + (dx * cos - dy * sin).round() as i16;
+        let marker_y = y + (dx * sin + dy * cos).round() as i16;
+        canvas.aa_circle(marker_x, marker_y, 2, theme.highlight).unwrap();
+    }
+    canvas.text("Moon", scene.small_font, x, y, 15, theme.text);
+
+    let selected = scene
+        .selected
+        .and_then(|selection| engine.get_object_position(selection, scene.stars, scene.planets));
+    if let Some((alt, az)) = selected {
+        let (x, y) = horizontal_to_canvas(alt, az, size, layout, view);
+        canvas.aa_circle(x, y, 20, theme.highlight).unwrap();
+    }
+
+    if display.minimap {
+        draw_minimap(canvas, size, layout, view, engine, scene);
+    }
+
+    if display.crosshair {
+        let (x, y) = stereo_to_canvas(view.pan.0, view.pan.1, size, layout, view);
+        canvas
+            .hline(x - CROSSHAIR_SIZE, x + CROSSHAIR_SIZE, y, theme.highlight)
+            .unwrap();
+        canvas
+            .vline(x, y - CROSSHAIR_SIZE, y + CROSSHAIR_SIZE, theme.highlight)
+            .unwrap();
+    }
+
+    if display.hour_ring {
+        draw_hour_ring(canvas, size, layout, view, engine, scene.small_font, theme);
+    }
+
+    if display.compass {
+        canvas.draw_compass_rose(size, layout, view, scene.small_font, theme.text);
+    }
+    for (label, az) in [("N", 0.0), ("E", PI / 2.0), ("S", PI), ("W", 3.0 * PI / 2.0)] {
+        // nudged inwards from the horizon and centered vertically on it
+        let (x, y) = inset_horizon_point(az, CARDINAL_INSET, size, layout, view);
+        let half_height = scene.font.height() as i16 / 2;
+        canvas.text(
+            label,
+            scene.font,
+            x.round() as i16,
+            y.round() as i16,
+            -half_height,
+            theme.text,
+        );
+    }
+
+    (transits, stats)
+}
+
+fn draw_ecliptic(
+    canvas: &mut impl Painter,
+    size: (u32, u32),
+    layout: Layout,
+    view: &View,
+    engine: &Engine,
+    color: Color,
+) {
+    match view.projection {
+        Projection::Stereographic => {
+            let ecliptic_points = engine.get_ecliptic_points(3);
+            let ecliptic = circle_from_three_points(
+                stereographic_projection(ecliptic_points[0].0, ecliptic_points[0].1),
+                stereographic_projection(ecliptic_points[1].0, ecliptic_points[1].1),
+                stereographic_projection(ecliptic_points[2].0, ecliptic_points[2].1),
+            );
+            let center = stereo_to_canvas_exact(ecliptic.0, ecliptic.1, size, layout, view);
+            let radius = canvas_scale(size, layout, view) * ecliptic.2;
+            canvas.clipped_circle(center, radius, dome_area(size, layout), color);
+        }
+        // only the stereographic projection keeps circles round, the fisheye needs the whole curve
+        Projection::Fisheye => {
+            let mut ecliptic_points = engine.get_ecliptic_points(ECLIPTIC_SAMPLES);
+            ecliptic_points.push(ecliptic_points[0]);
+            canvas.horizontal_curve(&ecliptic_points, size, layout, view, color);
+        }
+    }
+}
+
+fn star_label<'s>(star: &'s Star, magnitude: f64, display: &Display, view: &View) -> Option<&'s String> {
+    match display.label_mode {
+        _ if magnitude > label_limit(display.label_magnitude, view.zoom) => None,
+        LabelMode::Proper => star.name.as_ref(),
+        LabelMode::Designation => star.designation.as_ref(),
+        LabelMode::None => None,
+    }
+}
+
+struct StarLabel<'s> {
+    text: &'s str,
+    x: i16,
+    y: i16,
+    radius: i16, // of the star glyph
+}
+
+fn glyph_box(x: i16, y: i16, radius: i16) -> Rect {
+    let radius = i32::from(radius.max(1));
+    Rect::new(
+        i32::from(x) - radius,
+        i32::from(y) - radius,
+        2 * radius as u32 + 1,
+        2 * radius as u32 + 1,
+    )
+}
+
+fn overlap(a: &Rect, b: &Rect) -> bool {
+    a.left() < b.right() && b.left() < a.right() && a.top() < b.bottom() && b.top() < a.bottom()
+}
+
+fn place_label(default: Rect, anchor: (i16, i16), radius: i16, bounds: (u32, u32), occupied: &[Rect]) -> (Rect, bool) {
+    // the usual spot over the star if it is clear, else the first clear one around it at the end of a leader,
+    // and over the star after all when there is none
+    let clear = |rect: &Rect| {
+        let inside =
+            rect.left() >= 0 && rect.top() >= 0 && rect.right() <= bounds.0 as i32 && rect.bottom() <= bounds.1 as i32;
+        inside && !occupied.iter().any(|other| overlap(rect, other))
+    };
+    if clear(&default) {
+        return (default, false);
+    }
+    let (x, y) = (i32::from(anchor.0), i32::from(anchor.1));
+    let (w, h) = default.size();
+    let (width, height) = (w as i32, h as i32);
+    let near = i32::from(radius) + LEADER_LENGTH;
+    let diagonal = i32::from(radius) + LEADER_LENGTH * 7 / 10;
+    let candidates = [
+        (x + near, y - height / 2),
+        (x - near - width, y - height / 2),
+        (x + diagonal, y - diagonal - height),
+        (x - diagonal - width, y - diagonal - height),
+        (x + diagonal, y + diagonal),
+        (x - diagonal - width, y + diagonal),
+        (x - width / 2, y + near),
+        (x - width / 2, y - near - height),
+    ];
+    candidates
+        .into_iter()
+        .map(|(left, top)| Rect::new(left, top, w, h))
+        .find(clear)
+        .map_or((default, false), |rect| (rect, true))
+}
+
+fn draw_star_labels(
+    canvas: &mut impl Painter,
+    labels: &[StarLabel],
+    glyphs: &[Rect],
+    area: (u32, u32), // the dome's part of the pane, labels are kept inside it
+    font: &Font,
+    leaders: bool,
+    color: Color,
+) {
+    if !leaders {
+        for label in labels {
+            canvas.text(label.text, font, label.x, label.y, LABEL_OFFSET, color);
+        }
+        return;
+    }
+    // the brightest stars come first and get the first pick of the spots
+    let mut occupied = glyphs.to_vec();
+    for label in labels {
+        let default = text_box(font.size_of(label.text).unwrap(), label.x, label.y, LABEL_OFFSET);
+        let (rect, leader) = place_label(default, (label.x, label.y), label.radius, area, &occupied);
+        if leader {
+            // from just off the star to the nearest point of the label
+            let (x, y) = (i32::from(label.x), i32::from(label.y));
+            let end_x = x.clamp(rect.left(), rect.right() - 1);
+            let end_y = y.clamp(rect.top(), rect.bottom() - 1);
+            let (dx, dy) = ((end_x - x) as f64, (end_y - y) as f64);
+            let start = (label.radius + 2) as f64 / dx.hypot(dy).max(f64::EPSILON);
+            canvas.segment(
+                label.x + (dx * start).round() as i16,
+                label.y + (dy * start).round() as i16,
+                end_x as i16,
+                end_y as i16,
+                color,
+            );
+        }
+        canvas.text(
+            label.text,
+            font,
+            rect.center().x() as i16,
+            rect.bottom() as i16,
+            0,
+            color,
+        );
+        occupied.push(rect);
+    }
+}
+
+fn draw_chart(
+    canvas: &mut impl Painter,
+    size: (u32, u32),
+    view: &View,
+    engine: &Engine,
+    scene: &Scene,
+    display: &Display,
+) {
+    // the sky as a printable chart: the grid, the ecliptic, the stars by magnitude and the solar system as plain disks,
+    // without the daylight, the textures and the effects that only make sense on screen
+    let (theme, layout) = (scene.theme, scene.layout);
+    let radius = canvas_scale(size, layout, view).round() as i16;
+    let (center_x, center_y) = stereo_to_canvas(0.0, 0.0, size, layout, view);
+    canvas.aa_filled_circle(center_x, center_y, radius, theme.dome);
+    if display.gradient {
+        let horizon = mix_color(theme.dome, theme.grid, GRADIENT_HORIZON);
+        canvas.radial_gradient(center_x, center_y, radius, theme.dome, horizon);
+    }
+    canvas.draw_azimuthal_grid(size, layout, view, scene.grid, theme.grid);
+    draw_ecliptic(canvas, size, layout, view, engine, theme.ecliptic);
+
+    let (limit, extinction, gamma) = sky_conditions(display);
+    let mut labels = Vec::new();
+    let mut glyphs = Vec::new();
+    for star in visible_stars(scene.stars, limit) {
+        let (alt, az) = engine.get_star_position(star);
+        let magnitude = engine.get_star_magnitude(star) + extinction * (get_airmass(alt) - 1.0);
+        if alt < 0.0 || magnitude > limit {
+            continue;
+        }
+        let (x, y) = horizontal_to_canvas(alt, az, size, layout, view);
+        let (size_px, brightness) = magnitude_to_size_and_brightness(magnitude);
+        let brightness = conditions::apply_gamma(brightness, gamma);
+        let color = theme.star_color(star_tint(star), brightness);
+        canvas.glyph(display.glyph, x, y, size_px, color);
+        glyphs.push(glyph_box(x, y, size_px));
+        if let Some(label) = star_label(star, magnitude, display, view) {
+            labels.push(StarLabel {
+                text: label,
+                x,
+                y,
+                radius: size_px,
+            });
+        }
+    }
+    draw_star_labels(
+        canvas,
+        &labels,
+        &glyphs,
+        dome_area(size, layout),
+        scene.small_font,
+        display.leaders,
+        theme.text,
+    );
+
+    let (sun_alt, sun_az) = engine.get_sun_position();
+    if sun_alt >= 0.0 {
+        let (x, y) = horizontal_to_canvas(sun_alt, sun_az, size, layout, view);
+        let sun_size = disk_size(engine.get_sun_angular_radius(), sun_alt, size, layout, view);
+        canvas.aa_filled_circle(x, y, sun_size, theme.sun);
+        canvas.text("Sun", scene.small_font, x, y, sun_size, theme.text);
+    }
+    for planet in scene.planets {
+        let (alt, az, _) = engine.get_planet_position(planet);
+        if engine.is_observer(planet) || alt < 0.0 {
+            continue;
+        }
+        let (x, y) = horizontal_to_canvas(alt, az, size, layout, view);
+        let (_, size_y) = planet_sprite_size(&planet.name, view.zoom);
+        if is_telescopic(engine.get_planet_magnitude(planet), alt, display) {
+            canvas.ring(x, y, size_y * 3 / 8, theme.planet);
+            let label = format!("{} (telescopic)", planet.name);
+            canvas.text(&label, scene.small_font, x, y, size_y / 2 + 2, theme.text);
+            continue;
+        }
+        canvas.aa_filled_circle(x, y, size_y * 3 / 8, theme.planet);
+        canvas.text(&planet.name, scene.small_font, x, y, size_y / 2 + 2, theme.text);
+    }
+    let (moon_alt, moon_az, _, _) = engine.get_moon_position();
+    if moon_alt >= 0.0 {
+        let (x, y) = horizontal_to_canvas(moon_alt, moon_az, size, layout, view);
+        let moon_size = disk_size(engine.get_moon_angular_radius(), moon_alt, size, layout, view);
+        canvas.ring(x, y, moon_size, theme.text);
+        canvas.text("Moon", scene.small_font, x, y, moon_size, theme.text);
+    }
+
+    if display.compass {
+        canvas.draw_compass_rose(size, layout, view, scene.small_font, theme.text);
+    }
+    for (label, az) in [("N", 0.0), ("E", PI / 2.0), ("S", PI), ("W", 3.0 * PI / 2.0)] {
+        let (x, y) = inset_horizon_point(az, CARDINAL_INSET, size, layout, view);
+        let half_height = scene.font.height() as i16 / 2;
+        canvas.text(
+            label,
+            scene.font,
+            x.round() as i16,
+            y.round() as i16,
+            -half_height,
+            theme.text,
+        );
+    }
+}
+
+fn chart_path(time: DateTime<Utc>) -> PathBuf {
+    PathBuf::from(time.format(CHART_FILE).to_string())
+}
+
+fn draw_minimap(canvas: &mut Screen, size: (u32, u32), layout: Layout, view: &View, engine: &Engine, scene: &Scene) {
+    let theme = scene.theme;
+    let radius = MINIMAP_RADIUS as f64;
+    let (center_x, center_y) = (
+        MINIMAP_RADIUS + 10,
+        dome_area(size, layout).1 as i16 - MINIMAP_RADIUS - 10,
+    );
+    let flip = if view.flipped { -1.0 } else { 1.0 };
+    let to_minimap = |alt: f64, az: f64| {
+        let (x, y) = orthographic_projection(alt.max(0.0), az);
+        (
+            center_x - (radius * x * flip).round() as i16,
+            center_y - (radius * y).round() as i16,
+        )
+    };
+
+    canvas
+        .filled_circle(center_x, center_y, MINIMAP_RADIUS, theme.dome)
+        .unwrap();
+    canvas
+        .aa_circle(center_x, center_y, MINIMAP_RADIUS, theme.grid)
+        .unwrap();
+    for star in visible_stars(scene.stars, MINIMAP_MAGNITUDE) {
+        let (alt, az) = engine.get_star_position(star);
+        if alt > 0.0 {
+            let (x, y) = to_minimap(alt, az);
+            canvas.pixel(x, y, theme.star).unwrap();
+        }
+    }
+    let (alt, az) = engine.get_sun_position();
+    if alt > 0.0 {
+        let (x, y) = to_minimap(alt, az);
+        canvas.aa_filled_circle(x, y, 2, theme.sun);
+    }
+
+    // the edge of the visible part of the dome, traced back through the projection
+    let (width, height) = dome_area(size, layout);
+    let (width, height) = (width as f64, height as f64);
+    let samples = MINIMAP_OUTLINE_SAMPLES as f64;
+    let outline: Vec<(i16, i16)> = (0..4 * MINIMAP_OUTLINE_SAMPLES)
+        .map(|i| {
+            let t = (i % MINIMAP_OUTLINE_SAMPLES) as f64 / samples;
+            let (x, y) = match i / MINIMAP_OUTLINE_SAMPLES {
+                0 => (t * width, 0.0),
+                1 => (width, t * height),
+                2 => ((1.0 - t) * width, height),
+                _ => (0.0, (1.0 - t) * height),
+            };
+            let (sx, sy) = canvas_to_stereo(x, y, size, layout, view);
+            let (alt, az) = view.projection.unproject(sx, sy);
+            to_minimap(alt, az)
+        })
+        .collect();
+    for (i, &(x1, y1)) in outline.iter().enumerate() {
+        let (x2, y2) = outline[(i + 1) % outline.len()];
+        canvas.aa_line(x1, y1, x2, y2, theme.highlight).unwrap();
+    }
+}
+
+fn nudge_direction(action: Action) -> Option<(f64, f64)> {
+    // northwards and eastwards
+    match action {
+        Action::WalkNorth => Some((1.0, 0.0)),
+        Action::WalkSouth => Some((-1.0, 0.0)),
+        Action::WalkEast => Some((0.0, 1.0)),
+        Action::WalkWest => Some((0.0, -1.0)),
+        _ => None,
+    }
+}
+
+fn parse_longitude(text: &str) -> Option<f64> {
+    // degrees east either from -180 to 180 or from 0 to 360, kept in radians from 0 to 2 pi
+    let degrees = text.trim().parse::<f64>().ok()?;
+    (-180.0..=360.0)
+        .contains(&degrees)
+        .then(|| degrees.rem_euclid(360.0) / 180.0 * PI)
+}
+
+fn nudge_location(latitude: f64, longitude: f64, action: Action, step: f64) -> (f64, f64) {
+    // the poles stop the walk, the longitude goes round
+    let (north, east) = nudge_direction(action).unwrap_or((0.0, 0.0));
+    (
+        (latitude + north * step).clamp(-PI / 2.0, PI / 2.0),
+        (longitude + east * step).rem_euclid(2.0 * PI),
+    )
+}
+
+fn pane_rects(size: (u32, u32), count: usize) -> Vec<Rect> {
+    let (width, height) = size;
+    let pane_width = width / count as u32;
+    (0..count)
+        .map(|i| Rect::new((i as u32 * pane_width) as i32, 0, pane_width, height))
+        .collect()
+}
+
+fn main() {
+    let resources_path = find_resources();
+    let config = Config::load(&config_path());
+    let preset = PRESETS
+        .iter()
+        .position(|&name| Some(name) == config.get("theme", "preset"))
+        .unwrap_or(0);
+    let bindings = Bindings::load(&config);
+    let notation = match config.get("", "coordinates") {
+        Some(name) => Notation::parse(name).unwrap_or_else(|| {
+            eprintln!("Unknown coordinates {}, using decimal", name);
+            Notation::Decimal
+        }),
+        None => Notation::Decimal,
+    };
+    let glyph = match config.get("", "star_glyph") {
+        Some(name) => Glyph::parse(name).unwrap_or_else(|| {
+            eprintln!("Unknown star_glyph {}, using dot", name);
+            Glyph::Dot
+        }),
+        None => Glyph::Dot,
+    };
+    let quality = match config.get("", "antialiasing") {
+        Some(name) => Quality::parse(name).unwrap_or_else(|| {
+            eprintln!("Unknown antialiasing {}, using default", name);
+            Quality::Default
+        }),
+        None => Quality::Default,
+    };
+    let label_magnitude = match config.get("", "label_magnitude").map(str::parse::<f64>) {
+        Some(Ok(magnitude)) => magnitude,
+        Some(Err(_)) => {
+            eprintln!("Invalid label_magnitude, using {}", LABEL_MAGNITUDE);
+            LABEL_MAGNITUDE
+        }
+        None => LABEL_MAGNITUDE,
+    };
+    let world = match config.get("", "world") {
+        Some(name) => WORLDS
+            .iter()
+            .position(|world| world.name.eq_ignore_ascii_case(name))
+            .unwrap_or_else(|| {
+                eprintln!("Unknown world {}, using {}", name, EARTH.name);
+                0
+            }),
+        None => 0,
+    };
+    let frame_period = frame_period(&config);
+    let idle_period = idle_period(&config);
+    let initial_size = size_setting(&config, "--size", "window_size", MIN_SIZE..=MAX_SIZE, INITIAL_SIZE);
+    let layout = load_layout(&config);
+    let panel_fields = panel::load(&config);
+    let sky_conditions = conditions::load(&config);
+    let trail_length = match config.get("", "trail_length").map(str::parse::<usize>) {
+        Some(Ok(length)) => length,
+        Some(Err(_)) => {
+            eprintln!("Invalid trail_length, using {}", TRAIL_LENGTH);
+            TRAIL_LENGTH
+        }
+        None => TRAIL_LENGTH,
+    };
+    let grid = load_grid(&config);
+    let calibration = load_calibration(&config);
+    let bookmarks_path = PathBuf::from(config.get("", "bookmarks").unwrap_or(BOOKMARKS_FILE));
+    let session_path = PathBuf::from(config.get("", "session").unwrap_or(SESSION_FILE));
+    let mut theme = Theme::load(&config, config.get("theme", "preset").unwrap_or(PRESETS[0]));
+    let sdl_context = sdl2::init().unwrap_or_else(|error| startup_error("Could not initialize SDL", error));
+    let video_subsystem = sdl_context
+        .video()
+        .unwrap_or_else(|error| startup_error("Could not open the display, is a window system available?", error));
+
+    let mut window = video_subsystem
+        .window("Antikythera", initial_size, initial_size + layout.panel)
+        .resizable()
+        .position_centered()
+        .build()
+        .unwrap_or_else(|error| startup_error("Could not create the window", error));
+    window
+        .set_minimum_size(MIN_SIZE, MIN_SIZE + layout.panel)
+        .unwrap_or_else(|error| startup_error("Could not create the window", error));
+
+    let canvas = window
+        .into_canvas()
+        .build()
+        .unwrap_or_else(|error| startup_error("Could not create a renderer, is a graphics driver available?", error));
+    // only bookmark labels and date ranges take text input
+    video_subsystem.text_input().stop();
+
+    let mut stars =
+        load_stars(&resources_path).unwrap_or_else(|error| startup_error("Could not load the stars", error));
+    // smooth scaling for the planet textures blown up by the zoom
+    sdl2::hint::set("SDL_RENDER_SCALE_QUALITY", "1");
+    let texture_creator = canvas.texture_creator();
+    let mut canvas = Screen::new(canvas, &texture_creator, quality);
+    let mut textures = Textures::new(&texture_creator);
+    let moon_phases = load_moon_phases(&mut textures, &resources_path);
+    let mut planets =
+        load_planets(&resources_path).unwrap_or_else(|error| startup_error("Could not load the planets", error));
+    let constellations = boundaries::load(&resources_path.join("data/boundaries.dat"))
+        .unwrap_or_else(|error| startup_error("Could not load the constellation boundaries", error));
+    let mut planet_textures = load_planet_textures(&mut textures, &planets, &resources_path);
+    let ttf_context = ttf::init().unwrap_or_else(|error| startup_error("Could not initialize SDL_ttf", error));
+    let font = load_font(&ttf_context, &resources_path, 20);
+    let small_font = load_font(&ttf_context, &resources_path, 14);
+    canvas
+        .set_logical_size(initial_size, initial_size + layout.panel)
+        .unwrap_or_else(|error| startup_error("Could not size the renderer", error));
+    let mut event_pump = sdl_context
+        .event_pump()
+        .unwrap_or_else(|error| startup_error("Could not read input events", error));
+
+    // the monotonic clock, the wall clock can jump when it is set or synchronized
+    let mut real_time = Instant::now();
+    let mut focused = true;
+    let mut session = Session {
+        panes: vec![Pane {
+            latitude: LAT,
+            longitude: LON,
+            time: Utc::now(),
+            step: NEUTRAL_STEP,
+            view: View::default(),
+            flight: None,
+            trip: None,
+            home_latitude: None,
+            world,
+            trails: Trails::default(),
+        }],
+        focus: 0,
+        display: Display::new(label_magnitude, notation, glyph),
+        selected: None,
+        preset,
+        bookmarks: bookmarks::load(&bookmarks_path),
+    };
+    // off unless asked for on the command line
+    let requests = cli_option("--listen").map(|port| {
+        let number = port
+            .parse::<u16>()
+            .unwrap_or_else(|error| startup_error(&format!("Invalid port {}", port), error));
+        server::listen(number)
+            .unwrap_or_else(|error| startup_error(&format!("Could not listen on port {}", port), error))
+    });
+    let mut mode = Mode::Default;
+    let mut day_lengths: Option<((i32, f64, f64), Vec<f64>)> = None;
+    // for each pane, worked out again when its day or place changes
+    let mut rise_points: Vec<Option<(RisePointsKey, RisePoints)>> = Vec::new();
+    // for the focused dome, worked out again when the selection, its day or place changes
+    let mut selection_times: Option<(SelectionTimesKey, Vec<String>)> = None;
+    // the hour angle of the selection in the focused dome on the last frame, and when it last culminated
+    let mut meridian: Option<(SkyObject, usize, f64)> = None;
+    let mut culmination: Option<(SkyObject, Instant)> = None;
+    let mut message: Option<(String, Instant)> = None;
+    let mut frame_times = FrameTimes::new();
+    let launch = Instant::now();
+    let mut buffer = String::new();
+    let mut dragged = 0;
+    let mut ruler: Vec<(f64, f64)> = Vec::new(); // the measured points, as (alt, az)
+
+    'running: loop {
+        let frame_start = Instant::now();
+        canvas.set_draw_color(theme.background);
+        canvas.clear();
+        let rects = pane_rects(canvas.logical_size(), session.panes.len());
+        let mut click = None;
+        let mut export_chart = false;
+        for event in event_pump.poll_iter() {
+            // any input interrupts a go to
+            if let Event::KeyDown { .. } | Event::MouseButtonDown { .. } | Event::MouseWheel { .. } = event {
+                session.panes[session.focus].flight = None;
+            }
+            if let Event::KeyDown { .. } = event {
+                session.panes[session.focus].trip = None;
+            }
+            match event {
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Left,
+                    x,
+                    y,
+                    ..
+                } => {
+                    // clicking a pane gives it the keyboard focus
+                    if let Some(index) = rects.iter().position(|rect| rect.contains_point((x, y))) {
+                        session.focus = index;
+                    }
+                    dragged = 0;
+                }
+                Event::MouseButtonUp {
+                    mouse_btn: MouseButton::Left,
+                    x,
+                    y,
+                    ..
+                } if dragged < DRAG_THRESHOLD => {
+                    click = Some((x - rects[session.focus].x(), y));
+                }
+                Event::MouseMotion {
+                    mousestate, xrel, yrel, ..
+                } if mousestate.left() => {
+                    dragged += xrel.abs() + yrel.abs();
+                    if let Mode::Scrub(longitude) = &mut mode {
+                        *longitude += xrel as f64 * SCRUB_STEP;
+                        let pane = &mut session.panes[session.focus];
+                        pane.time = scrub_time(pane, &calibration, *longitude);
+                    } else if dragged >= DRAG_THRESHOLD {
+                        let rect = rects[session.focus];
+                        session.panes[session.focus]
+                            .view
+                            .drag(xrel, yrel, (rect.width(), rect.height()), layout);
+                    }
+                }
+                Event::MouseWheel { y, .. } => {
+                    session.panes[session.focus].view.zoom_by(ZOOM_STEP.powi(y));
+                }
+                Event::Window {
+                    win_event: WindowEvent::Resized(width, height),
+                    ..
+                } => {
+                    let width = (width as u32).max(MIN_SIZE);
+                    let height = (height as u32).max(MIN_SIZE + layout.panel);
+                    canvas.set_logical_size(width, height).unwrap();
+                }
+                Event::Window {
+                    win_event: WindowEvent::FocusLost,
+                    ..
+                } => focused = false,
+                Event::Window {
+                    win_event: WindowEvent::FocusGained,
+                    ..
+                } => focused = true,
+                Event::Quit { .. } => break 'running,
+                Event::TextInput { text, .. } if matches!(mode, Mode::SetBookmark | Mode::SetEclipseRange) => {
+                    buffer.push_str(&text);
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    keymod,
+                    ..
+                } if matches!(mode, Mode::Default)
+                    && keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD)
+                    && bindings.ctrl_action(keycode).and_then(nudge_direction).is_some() =>
+                {
+                    let step = if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                        FINE_NUDGE_STEP
+                    } else {
+                        NUDGE_STEP
+                    };
+                    let pane = &mut session.panes[session.focus];
+                    if let Some(action) = bindings.ctrl_action(keycode) {
+                        (pane.latitude, pane.longitude) = nudge_location(pane.latitude, pane.longitude, action, step);
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    keymod,
+                    ..
+                } => match mode {
+                    Mode::Default => match bindings.action(keycode) {
+                        Some(Action::StepBackward) => {
+                            let pane = &mut session.panes[session.focus];
+                            pane.step = if pane.step > 0 { pane.step - 1 } else { pane.step };
+                        }
+                        Some(Action::StepForward) => {
+                            let pane = &mut session.panes[session.focus];
+                            pane.step = if pane.step < STEPS.len() - 1 {
+                                pane.step + 1
+                            } else {
+                                pane.step
+                            };
+                        }
+                        Some(Action::Reverse) => {
+                            let pane = &mut session.panes[session.focus];
+                            if pane.step == NEUTRAL_STEP {
+                                message = Some(("Real time runs forward only".to_string(), Instant::now()));
+                            }
+                            pane.step = reverse_step(pane.step);
+                        }
+                        Some(Action::SetLatitude) => {
+                            mode = Mode::SetLatitude;
+                            buffer = String::new();
+                        }
+                        Some(Action::SetLongitude) => {
+                            mode = Mode::SetLongitude;
+                            buffer = String::new();
+                        }
+                        Some(Action::Aberration) => {
+                            session.display.aberration = !session.display.aberration;
+                        }
+                        Some(Action::Moonlight) => {
+                            session.display.moonlight = !session.display.moonlight;
+                        }
+                        Some(Action::DoubleMarkers) => {
+                            session.display.double_markers = !session.display.double_markers;
+                        }
+                        Some(Action::FpsOverlay) => {
+                            session.display.fps_overlay = !session.display.fps_overlay;
+                        }
+                        Some(Action::Theme) => {
+                            session.preset = (session.preset + 1) % PRESETS.len();
+                            theme = Theme::load(&config, PRESETS[session.preset]);
+                        }
+                        Some(Action::Elongations) => {
+                            message = Some((
+                                report_elongations(
+                                    &WORLDS[session.panes[session.focus].world],
+                                    &calibration,
+                                    &planets,
+                                    session.panes[session.focus].time,
+                                ),
+                                Instant::now(),
+                            ));
+                        }
+                        Some(Action::GalacticGrid) => {
+                            session.display.galactic_grid = !session.display.galactic_grid;
+                        }
+                        Some(Action::Conditions) => {
+                            // through the presets in turn, then back to none
+                            let display = &mut session.display;
+                            let current = display.conditions.as_ref().and_then(|conditions| {
+                                sky_conditions.iter().position(|known| known.name == conditions.name)
+                            });
+                            let next = match current {
+                                Some(index) => sky_conditions.get(index + 1),
+                                None => sky_conditions.first(),
+                            };
+                            display.conditions = next.cloned();
+                            let text = match next {
+                                Some(conditions) => format!("Sky: {}", conditions.name),
+                                None => "Sky conditions off".to_string(),
+                            };
+                            message = Some((text, Instant::now()));
+                        }
+                        Some(Action::Boundaries) => {
+                            session.display.boundaries = !session.display.boundaries;
+                            if session.display.boundaries && constellations.is_empty() {
+                                let text = "No data/boundaries.dat, it isn't bundled, see the README".to_string();
+                                message = Some((text, Instant::now()));
+                            }
+                        }
+                        Some(Action::HourRing) => {
+                            session.display.hour_ring = !session.display.hour_ring;
+                        }
+                        Some(Action::StarGlyph) => {
+                            session.display.glyph = session.display.glyph.next();
+                            let text = format!("Star glyphs: {}", session.display.glyph.name());
+                            message = Some((text, Instant::now()));
+                        }
+                        Some(Action::Stats) => {
+                            session.display.stats = !session.display.stats;
+                        }
+                        Some(Action::Compass) => {
+                            session.display.compass = !session.display.compass;
+                        }
+                        Some(Action::RisePoints) => {
+                            session.display.rise_points = !session.display.rise_points;
+                        }
+                        Some(Action::LunarNodes) => {
+                            session.display.lunar_nodes = !session.display.lunar_nodes;
+                        }
+                        Some(Action::Trails) => {
+                            // trails start afresh each time they are turned on
+                            session.display.trails = !session.display.trails;
+                            for pane in &mut session.panes {
+                                pane.trails.clear();
+                            }
+                        }
+                        Some(Action::Crosshair) => {
+                            session.display.crosshair = !session.display.crosshair;
+                        }
+                        Some(Action::DayLength) => {
+                            session.display.day_length_chart = !session.display.day_length_chart;
+                        }
+                        Some(Action::Twinkle) => {
+                            session.display.twinkle = !session.display.twinkle;
+                        }
+                        Some(Action::Highlight) => {
+                            // up in steps, with shift back down, off past either end
+                            let steps = MAX_HIGHLIGHT_ALTITUDE / HIGHLIGHT_STEP + 1;
+                            let step = session.display.highlight_altitude / HIGHLIGHT_STEP;
+                            let step = if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                                (step + steps - 1) % steps
+                            } else {
+                                (step + 1) % steps
+                            };
+                            session.display.highlight_altitude = step * HIGHLIGHT_STEP;
+                            let text = match session.display.highlight_altitude {
+                                0 => "Altitude highlight off".to_string(),
+                                altitude => format!("Highlighting objects above {}°", altitude),
+                            };
+                            message = Some((text, Instant::now()));
+                        }
+                        Some(Action::Seeing) => {
+                            session.display.seeing = (session.display.seeing + 1) % (MAX_SEEING + 1);
+                            let text = format!("Seeing {} of {}", session.display.seeing, MAX_SEEING);
+                            message = Some((text, Instant::now()));
+                        }
+                        Some(Action::World) => {
+                            let pane = &mut session.panes[session.focus];
+                            pane.world = (pane.world + 1) % WORLDS.len();
+                            pane.trails.clear();
+                            // the new world can't be looked at from itself
+                            if let Some(SkyObject::Planet(index)) = session.selected {
+                                if planets[index].name == WORLDS[pane.world].name {
+                                    session.selected = None;
+                                }
+                            }
+                            message = Some((format!("Observing from {}", WORLDS[pane.world].name), Instant::now()));
+                        }
+                        Some(Action::Atmosphere) => {
+                            session.display.atmosphere = !session.display.atmosphere;
+                        }
+                        Some(Action::Reload) => {
+                            // all or nothing, a file that doesn't parse leaves the sky as it was
+                            let reloaded = load_stars(&resources_path).and_then(|new_stars| {
+                                let new_planets = load_planets(&resources_path)?;
+                                Ok((new_stars, new_planets))
+                            });
+                            match reloaded {
+                                Ok((new_stars, new_planets)) => {
+                                    textures.clear();
+                                    planet_textures =
+                                        load_planet_textures(&mut textures, &new_planets, &resources_path);
+                                    stars = new_stars;
+                                    planets = new_planets;
+                                    // indices into the old catalogs mean nothing now
+                                    session.selected = None;
+                                    for pane in &mut session.panes {
+                                        pane.trails.clear();
+                                    }
+                                    let text = format!("Reloaded {} stars and {} planets", stars.len(), planets.len());
+                                    message = Some((text, Instant::now()));
+                                }
+                                Err(error) => {
+                                    eprintln!("Could not reload the catalogs: {}", error);
+                                    message = Some((format!("Reload failed: {}", error), Instant::now()));
+                                }
+                            }
+                        }
+                        Some(Action::Projection) => {
+                            let pane = &mut session.panes[session.focus];
+                            let (projection, text) = match pane.view.projection {
+                                Projection::Stereographic => (Projection::Fisheye, "Fisheye projection"),
+                                Projection::Fisheye => (Projection::Stereographic, "Stereographic projection"),
+                            };
+                            pane.view = pane.view.with_projection(projection);
+                            message = Some((text.to_string(), Instant::now()));
+                        }
+                        Some(Action::ExportSvg) => {
+                            // drawn below once the sky for this frame is known
+                            export_chart = true;
+                        }
+                        Some(Action::Leaders) => {
+                            session.display.leaders = !session.display.leaders;
+                        }
+                        Some(Action::ZodiacalLight) => {
+                            session.display.zodiacal_light = !session.display.zodiacal_light;
+                        }
+                        Some(Action::Gradient) => {
+                            session.display.gradient = !session.display.gradient;
+                        }
+                        Some(Action::Refraction) => {
+                            session.display.refraction = !session.display.refraction;
+                        }
+                        Some(Action::PoleTrip) => {
+                            // up to the north pole, and again back to where the trip started
+                            let pane = &mut session.panes[session.focus];
+                            let to = pane.home_latitude.take().unwrap_or_else(|| {
+                                pane.home_latitude = Some(pane.latitude);
+                                PI / 2.0
+                            });
+                            pane.trip = Some(LatitudeTrip {
+                                from: pane.latitude,
+                                to,
+                                start: Instant::now(),
+                            });
+                        }
+                        Some(Action::Glow) => {
+                            session.display.glow = !session.display.glow;
+                        }
+                        Some(Action::Cycle) => {
+                            let pane = &mut session.panes[session.focus];
+                            let engine = pane.engine(calibration);
+                            let backward = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+                            match cycle_selection(session.selected, &engine, &planets, backward) {
+                                Some(selection) => {
+                                    if let Some((alt, az)) = engine.get_object_position(selection, &stars, &planets) {
+                                        pane.flight = Some(Flight {
+                                            from: pane.view,
+                                            to: pane.view.centered_on(alt, az),
+                                            start: Instant::now(),
+                                        });
+                                    }
+                                    session.selected = Some(selection);
+                                    let name = selection_name(selection, &stars, &planets);
+                                    message = Some((format!("Selected {}", name), Instant::now()));
+                                }
+                                None => {
+                                    message = Some(("Nothing above the horizon".to_string(), Instant::now()));
+                                }
+                            }
+                        }
+                        Some(Action::GoTo) => match session.selected {
+                            Some(selection) => {
+                                let pane = &mut session.panes[session.focus];
+                                let engine = pane.engine(calibration);
+                                match engine.get_object_position(selection, &stars, &planets) {
+                                    Some((alt, az)) if alt > 0.0 => {
+                                        pane.flight = Some(Flight {
+                                            from: pane.view,
+                                            to: pane.view.centered_on(alt, az),
+                                            start: Instant::now(),
+                                        });
+                                    }
+                                    _ => {
+                                        let name = selection_name(selection, &stars, &planets);
+                                        message = Some((format!("{} is below the horizon", name), Instant::now()));
+                                    }
+                                }
+                            }
+                            None => {
+                                message = Some(("Select an object to go to".to_string(), Instant::now()));
+                            }
+                        },
+                        Some(Action::Minimap) => {
+                            session.display.minimap = !session.display.minimap;
+                        }
+                        Some(Action::Libration) => {
+                            session.display.libration = !session.display.libration;
+                        }
+                        Some(Action::TimeDisplay) => {
+                            session.display.time_display = match session.display.time_display {
+                                TimeDisplay::Clock => TimeDisplay::Solar,
+                                TimeDisplay::Solar => TimeDisplay::Sidereal,
+                                TimeDisplay::Sidereal => TimeDisplay::Clock,
+                            };
+                        }
+                        Some(Action::InfoPanel) => {
+                            session.display.info_panel = !session.display.info_panel;
+                        }
+                        Some(Action::Labels) => {
+                            session.display.label_mode = match session.display.label_mode {
+                                LabelMode::Proper => LabelMode::Designation,
+                                LabelMode::Designation => LabelMode::None,
+                                LabelMode::None => LabelMode::Proper,
+                            };
+                        }
+                        Some(Action::LabelsBrighter) => {
+                            session.display.label_magnitude -= LABEL_MAGNITUDE_STEP;
+                            let text = format!("Labels down to magnitude {:.1}", session.display.label_magnitude);
+                            message = Some((text, Instant::now()));
+                        }
+                        Some(Action::LabelsFainter) => {
+                            session.display.label_magnitude += LABEL_MAGNITUDE_STEP;
+                            let text = format!("Labels down to magnitude {:.1}", session.display.label_magnitude);
+                            message = Some((text, Instant::now()));
+                        }
+                        Some(Action::Split) => {
+                            if session.panes.len() == 1 {
+                                let pane = session.panes[0].clone();
+                                session.panes.push(pane);
+                            } else {
+                                session.panes.truncate(1);
+                            }
+                            session.focus = min(session.focus, session.panes.len() - 1);
+                        }
+                        Some(Action::Focus) => {
+                            session.focus = (session.focus + 1) % session.panes.len();
+                        }
+                        Some(Action::ResetTime) => {
+                            let pane = &mut session.panes[session.focus];
+                            pane.time = Utc::now();
+                            pane.step = NEUTRAL_STEP;
+                            message = Some(("Reset to now".to_string(), Instant::now()));
+                        }
+                        Some(Action::ResetView) => {
+                            let pane = &mut session.panes[session.focus];
+                            pane.view = View {
+                                projection: pane.view.projection,
+                                ..View::default()
+                            };
+                            message = Some(("View reset".to_string(), Instant::now()));
+                        }
+                        Some(Action::Mirror) => {
+                            let view = &mut session.panes[session.focus].view;
+                            view.flipped = !view.flipped;
+                        }
+                        Some(Action::ZoomIn) => {
+                            session.panes[session.focus].view.zoom_by(ZOOM_STEP);
+                        }
+                        Some(Action::ZoomOut) => {
+                            session.panes[session.focus].view.zoom_by(1.0 / ZOOM_STEP);
+                        }
+                        Some(Action::Bookmark) => {
+                            mode = Mode::SetBookmark;
+                            buffer = String::new();
+                            video_subsystem.text_input().start();
+                        }
+                        Some(Action::Bookmarks) => {
+                            mode = Mode::Bookmarks;
+                        }
+                        Some(Action::SaveSession) => {
+                            let text = match session::save(&session_path, &session, &stars, &planets) {
+                                Ok(()) => "Session saved".to_string(),
+                                Err(error) => format!("Couldn't save session: {}", error),
+                            };
+                            message = Some((text, Instant::now()));
+                        }
+                        Some(Action::LoadSession) => {
+                            // a file that can't be used leaves everything as it is
+                            let text = match session::load(&session_path, &mut session, &stars, &planets) {
+                                Ok(()) => {
+                                    theme = Theme::load(&config, PRESETS[session.preset]);
+                                    "Session restored".to_string()
+                                }
+                                Err(error) => {
+                                    eprintln!("{}", error);
+                                    format!("Couldn't restore session: {}", error)
+                                }
+                            };
+                            message = Some((text, Instant::now()));
+                        }
+                        Some(Action::Eclipses) => {
+                            mode = Mode::SetEclipseRange;
+                            buffer = String::new();
+                            video_subsystem.text_input().start();
+                        }
+                        Some(Action::Ruler) => {
+                            mode = Mode::Ruler;
+                            ruler.clear();
+                        }
+                        Some(action @ (Action::NextSunEvent | Action::NextMoonEvent)) => {
+                            // rises, with shift sets
+                            let rising = !keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+                            let pane = &mut session.panes[session.focus];
+                            let text = jump_to_crossing(pane, &calibration, action == Action::NextMoonEvent, rising);
+                            message = Some((text, Instant::now()));
+                        }
+                        Some(Action::Scrub) => {
+                            let pane = &session.panes[session.focus];
+                            let engine = pane.engine(calibration);
+                            mode = Mode::Scrub(engine.get_sun_longitude());
+                        }
+                        // only bound with Ctrl held, handled above
+                        Some(Action::WalkNorth | Action::WalkSouth | Action::WalkEast | Action::WalkWest) | None => {}
+                    },
+                    Mode::SetBookmark => match keycode {
+                        Keycode::Return => {
+                            let pane = &session.panes[session.focus];
+                            let label = match buffer.trim() {
+                                "" => pane.time.format("%Y-%b-%d %H:%M").to_string(),
+                                label => label.to_string(),
+                            };
+                            session.bookmarks.push(Bookmark {
+                                label,
+                                time: pane.time,
+                                latitude: pane.latitude,
+                                longitude: pane.longitude,
+                                // bookmarks keep the stereographic pan, whatever the pane shows
+                                view: pane.view.with_projection(Projection::Stereographic),
+                            });
+                            let text = match bookmarks::save(&bookmarks_path, &session.bookmarks) {
+                                Ok(()) => "Bookmark saved".to_string(),
+                                Err(error) => format!("Couldn't save bookmarks: {}", error),
+                            };
+                            message = Some((text, Instant::now()));
+                            video_subsystem.text_input().stop();
+                            mode = Mode::Default;
+                        }
+                        Keycode::Escape => {
+                            video_subsystem.text_input().stop();
+                            mode = Mode::Default;
+                        }
+                        Keycode::Backspace => {
+                            buffer.pop();
+                        }
+                        _ => {}
+                    },
+                    Mode::SetEclipseRange => match keycode {
+                        Keycode::Return => {
+                            let text = match parse_date_range(&buffer) {
+                                Some((start, end)) => {
+                                    let pane = &session.panes[session.focus];
+                                    report_eclipses(
+                                        &WORLDS[pane.world],
+                                        &calibration,
+                                        start,
+                                        end,
+                                        pane.latitude,
+                                        pane.longitude,
+                                    )
+                                }
+                                None => "Expected two dates like 2024-01-01 2026-12-31".to_string(),
+                            };
+                            message = Some((text, Instant::now()));
+                            video_subsystem.text_input().stop();
+                            mode = Mode::Default;
+                        }
+                        Keycode::Escape => {
+                            video_subsystem.text_input().stop();
+                            mode = Mode::Default;
+                        }
+                        Keycode::Backspace => {
+                            buffer.pop();
+                        }
+                        _ => {}
+                    },
+                    Mode::Bookmarks => {
+                        let index = keycode as i32 - Keycode::Num1 as i32;
+                        if let Some(bookmark) = usize::try_from(index)
+                            .ok()
+                            .and_then(|index| session.bookmarks.get(index))
+                        {
+                            let pane = &mut session.panes[session.focus];
+                            pane.time = bookmark.time;
+                            pane.latitude = bookmark.latitude;
+                            pane.longitude = bookmark.longitude;
+                            pane.view = bookmark.view.with_projection(pane.view.projection);
+                            pane.step = NEUTRAL_STEP;
+                            message = Some((bookmark.label.clone(), Instant::now()));
+                            mode = Mode::Default;
+                        } else if keycode == Keycode::Escape {
+                            mode = Mode::Default;
+                        }
+                    }
+                    Mode::Ruler => {
+                        if keycode == Keycode::Escape {
+                            ruler.clear();
+                            mode = Mode::Default;
+                        }
+                    }
+                    Mode::Scrub(_) => {
+                        if keycode == Keycode::Escape {
+                            mode = Mode::Default;
+                        }
+                    }
+                    Mode::SetLatitude => match keycode {
+                        Keycode::Return => {
+                            if let Ok(new_latitude) = buffer.parse::<f64>() {
+                                if (-90.0..=90.0).contains(&new_latitude) {
+                                    session.panes[session.focus].latitude = new_latitude / 180.0 * PI
+                                }
+                            }
+                            mode = Mode::Default;
+                        }
+                        Keycode::Escape => {
+                            buffer = String::new();
+                            mode = Mode::Default;
+                        }
+                        _ => {
+                            buffer.push_str(&keycode.to_string());
+                        }
+                    },
+                    Mode::SetLongitude => match keycode {
+                        Keycode::Return => {
+                            if let Some(new_longitude) = parse_longitude(&buffer) {
+                                session.panes[session.focus].longitude = new_longitude;
+                            }
+                            mode = Mode::Default;
+                        }
+                        Keycode::Escape => {
+                            buffer = String::new();
+                            mode = Mode::Default;
+                        }
+                        _ => {
+                            buffer.push_str(&keycode.to_string());
+                        }
+                    },
+                },
+                _ => {}
+            }
+        }
+
+        let now = Instant::now();
+        let elapsed = now - real_time;
+        real_time = now;
+        frame_times.push(elapsed.as_secs_f64());
+        let rects = pane_rects(canvas.logical_size(), session.panes.len());
+        let mut engines = Vec::new();
+        for pane in &mut session.panes {
+            if let Some(flight) = &pane.flight {
+                match flight.view_at(flight.start.elapsed().as_secs_f64()) {
+                    Some(view) => pane.view = view,
+                    None => {
+                        pane.view = flight.to;
+                        pane.flight = None;
+                    }
+                }
+            }
+            if let Some(trip) = &pane.trip {
+                match trip.latitude_at(trip.start.elapsed().as_secs_f64()) {
+                    Some(latitude) => pane.latitude = latitude,
+                    None => {
+                        pane.latitude = trip.to;
+                        pane.trip = None;
+                    }
+                }
+            }
+            pane.time = advance_time(pane.time, elapsed, STEPS[pane.step].value);
+            let mut engine = pane.engine(calibration);
+            engine.aberration = session.display.aberration;
+            engine.refraction = session.display.refraction;
+            if session.display.trails {
+                pane.trails
+                    .record(pane.time, &trail_positions(&engine, &planets), trail_length);
+            }
+            engines.push(engine);
+        }
+        let engine = &engines[session.focus];
+        let hour_angle = session.selected.and_then(|selected| {
+            let hour_angle = engine.get_hour_angle(selected, &stars, &planets)?;
+            Some((selected, hour_angle))
+        });
+        match hour_angle {
+            Some((selected, hour_angle)) => {
+                if let Some((object, focus, previous)) = meridian {
+                    if (object, focus) == (selected, session.focus) && crossed_meridian(previous, hour_angle) {
+                        let text = format!("{} crosses the meridian", selection_name(selected, &stars, &planets));
+                        message = Some((text, Instant::now()));
+                        culmination = Some((selected, Instant::now()));
+                    }
+                }
+                meridian = Some((selected, session.focus, hour_angle));
+            }
+            None => meridian = None,
+        }
+
+        for request in requests.iter().flat_map(|requests| requests.try_iter()) {
+            let pane = &mut session.panes[session.focus];
+            let answer = server::parse_command(&request.command)
+                .and_then(|command| answer_command(command, pane, engine, &stars, &planets))
+                .unwrap_or_else(server::error);
+            // a client gone in the meantime needs no answer
+            let _ = request.reply.send(answer);
+        }
+
+        if let Some((x, y)) = click {
+            let size = (rects[session.focus].width(), rects[session.focus].height());
+            let view = &session.panes[session.focus].view;
+            if let Mode::Ruler = mode {
+                // a third click starts a new measurement
+                if let Some(point) = canvas_to_horizontal(x.into(), y.into(), size, layout, view) {
+                    if ruler.len() == 2 {
+                        ruler.clear();
+                    }
+                    ruler.push(point);
+                }
+            } else {
+                session.selected = pick_object(engine, &stars, &planets, size, layout, view, (x, y));
+                // with the boundaries shown a click also tells where it fell
+                let point = canvas_to_horizontal(x.into(), y.into(), size, layout, view);
+                if let (true, Some((alt, az))) = (session.display.boundaries, point) {
+                    let (ascension, declination) = engine.horizontal_to_equatorial(alt, az);
+                    if let Some(boundary) = boundaries::find(&constellations, ascension, declination) {
+                        message = Some((format!("In {}", boundary.name), Instant::now()));
+                    }
+                }
+            }
+        }
+
+        let scene = Scene {
+            stars: &stars,
+            planets: &planets,
+            boundaries: &constellations,
+            grid,
+            layout,
+            planet_textures: &planet_textures,
+            moon_phases: &moon_phases,
+            font: &font,
+            small_font: &small_font,
+            theme: &theme,
+            selected: session.selected,
+            clock: launch.elapsed().as_secs_f64(),
+        };
+        if export_chart {
+            let pane = &session.panes[session.focus];
+            let size = (rects[session.focus].width(), rects[session.focus].height());
+            let mut svg = SvgPainter::new(dome_area(size, layout));
+            draw_chart(&mut svg, size, &pane.view, engine, &scene, &session.display);
+            let path = chart_path(pane.time);
+            let text = match fs::write(&path, svg.finish()) {
+                Ok(()) => format!("Saved {}", path.display()),
+                Err(error) => format!("Couldn't save chart: {}", error),
+            };
+            message = Some((text, Instant::now()));
+        }
+        let mut transits = Vec::new();
+        let mut stats = FrameStats::default();
+        for (i, rect) in rects.iter().enumerate() {
+            canvas.set_viewport(*rect);
+            let (frame_transits, frame_stats) = render_frame(
+                &mut canvas,
+                (rect.width(), rect.height()),
+                &session.panes[i].view,
+                &engines[i],
+                &scene,
+                &session.display,
+                &session.panes[i].trails,
+            );
+            let pane = &session.panes[i];
+            if session.display.rise_points && WORLDS[pane.world].is_earth() {
+                let key = (local_midnight(pane.time, pane.longitude), pane.latitude, pane.longitude);
+                rise_points.resize(rects.len(), None);
+                if rise_points[i].map(|(cached, _)| cached) != Some(key) {
+                    rise_points[i] = Some((
+                        key,
+                        get_rise_points(&calibration, pane.time, pane.latitude, pane.longitude),
+                    ));
+                }
+                let (_, points) = rise_points[i].as_ref().unwrap();
+                let size = (rect.width(), rect.height());
+                draw_rise_points(&mut canvas, size, layout, &pane.view, points, &small_font, &theme);
+            }
+            if i == session.focus {
+                transits = frame_transits;
+                stats = frame_stats;
+                let size = (rect.width(), rect.height());
+                if let Some((object, start)) = culmination {
+                    // a ring fading out around the selection that just culminated
+                    let fade = 1.0 - start.elapsed().as_secs_f64() / CULMINATION_FLASH.as_secs_f64();
+                    let place = engines[i].get_object_position(object, &stars, &planets);
+                    if let (true, Some((alt, az))) = (fade > 0.0 && session.selected == Some(object), place) {
+                        let (x, y) = horizontal_to_canvas(alt, az, size, layout, &session.panes[i].view);
+                        let Color { r, g, b, .. } = theme.highlight;
+                        let color = Color::RGBA(r, g, b, (fade * 255.0).round() as u8);
+                        canvas.aa_circle(x, y, CULMINATION_MARKER, color).unwrap();
+                    }
+                }
+                let notation = session.display.notation;
+                draw_ruler(&mut canvas, size, &session.panes[i].view, &ruler, notation, &scene);
+            }
+            if session.panes.len() > 1 && i == session.focus {
+                canvas
+                    .rectangle(
+                        0,
+                        0,
+                        (rect.width() - 1).try_into().unwrap(),
+                        dome_area((rect.width(), rect.height()), layout).1.try_into().unwrap(),
+                        theme.highlight,
+                    )
+                    .unwrap();
+            }
+        }
+        canvas.set_viewport(None);
 
+        let (width, height) = canvas.logical_size();
         canvas
             .box_(
                 0,
-                (height - PANEL_SIZE).try_into().unwrap(),
+                (height - layout.panel).try_into().unwrap(),
                 width.try_into().unwrap(),
                 height.try_into().unwrap(),
-                Color::RGB(0, 0, 0),
+                theme.panel,
             )
             .unwrap();
+        let pane = &session.panes[session.focus];
         let text = match mode {
             Mode::Default => {
-                format!(
-                    "lat: {:.4}; lon: {:.4}; {}; Step: {}",
-                    latitude / PI * 180.0,
-                    longitude / PI * 180.0,
-                    engine.time.format("%Y-%b-%d %H:%M:%S %Z"),
-                    STEPS[step].name
-                )
+                let rect = rects[session.focus];
+                let size = (rect.width(), rect.height());
+                let (x, y) = stereo_to_canvas(pane.view.pan.0, pane.view.pan.1, size, layout, &pane.view);
+                // read back from the pixel under the crosshair, as a click there would be
+                let center = canvas_to_horizontal(x.into(), y.into(), size, layout, &pane.view);
+                let circumpolar = if panel_fields.contains(&Field::Circumpolar) {
+                    circumpolar_names(engine, &stars, &planets, pane.latitude)
+                } else {
+                    Vec::new()
+                };
+                let mut parts: Vec<String> = panel_fields
+                    .iter()
+                    .filter_map(|&field| {
+                        panel_field(field, engine, pane, &session.display, center, &transits, &circumpolar)
+                    })
+                    .collect();
+                if let Some((message, shown)) = &message {
+                    if shown.elapsed() < MESSAGE_DURATION {
+                        parts.push(message.clone());
+                    }
+                }
+                let mut text = parts.join("; ");
+                if session.panes.len() > 1 {
+                    text = format!("[{}] {}", session.focus + 1, text);
+                }
+                // the font is monospaced, so the width in characters is fixed
+                let char_width = font.size_of_char('0').map_or(1, |(width, _)| width.max(1));
+                panel::truncate(&text, (width.saturating_sub(20) / char_width) as usize)
             }
             Mode::SetLatitude => {
                 format!("Set latitude: {}", buffer)
@@ -494,38 +3660,534 @@ fn main() {
             Mode::SetLongitude => {
                 format!("Set longitude: {}", buffer)
             }
+            Mode::SetBookmark => {
+                format!("Bookmark label: {}", buffer)
+            }
+            Mode::SetEclipseRange => {
+                format!("Eclipses between (YYYY-MM-DD YYYY-MM-DD): {}", buffer)
+            }
+            Mode::Scrub(_) => format!(
+                "{}; Sun at {}; drag sideways to move it along the ecliptic, Escape to close",
+                engine.time.format("%Y-%b-%d %H:%M %Z"),
+                format_zodiac(engine.get_sun_longitude())
+            ),
+            Mode::Ruler => match *ruler {
+                [a, b] => format!(
+                    "Distance: {}; click to measure again, Escape to close",
+                    format::degrees(angular_distance(a, b), session.display.notation, 2)
+                ),
+                _ => "Click two points to measure; Escape to close".to_string(),
+            },
+            Mode::Bookmarks => match session.bookmarks.len() {
+                0 => "No bookmarks yet; Escape to close".to_string(),
+                count => format!(
+                    "Recall a bookmark with 1-{}; Escape to close",
+                    min(count, BOOKMARK_SLOTS)
+                ),
+            },
         };
-        let (texture, x, y) = render_text(&font, &texture_creator, &text);
-        canvas
-            .copy(
-                &texture,
-                None,
-                Rect::new(10, (height - PANEL_SIZE).try_into().unwrap(), x, y),
-            )
-            .unwrap();
+        // an empty line can't be rendered, and the panel may be configured to show nothing
+        if !text.is_empty() {
+            let (texture, x, y) = render_text(&font, &texture_creator, &text, theme.text);
+            canvas
+                .copy(
+                    &texture,
+                    None,
+                    Rect::new(10, (height - layout.panel).try_into().unwrap(), x, y),
+                )
+                .unwrap();
+        }
+
+        if let (Some(selection), true) = (session.selected, session.display.info_panel) {
+            let key = (
+                selection,
+                engine.world().id,
+                local_midnight(pane.time, pane.longitude),
+                local_noon(pane.time, pane.longitude),
+                pane.latitude,
+                pane.longitude,
+            );
+            if selection_times.as_ref().map(|(cached, _)| *cached) != Some(key) {
+                let times = get_selection_times(selection, engine, &stars, &planets, pane.latitude, pane.longitude);
+                selection_times = Some((key, times));
+            }
+            let (_, times) = selection_times.as_ref().unwrap();
+            let lines = describe_selection(
+                selection,
+                engine,
+                &stars,
+                &planets,
+                pane.latitude,
+                &session.display,
+                times,
+            );
+            let line_height = small_font.height();
+            canvas
+                .box_(
+                    0,
+                    0,
+                    INFO_PANEL_WIDTH,
+                    (lines.len() as i32 * line_height + 20).try_into().unwrap(),
+                    theme.panel,
+                )
+                .unwrap();
+            for (i, line) in lines.iter().enumerate() {
+                let (texture, x, y) = render_text(&small_font, &texture_creator, line, theme.text);
+                canvas
+                    .copy(&texture, None, Rect::new(10, 10 + i as i32 * line_height, x, y))
+                    .unwrap();
+            }
+        }
+
+        if let Mode::Bookmarks = mode {
+            let line_height = small_font.height();
+            let shown = &session.bookmarks[..min(session.bookmarks.len(), BOOKMARK_SLOTS)];
+            canvas
+                .box_(
+                    0,
+                    0,
+                    INFO_PANEL_WIDTH,
+                    (shown.len() as i32 * line_height + 20).try_into().unwrap(),
+                    theme.panel,
+                )
+                .unwrap();
+            for (i, bookmark) in shown.iter().enumerate() {
+                let line = format!("{}. {}", i + 1, bookmark.label);
+                let (texture, x, y) = render_text(&small_font, &texture_creator, &line, theme.text);
+                canvas
+                    .copy(&texture, None, Rect::new(10, 10 + i as i32 * line_height, x, y))
+                    .unwrap();
+            }
+        }
+
+        if session.display.day_length_chart {
+            let pane = &session.panes[session.focus];
+            let key = (pane.time.year(), pane.latitude, pane.longitude);
+            if day_lengths.as_ref().map(|(cached, _)| *cached) != Some(key) {
+                day_lengths = Some((key, get_day_lengths(&calibration, key.0, key.1, key.2)));
+            }
+            let origin = (
+                (width as i16) - CHART_WIDTH - 10,
+                (height - layout.panel) as i16 - CHART_HEIGHT - 30,
+            );
+            let (_, lengths) = day_lengths.as_ref().unwrap();
+            draw_day_length_chart(&mut canvas, origin, lengths, pane.latitude, &small_font, &theme);
+        }
+
+        let mut overlay = Vec::new();
+        if session.display.fps_overlay {
+            let frame_time = frame_times.average();
+            overlay.push(format!("{:.0} fps; {:.2} ms", 1.0 / frame_time, frame_time * 1000.0));
+        }
+        if session.display.stats {
+            overlay.extend(format_stats(&stats));
+        }
+        for (i, text) in overlay.iter().enumerate() {
+            let (texture, x, y) = render_text(&small_font, &texture_creator, text, theme.text);
+            let top = 10 + i as i32 * small_font.height();
+            canvas
+                .copy(&texture, None, Rect::new((width - x - 10) as i32, top, x, y))
+                .unwrap();
+        }
 
         canvas.present();
-        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 240));
+        // in the background the sky is drawn only now and then, the time still follows the clock between frames
+        let period = if focused { frame_period } else { Some(idle_period) };
+        if let Some(period) = period {
+            // only sleep for what is left of the frame after the work above
+            ::std::thread::sleep(period.saturating_sub(frame_start.elapsed()));
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use antikythera::{parse_planets, Variability, VariableKind};
 
     #[test]
     fn test_horizontal_to_canvas() {
-        assert_eq!(horizontal_to_canvas(PI / 2.0, 0.0, (640, 670)), (320, 320));
-        assert_eq!(horizontal_to_canvas(PI / 2.0, PI / 2.0, (640, 670)), (320, 320));
-        assert_eq!(horizontal_to_canvas(PI / 2.0, PI, (640, 670)), (320, 320));
-        assert_eq!(horizontal_to_canvas(PI / 2.0, 3.0 * PI / 2.0, (640, 670)), (320, 320));
+        let view = View::default();
+        assert_eq!(
+            horizontal_to_canvas(PI / 2.0, 0.0, (640, 670), Layout::default(), &view),
+            (320, 320)
+        );
+        assert_eq!(
+            horizontal_to_canvas(PI / 2.0, PI / 2.0, (640, 670), Layout::default(), &view),
+            (320, 320)
+        );
+        assert_eq!(
+            horizontal_to_canvas(PI / 2.0, PI, (640, 670), Layout::default(), &view),
+            (320, 320)
+        );
+        assert_eq!(
+            horizontal_to_canvas(PI / 2.0, 3.0 * PI / 2.0, (640, 670), Layout::default(), &view),
+            (320, 320)
+        );
+
+        // the horizon keeps the margin from the edges
+        assert_eq!(
+            horizontal_to_canvas(0.0, 0.0, (640, 670), Layout::default(), &view),
+            (320, 8)
+        );
+        assert_eq!(
+            horizontal_to_canvas(0.0, PI / 2.0, (640, 670), Layout::default(), &view),
+            (8, 320)
+        );
+        assert_eq!(
+            horizontal_to_canvas(0.0, PI, (640, 670), Layout::default(), &view),
+            (320, 632)
+        );
+        assert_eq!(
+            horizontal_to_canvas(0.0, 3.0 * PI / 2.0, (640, 670), Layout::default(), &view),
+            (632, 320)
+        );
+
+        assert_eq!(
+            horizontal_to_canvas(-PI / 2.0, 0.0, (640, 670), Layout::default(), &view),
+            (-1, -1)
+        );
+
+        let view = View {
+            zoom: 2.0,
+            pan: (0.0, 0.5),
+            flipped: true,
+            projection: Projection::Stereographic,
+        };
+        assert_eq!(
+            horizontal_to_canvas(0.0, 0.0, (640, 670), Layout::default(), &view),
+            (320, 8)
+        );
+        assert_eq!(
+            horizontal_to_canvas(0.0, PI / 2.0, (640, 670), Layout::default(), &view),
+            (944, 632)
+        );
+        assert_eq!(
+            horizontal_to_canvas(PI / 2.0, 0.0, (640, 670), Layout::default(), &view),
+            (320, 632)
+        );
+    }
+
+    #[test]
+    fn test_get_day_lengths() {
+        let north = get_day_lengths(&Calibration::default(), 2024, LAT, LON);
+        let south = get_day_lengths(&Calibration::default(), 2024, -LAT, LON);
+        assert_eq!(north.len(), DAY_LENGTH_SAMPLES as usize);
+        // longest around the june solstice in the north, mirrored in the south up to refraction
+        let longest = (0..north.len()).max_by(|&a, &b| north[a].total_cmp(&north[b])).unwrap();
+        assert!((24..=26).contains(&longest));
+        assert!(north.iter().zip(&south).all(|(n, s)| (n + s - 24.0).abs() < 0.6));
+    }
+
+    #[test]
+    fn test_parse_date_range() {
+        let (start, end) = parse_date_range(" 2024-01-01   2026-12-31 ").unwrap();
+        assert_eq!(start, Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+        assert_eq!(end, Utc.with_ymd_and_hms(2026, 12, 31, 0, 0, 0).unwrap());
+        assert!(parse_date_range("2026-12-31 2024-01-01").is_none());
+        assert!(parse_date_range("2024-01-01").is_none());
+        assert!(parse_date_range("2024-01-01 2025-01-01 2026-01-01").is_none());
+        assert!(parse_date_range("2024-13-01 2025-01-01").is_none());
+    }
+
+    #[test]
+    fn test_report_eclipses() {
+        let report = |world, range| {
+            let (start, end) = parse_date_range(range).unwrap();
+            report_eclipses(world, &Calibration::default(), start, end, LAT, LON)
+        };
+        assert!(report(&EARTH, "2024-01-01 2025-01-01").starts_with("2 eclipses, first penumbral lunar 2024-Mar-25"));
+        assert_eq!(
+            report(&WORLDS[1], "2024-01-01 2025-01-01"),
+            "Eclipses are only worked out for the Earth"
+        );
+        assert_eq!(
+            report(&EARTH, "1900-01-01 2100-01-01"),
+            "Search at most 100 years at a time"
+        );
+    }
+
+    #[test]
+    fn test_format_hours() {
+        assert_eq!(format_hours(0.0), "00:00:00");
+        assert_eq!(format_hours(13.5125), "13:30:45");
+        assert_eq!(format_hours(23.99999), "00:00:00");
+    }
+
+    #[test]
+    fn test_glow_strength() {
+        assert_eq!(glow_strength(2.0), 0.0);
+        assert_eq!(glow_strength(GLOW_MAGNITUDE), 0.0);
+        assert!((glow_strength(-1.46) - 0.492).abs() < 1e-9); // sirius
+        assert_eq!(glow_strength(-4.5), 1.0); // venus
+    }
+
+    #[test]
+    fn test_is_telescopic() {
+        let mut display = Display::new(LABEL_MAGNITUDE, Notation::Decimal, Glyph::Dot);
+        assert!(!is_telescopic(5.7, PI / 2.0, &display)); // uranus at its best
+        assert!(is_telescopic(7.8, PI / 2.0, &display)); // neptune
+        display.conditions = Some(Conditions {
+            name: "city".to_string(),
+            limit: 4.0,
+            extinction: 0.4,
+            gamma: 1.6,
+        });
+        assert!(!is_telescopic(3.9, PI / 2.0, &display));
+        // the same planet sinks past the limit low in the sky
+        assert!(is_telescopic(3.9, PI / 12.0, &display));
+    }
+
+    #[test]
+    fn test_twinkle_factor() {
+        assert_eq!(twinkle_factor(PI / 4.0, 7, 1.5, TWINKLE_DEPTH), 1.0);
+        for clock in [0.0, 0.3, 1.7, 12.0] {
+            let factor = twinkle_factor(0.0, 7, clock, TWINKLE_DEPTH);
+            assert!((1.0 - TWINKLE_DEPTH..=1.0 + TWINKLE_DEPTH).contains(&factor));
+            let factor = twinkle_factor(TWINKLE_ALTITUDE / 2.0, 7, clock, TWINKLE_DEPTH);
+            assert!((1.0 - TWINKLE_DEPTH / 2.0..=1.0 + TWINKLE_DEPTH / 2.0).contains(&factor));
+        }
+        // neighbouring stars are out of step
+        assert!((twinkle_factor(0.0, 7, 0.0, TWINKLE_DEPTH) - twinkle_factor(0.0, 8, 0.0, TWINKLE_DEPTH)).abs() > 0.01);
+    }
+
+    #[test]
+    fn test_seeing_offset() {
+        assert_eq!(seeing_offset(0.0, 7, 1.5, 0), (0, 0));
+        let reach = |alt: f64| {
+            (0..100)
+                .map(|i| seeing_offset(alt, 7, i as f64 * 0.05, MAX_SEEING))
+                .map(|(dx, dy)| dx.abs().max(dy.abs()))
+                .max()
+                .unwrap()
+        };
+        // within the amplitude overhead, further out low down
+        assert!(reach(PI / 2.0) as f64 <= (MAX_SEEING as f64 * SEEING_JITTER).round());
+        assert!(reach(0.1) > reach(PI / 2.0));
+    }
+
+    #[test]
+    fn test_canvas_to_stereo() {
+        let view = View {
+            zoom: 3.0,
+            pan: (0.2, -0.4),
+            flipped: true,
+            projection: Projection::Stereographic,
+        };
+        for (x, y) in [(0.0, 0.0), (0.5, -0.3), (0.1, 0.6)] {
+            let (cx, cy) = stereo_to_canvas(x, y, (640, 670), Layout::default(), &view);
+            let (sx, sy) = canvas_to_stereo(cx.into(), cy.into(), (640, 670), Layout::default(), &view);
+            // back to within a pixel
+            assert!((sx - x).abs() < 1.0 / canvas_scale((640, 670), Layout::default(), &view));
+            assert!((sy - y).abs() < 1.0 / canvas_scale((640, 670), Layout::default(), &view));
+        }
+    }
+
+    #[test]
+    fn test_canvas_to_horizontal() {
+        let size = (640, 670);
+        let view = View {
+            zoom: 2.5,
+            pan: (-0.3, 0.2),
+            flipped: true,
+            projection: Projection::Stereographic,
+        };
+        for (alt, az) in [(1.2, 0.3), (0.4, 5.0), (0.05, 3.5), (PI / 2.0, 0.0)] {
+            let (x, y) = horizontal_to_canvas(alt, az, size, Layout::default(), &view);
+            let (alt2, az2) = canvas_to_horizontal(x.into(), y.into(), size, Layout::default(), &view).unwrap();
+            // back on the same pixel
+            assert_eq!(horizontal_to_canvas(alt2, az2, size, Layout::default(), &view), (x, y));
+            let (sx, sy) = stereographic_projection(alt, az);
+            let (sx2, sy2) = stereographic_projection(alt2, az2);
+            assert!((sx - sx2).hypot(sy - sy2) * canvas_scale(size, Layout::default(), &view) < 1.0);
+        }
+        // the corners of an unzoomed pane lie beyond the horizon, the center is the zenith
+        let view = View::default();
+        assert!(canvas_to_horizontal(0.0, 0.0, size, Layout::default(), &view).is_none());
+        assert!(canvas_to_horizontal(639.0, 639.0, size, Layout::default(), &view).is_none());
+        let (alt, _) = canvas_to_horizontal(320.0, 320.0, size, Layout::default(), &view).unwrap();
+        assert!((alt - PI / 2.0).abs() < 1e-12);
+    }
 
-        assert_eq!(horizontal_to_canvas(0.0, 0.0, (640, 670)), (320, 0));
-        assert_eq!(horizontal_to_canvas(0.0, PI / 2.0, (640, 670)), (0, 320));
-        assert_eq!(horizontal_to_canvas(0.0, PI, (640, 670)), (320, 640));
-        assert_eq!(horizontal_to_canvas(0.0, 3.0 * PI / 2.0, (640, 670)), (640, 320));
+    #[test]
+    fn test_fisheye() {
+        let size = (640, 670);
+        let view = View::default().with_projection(Projection::Fisheye);
+        // the horizon stays put, 45 degrees lies halfway out where the stereographic puts it closer in
+        assert_eq!(horizontal_to_canvas(0.0, 0.0, size, Layout::default(), &view), (320, 8));
+        assert_eq!(
+            horizontal_to_canvas(PI / 4.0, 0.0, size, Layout::default(), &view),
+            (320, 164)
+        );
+        assert!(horizontal_to_canvas(PI / 4.0, 0.0, size, Layout::default(), &View::default()).1 > 164);
+        let (alt, az) = canvas_to_horizontal(320.0, 164.0, size, Layout::default(), &view).unwrap();
+        assert!((alt - PI / 4.0).abs() < 1e-12 && az.abs() < 1e-12);
+        // switching keeps the same point in the middle of the pane
+        let centered = View::default().centered_on(0.3, 2.0);
+        let switched = centered.with_projection(Projection::Fisheye);
+        assert_eq!(switched.zoom, centered.zoom);
+        let (alt, az) = canvas_to_horizontal(320.0, 320.0, size, Layout::default(), &switched).unwrap();
+        assert!((alt - 0.3).abs() < 1e-9 && (az - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_longitude() {
+        let degrees = |text: &str| parse_longitude(text).map(|longitude| longitude / PI * 180.0);
+        // west of greenwich as negative or as past 180 alike
+        assert!((degrees("-122").unwrap() - 238.0).abs() < 1e-9);
+        assert!((degrees("238").unwrap() - 238.0).abs() < 1e-9);
+        assert!((degrees("-180").unwrap() - 180.0).abs() < 1e-9);
+        assert_eq!(degrees("0"), Some(0.0));
+        // the full circle wraps back around to greenwich
+        assert_eq!(degrees("360"), Some(0.0));
+        assert_eq!(degrees("-0"), Some(0.0));
+        assert_eq!(degrees("-181"), None);
+        assert_eq!(degrees("360.5"), None);
+        assert_eq!(degrees("west"), None);
+    }
+
+    #[test]
+    fn test_nudge_location() {
+        let (latitude, longitude) = nudge_location(LAT, LON, Action::WalkNorth, NUDGE_STEP);
+        assert!((latitude - LAT - PI / 180.0).abs() < 1e-12);
+        assert_eq!(longitude, LON);
+        let (_, longitude) = nudge_location(LAT, LON, Action::WalkWest, FINE_NUDGE_STEP);
+        assert!((longitude - (2.0 * PI - 0.1 / 180.0 * PI)).abs() < 1e-12);
+        assert_eq!(
+            nudge_location(PI / 2.0, 1.0, Action::WalkNorth, NUDGE_STEP),
+            (PI / 2.0, 1.0)
+        );
+        assert_eq!(nudge_location(0.5, 1.0, Action::Mirror, NUDGE_STEP), (0.5, 1.0));
+    }
+
+    #[test]
+    fn test_flight() {
+        let flight = Flight {
+            from: View::default(),
+            to: View::default().centered_on(PI / 4.0, PI / 2.0),
+            start: Instant::now(),
+        };
+        assert_eq!(flight.to.zoom, GOTO_ZOOM);
+        let start = flight.view_at(0.0).unwrap();
+        assert_eq!(start.zoom, 1.0);
+        assert_eq!(start.pan, (0.0, 0.0));
+        let middle = flight.view_at(GOTO_DURATION / 2.0).unwrap();
+        assert!((middle.zoom - GOTO_ZOOM.sqrt()).abs() < 1e-12);
+        assert!((middle.pan.0 - flight.to.pan.0 / 2.0).abs() < 1e-12);
+        assert!(flight.view_at(GOTO_DURATION).is_none());
+        // the crosshair ends on the object
+        let (alt, az) = canvas_to_horizontal(320.0, 320.0, (640, 670), Layout::default(), &flight.to).unwrap();
+        assert!((alt - PI / 4.0).abs() < 1e-12 && (az - PI / 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_latitude_trip() {
+        let trip = LatitudeTrip {
+            from: LAT,
+            to: PI / 2.0,
+            start: Instant::now(),
+        };
+        assert_eq!(trip.latitude_at(0.0), Some(LAT));
+        let middle = trip.latitude_at(POLE_TRIP_DURATION / 2.0).unwrap();
+        assert!((middle - (LAT + PI / 2.0) / 2.0).abs() < 1e-12);
+        // eased, slow at the start
+        let early = trip.latitude_at(POLE_TRIP_DURATION / 10.0).unwrap();
+        assert!(early - LAT < (PI / 2.0 - LAT) / 10.0);
+        assert!(trip.latitude_at(POLE_TRIP_DURATION).is_none());
+    }
+
+    #[test]
+    fn test_dome_area() {
+        assert_eq!(dome_area((640, 670), Layout::default()), (640, 640));
+        assert_eq!(dome_area((640, 20), Layout::default()), (640, 1));
+        // a wide window keeps the dome round and in the middle
+        let view = View::default();
+        assert_eq!(
+            horizontal_to_canvas(PI / 2.0, 0.0, (1600, 430), Layout::default(), &view),
+            (800, 200)
+        );
+        assert_eq!(
+            horizontal_to_canvas(0.0, 0.0, (1600, 430), Layout::default(), &view),
+            (800, 8)
+        );
+        assert_eq!(
+            horizontal_to_canvas(0.0, PI / 2.0, (1600, 430), Layout::default(), &view),
+            (608, 200)
+        );
+        // and a tall one
+        assert_eq!(
+            horizontal_to_canvas(0.0, PI, (400, 1630), Layout::default(), &view),
+            (200, 992)
+        );
+        // a pane too small for the margin still shows a dome
+        assert!((dome_radius((20, 50), Layout::default()) - 5.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_view() {
+        let mut view = View::default();
+        view.zoom_by(0.5);
+        assert_eq!(view.zoom, 1.0);
+        view.zoom_by(1000.0);
+        assert_eq!(view.zoom, MAX_ZOOM);
+
+        let mut view = View::default();
+        view.drag(156, 0, (640, 670), Layout::default());
+        assert!((view.pan.0 - 0.5).abs() < 1e-12);
+        view.drag(640, 0, (640, 670), Layout::default());
+        assert!((view.pan.0 - 1.0).abs() < 1e-12);
+        view.flipped = true;
+        view.drag(156, 0, (640, 670), Layout::default());
+        assert!((view.pan.0 - 0.5).abs() < 1e-12);
+        let (alt, az) = canvas_to_horizontal(320.0, 320.0, (640, 670), Layout::default(), &view).unwrap();
+        assert!((alt - (2.0 * 2.0f64.atan() - PI / 2.0)).abs() < 1e-12);
+        assert!((az - PI / 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_disk_size() {
+        let view = View::default();
+        let radius = 0.5 / 180.0 * PI;
+        let zenith = disk_size(radius, PI / 2.0, (640, 670), Layout::default(), &view);
+        assert!((disk_size(radius, 0.0, (640, 670), Layout::default(), &view) - 2 * zenith).abs() <= 1);
+        // as big as the projection makes it once zoomed in past the magnification
+        let view = View {
+            zoom: 2.0 * DISK_MAGNIFICATION,
+            ..View::default()
+        };
+        let (_, y) = horizontal_to_canvas(PI / 2.0 - radius, 0.0, (640, 670), Layout::default(), &view);
+        assert!((disk_size(radius, PI / 2.0, (640, 670), Layout::default(), &view) - (320 - y)).abs() <= 1);
+        assert_eq!(disk_size(0.0, 0.0, (640, 670), Layout::default(), &view), 1);
+    }
 
-        assert_eq!(horizontal_to_canvas(-PI / 2.0, 0.0, (640, 670)), (-1, -1));
+    #[test]
+    fn test_visible_stars() {
+        let star = |hip, magnitude, variability| Star {
+            variability,
+            ..Star::new(hip, 0.0, 0.0, magnitude)
+        };
+        // a mira can flare from below the limit to naked eye brightness
+        let mira = Variability {
+            kind: VariableKind::Pulsating,
+            max_magnitude: 2.0,
+            min_magnitude: 10.1,
+            period: 332.0,
+            epoch: 2459400.0,
+        };
+        let mut stars = vec![
+            star(1, 7.5, None),
+            star(2, 9.0, Some(mira)),
+            star(3, -1.46, None),
+            star(4, 5.9, None),
+        ];
+        sort_by_brightness(&mut stars);
+        let visible: Vec<u32> = visible_stars(&stars, LIMITING_MAGNITUDE)
+            .iter()
+            .map(|star| star.hip)
+            .collect();
+        assert_eq!(visible, [3, 2, 4]);
+        assert!(visible_stars(&stars, -2.0).is_empty());
+        assert_eq!(visible_stars(&stars, 10.0).len(), 4);
     }
 
     #[test]
@@ -537,4 +4199,512 @@ mod tests {
         assert_eq!(magnitude_to_size_and_brightness(2.5), (1, 255));
         assert_eq!(magnitude_to_size_and_brightness(3.5), (0, 255));
     }
+
+    #[test]
+    fn test_daylight_factor() {
+        assert_eq!(daylight_factor(PI / 4.0), 0.0);
+        assert_eq!(daylight_factor(0.0), 0.0);
+        assert!((daylight_factor(-6.0 / 180.0 * PI) - 0.5).abs() < 1e-12);
+        assert_eq!(daylight_factor(-12.0 / 180.0 * PI), 1.0);
+        assert_eq!(daylight_factor(-PI / 2.0), 1.0);
+    }
+
+    #[test]
+    fn test_load_grid() {
+        assert_eq!(load_grid(&Config::parse("")), Grid::default());
+        let grid = load_grid(&Config::parse("grid_spokes = 4\ngrid_spacing = 10\n"));
+        assert_eq!(grid, Grid { spokes: 4, spacing: 10 });
+        let grid = load_grid(&Config::parse("grid_spokes = many\ngrid_spacing = 90\n"));
+        assert_eq!(grid, Grid::default());
+    }
+
+    #[test]
+    fn test_load_layout() {
+        assert_eq!(load_layout(&Config::parse("")), Layout::default());
+        let layout = load_layout(&Config::parse("panel_size = 0\ndome_margin = 20\n"));
+        assert_eq!(layout, Layout { panel: 0, margin: 20 });
+        // sizes that would overflow the window height fall back to the defaults
+        let layout = load_layout(&Config::parse("panel_size = 4294967295\ndome_margin = 4294967295\n"));
+        assert_eq!(layout, Layout::default());
+    }
+
+    #[test]
+    fn test_load_calibration() {
+        assert_eq!(load_calibration(&Config::parse("")), Calibration::default());
+        let config = Config::parse("[calibration]\ndaily_phase = 1.75\nnodal_phase = south\n");
+        let calibration = load_calibration(&config);
+        assert_eq!(calibration.daily_phase, 1.75);
+        assert_eq!(calibration.nodal_phase, Calibration::default().nodal_phase);
+    }
+
+    #[test]
+    fn test_parse_frame_period() {
+        assert_eq!(parse_frame_period("uncapped"), Some(None));
+        assert_eq!(parse_frame_period("50"), Some(Some(Duration::from_millis(20))));
+        assert_eq!(parse_frame_period("0"), None);
+        assert_eq!(parse_frame_period("-30"), None);
+        assert_eq!(parse_frame_period("fast"), None);
+        assert_eq!(parse_frame_period("1e-300"), None);
+    }
+
+    #[test]
+    fn test_place_label() {
+        // a label over a star at 100, 100, first clear, then with its spot taken, then pressed against the right edge
+        let default = Rect::new(80, 85, 40, 10);
+        assert_eq!(place_label(default, (100, 100), 2, (400, 400), &[]), (default, false));
+        let taken = [Rect::new(90, 80, 20, 10)];
+        let (rect, leader) = place_label(default, (100, 100), 2, (400, 400), &taken);
+        assert!(leader);
+        assert_eq!((rect.left(), rect.top()), (114, 95));
+        let (rect, leader) = place_label(default, (100, 100), 2, (150, 400), &taken);
+        assert!(leader);
+        assert_eq!((rect.right(), rect.top()), (86, 95));
+        // nowhere to go, it stays put
+        let everything = [Rect::new(0, 0, 400, 400)];
+        assert_eq!(
+            place_label(default, (100, 100), 2, (400, 400), &everything),
+            (default, false)
+        );
+    }
+
+    #[test]
+    fn test_idle_period() {
+        assert_eq!(idle_period(&Config::parse("")), Duration::from_millis(250));
+        assert_eq!(idle_period(&Config::parse("idle_fps = 1\n")), Duration::from_secs(1));
+        assert_eq!(
+            idle_period(&Config::parse("idle_fps = uncapped\n")),
+            Duration::from_millis(250)
+        );
+    }
+
+    #[test]
+    fn test_crossed_meridian() {
+        assert!(crossed_meridian(-0.01, 0.02));
+        assert!(crossed_meridian(0.02, -0.01)); // time running backwards
+        assert!(!crossed_meridian(0.01, 0.02));
+        // around the back of the sky
+        assert!(!crossed_meridian(PI - 0.01, -PI + 0.01));
+    }
+
+    #[test]
+    fn test_missing_resources() {
+        let resources = Path::new(env!("CARGO_MANIFEST_DIR")).join(RESOURCES_DIR);
+        assert!(missing_resources(&resources).is_empty());
+        assert_eq!(
+            missing_resources(&resources.join("nowhere")),
+            [STARS_FILE, PLANETS_FILE, FONT_FILE]
+        );
+    }
+
+    #[test]
+    fn test_load_stars() {
+        // a typo in an optional catalog fails the load instead of the program, so a reload keeps the old sky
+        let resources = Path::new(env!("CARGO_MANIFEST_DIR")).join(RESOURCES_DIR);
+        assert!(load_stars(&resources).is_ok());
+        let broken = env::temp_dir().join(format!("antikythera-load-stars-{}", process::id()));
+        fs::create_dir_all(broken.join("data")).unwrap();
+        fs::write(broken.join(STARS_FILE), "91262 4.874 0.677 0.03 Vega\n").unwrap();
+        for file in ["variables", "doubles", "designations", "colors"] {
+            let path = broken.join("data").join(format!("{}.dat", file));
+            fs::write(&path, "Vega\n").unwrap();
+            let error = load_stars(&broken).err().unwrap();
+            assert!(error.contains(&format!("{}.dat", file)), "{}", error);
+            fs::remove_file(&path).unwrap();
+        }
+        assert_eq!(load_stars(&broken).unwrap().len(), 1);
+        // mira, fainter than every other star on average, is kept for its maximum
+        let faint: String = (1..=STAR_LIMIT).map(|hip| format!("{} 0.0 0.0 6.0\n", hip)).collect();
+        fs::write(broken.join(STARS_FILE), faint + "10826 0.608 -0.052 9.0\n").unwrap();
+        fs::write(
+            broken.join("data/variables.dat"),
+            "10826 P 2.00 10.10 332.0 2459400.0\n",
+        )
+        .unwrap();
+        let stars = load_stars(&broken).unwrap();
+        assert_eq!(stars.len(), STAR_LIMIT);
+        assert_eq!(stars[0].hip, 10826);
+        fs::remove_dir_all(&broken).unwrap();
+    }
+
+    #[test]
+    fn test_reverse_step() {
+        assert_eq!(reverse_step(NEUTRAL_STEP), NEUTRAL_STEP);
+        for step in (0..STEPS.len()).filter(|&step| step != NEUTRAL_STEP) {
+            assert_eq!(STEPS[reverse_step(step)].value, -STEPS[step].value);
+        }
+    }
+
+    #[test]
+    fn test_advance_time() {
+        // a minute of uneven frames at the real time step ends exactly a minute on
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let frames = [16_666_667, 16_666_666, 33_333_333, 8_333_334].repeat(800);
+        let total: u64 = frames.iter().sum();
+        assert_eq!(total, 60_000_000_000);
+        let rate = STEPS[NEUTRAL_STEP].value;
+        let time = frames.iter().fold(start, |time, &frame| {
+            advance_time(time, Duration::from_nanos(frame), rate)
+        });
+        assert_eq!(time - start, chrono::Duration::minutes(1));
+        // and faster steps scale it exactly
+        let time = advance_time(start, Duration::from_millis(1500), -86164);
+        assert_eq!(start - time, chrono::Duration::milliseconds(129246000));
+    }
+
+    #[test]
+    fn test_local_midnight() {
+        let time = Utc.with_ymd_and_hms(2024, 3, 1, 2, 0, 0).unwrap();
+        assert_eq!(
+            local_midnight(time, 0.0),
+            Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap()
+        );
+        // 90° east is six hours ahead, 90° west six hours behind
+        assert_eq!(
+            local_midnight(time, PI / 2.0),
+            Utc.with_ymd_and_hms(2024, 2, 29, 18, 0, 0).unwrap()
+        );
+        assert_eq!(
+            local_midnight(time, -PI / 2.0),
+            Utc.with_ymd_and_hms(2024, 2, 29, 6, 0, 0).unwrap()
+        );
+        // the small hours still belong to the night before, the afternoon to the one ahead
+        let noon = Utc.with_ymd_and_hms(2024, 2, 29, 12, 0, 0).unwrap();
+        assert_eq!(local_noon(time, 0.0), noon);
+        assert_eq!(
+            local_noon(time + chrono::Duration::hours(12), 0.0),
+            noon + chrono::Duration::days(1)
+        );
+    }
+
+    #[test]
+    fn test_get_selection_times() {
+        let time = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let engine = Engine::new(time, LAT, LON);
+        let times = |selection| get_selection_times(selection, &engine, &[], &[], LAT, LON);
+        assert_eq!(times(SkyObject::Sun).len(), 1);
+        assert_eq!(times(SkyObject::Moon).len(), 2);
+        assert!(times(SkyObject::Moon)[1].starts_with("Tonight: "));
+        // nothing is searched for off the Earth
+        let mars = Engine::on_world(&WORLDS[1], time, LAT, LON);
+        assert!(get_selection_times(SkyObject::Sun, &mars, &[], &[], LAT, LON).is_empty());
+    }
+
+    #[test]
+    fn test_format_stats() {
+        let stats = FrameStats {
+            stars: 5000,
+            stars_above: 2400,
+            stars_drawn: 1900,
+            planets: 5,
+            planets_above: 3,
+        };
+        assert_eq!(
+            format_stats(&stats),
+            ["Stars: 2400 up, 1900 drawn, 3100 culled", "Planets: 3 up of 5"]
+        );
+    }
+
+    #[test]
+    fn test_format_zodiac() {
+        assert_eq!(format_zodiac(0.0), "0° Aries");
+        assert_eq!(format_zodiac(PI / 2.0 + 1e-9), "0° Cancer");
+        assert_eq!(format_zodiac(359.5 / 180.0 * PI), "29° Pisces");
+        assert_eq!(format_zodiac(-PI / 180.0), "29° Pisces");
+    }
+
+    #[test]
+    fn test_scrub_time() {
+        let time = DateTime::parse_from_rfc3339("2024-03-01T04:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let pane = Pane {
+            latitude: LAT,
+            longitude: LON,
+            time,
+            step: 0,
+            view: View::default(),
+            flight: None,
+            trip: None,
+            home_latitude: None,
+            world: 0,
+            trails: Trails::default(),
+        };
+        // the solstice, at the same time of day
+        let solstice = scrub_time(&pane, &Calibration::default(), PI / 2.0);
+        assert_eq!(solstice.time(), time.time());
+        assert_eq!((solstice.month(), solstice.day()), (6, 21));
+        let longitude = Engine::new(solstice, LAT, LON).get_sun_longitude();
+        assert!((longitude - PI / 2.0).abs() < 1.0 / 180.0 * PI);
+        assert_eq!(
+            scrub_time(
+                &pane,
+                &Calibration::default(),
+                Engine::new(time, LAT, LON).get_sun_longitude()
+            ),
+            time
+        );
+    }
+
+    #[test]
+    fn test_jump_to_crossing() {
+        let time = Utc.with_ymd_and_hms(2024, 3, 1, 4, 0, 0).unwrap();
+        let mut pane = Pane {
+            latitude: LAT,
+            longitude: LON,
+            time,
+            step: 0,
+            view: View::default(),
+            flight: None,
+            trip: None,
+            home_latitude: None,
+            world: 0,
+            trails: Trails::default(),
+        };
+        let text = jump_to_crossing(&mut pane, &Calibration::default(), false, true);
+        assert!(text.starts_with("Next sunrise at "));
+        assert_eq!(pane.step, NEUTRAL_STEP);
+        let sunrise = pane.time;
+        assert!(sunrise > time && sunrise - time < chrono::Duration::days(1));
+        // the next press finds the following day's
+        jump_to_crossing(&mut pane, &Calibration::default(), false, true);
+        assert!((pane.time - sunrise - chrono::Duration::days(1)).num_minutes().abs() < 5);
+        // the polar night has no sunrise to go to
+        pane.latitude = 80.0 / 180.0 * PI;
+        pane.time = Utc.with_ymd_and_hms(2024, 12, 21, 0, 0, 0).unwrap();
+        assert_eq!(
+            jump_to_crossing(&mut pane, &Calibration::default(), false, true),
+            "No sunrise within 2 days"
+        );
+        assert_eq!(pane.time, Utc.with_ymd_and_hms(2024, 12, 21, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_format_dark_window() {
+        let time = |hour: u32| Utc.with_ymd_and_hms(2024, 3, 1, hour, 30, 0).unwrap();
+        let window = |all_night: bool| DarkWindow {
+            start: time(19),
+            end: time(23),
+            best: time(21),
+            all_night,
+        };
+        assert_eq!(
+            format_dark_window(Some(window(false))),
+            "Tonight: 19:30-23:30, best at 21:30"
+        );
+        assert_eq!(
+            format_dark_window(Some(window(true))),
+            "Tonight: all night, best at 21:30"
+        );
+        assert_eq!(format_dark_window(None), "Tonight: not visible");
+    }
+
+    #[test]
+    fn test_planet_sprite_size() {
+        assert_eq!(planet_sprite_size("Mars", 1.0), (16, 16));
+        assert_eq!(planet_sprite_size("Jupiter", 4.0), (64, 64));
+        assert_eq!(planet_sprite_size("Saturn", 2.0), (70, 28));
+    }
+
+    #[test]
+    fn test_zodiacal_light_strength() {
+        let degrees = |value: f64| value / 180.0 * PI;
+        // nothing until the twilight is nearly over, then strongest on the ecliptic near the Sun
+        assert_eq!(zodiacal_light_strength(degrees(30.0), 0.0, degrees(-10.0)), 0.0);
+        let strength = zodiacal_light_strength(degrees(30.0), 0.0, degrees(-20.0));
+        assert!(strength > 0.7);
+        assert!(zodiacal_light_strength(degrees(30.0), 0.0, degrees(-15.0)) < strength);
+        assert_eq!(zodiacal_light_strength(degrees(-30.0), 0.0, degrees(-20.0)), strength);
+        // narrowing and fading away from the Sun, and off the ecliptic
+        assert!(zodiacal_light_strength(degrees(60.0), 0.0, degrees(-20.0)) < strength);
+        assert!(zodiacal_light_strength(degrees(30.0), degrees(5.0), degrees(-20.0)) < strength);
+        assert_eq!(
+            zodiacal_light_strength(degrees(60.0), degrees(10.0), degrees(-20.0)),
+            0.0
+        );
+        assert_eq!(zodiacal_light_strength(degrees(100.0), 0.0, degrees(-20.0)), 0.0);
+        assert_eq!(zodiacal_light_strength(degrees(5.0), 0.0, degrees(-20.0)), 0.0);
+    }
+
+    #[test]
+    fn test_belt_of_venus_strength() {
+        let degrees = |value: f64| value / 180.0 * PI;
+        // nothing in daylight or in deep twilight
+        assert_eq!(belt_of_venus_strength(degrees(10.0), 0.0, degrees(5.0)), 0.0);
+        assert_eq!(belt_of_venus_strength(degrees(10.0), 0.0, degrees(-7.0)), 0.0);
+        // above the shadow opposite the Sun, but not on the Sun's side
+        let strength = belt_of_venus_strength(degrees(7.0), 0.0, degrees(-2.0));
+        assert!(strength > 0.5);
+        assert_eq!(belt_of_venus_strength(degrees(7.0), PI, degrees(-2.0)), 0.0);
+        assert!(belt_of_venus_strength(degrees(7.0), degrees(60.0), degrees(-2.0)) < strength);
+        // the shadow itself stays dark
+        assert_eq!(belt_of_venus_strength(degrees(1.0), 0.0, degrees(-2.0)), 0.0);
+    }
+
+    #[test]
+    fn test_label_limit() {
+        assert_eq!(label_limit(2.5, 1.0), 2.5);
+        assert_eq!(label_limit(2.5, 0.5), 2.5);
+        assert!((label_limit(2.5, 4.0) - 4.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_frame_times() {
+        let mut frame_times = FrameTimes::new();
+        assert_eq!(frame_times.average(), 0.0);
+        frame_times.push(0.01);
+        frame_times.push(0.03);
+        assert!((frame_times.average() - 0.02).abs() < 1e-12);
+        for _ in 0..FRAME_WINDOW {
+            frame_times.push(0.005);
+        }
+        assert!((frame_times.average() - 0.005).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_moon_phase_frame() {
+        let degrees = |degrees: f64| degrees / 180.0 * PI;
+        // new, first quarter, full and last quarter, as the textures are numbered
+        for (phase, frame) in [(0.0, 0), (90.0, 6), (180.0, 12), (270.0, 18)] {
+            assert_eq!(moon_phase_frame(degrees(phase), 24), frame);
+        }
+        // the switch to the next frame halfway between two
+        assert_eq!(moon_phase_frame(degrees(7.4), 24), 0);
+        assert_eq!(moon_phase_frame(degrees(7.6), 24), 1);
+        // a waxing crescent never shows a waning frame, nor the other way round
+        assert!((1..12).contains(&moon_phase_frame(degrees(20.0), 24)));
+        assert!((13..24).contains(&moon_phase_frame(degrees(340.0), 24)));
+        // just short of a full lunation is new again, and out of range phases wrap
+        assert_eq!(moon_phase_frame(degrees(359.0), 24), 0);
+        assert_eq!(moon_phase_frame(degrees(-1.0), 24), 0);
+        assert_eq!(moon_phase_frame(degrees(450.0), 24), 6);
+        assert_eq!(moon_phase_frame(degrees(90.0), 12), 3);
+    }
+
+    #[test]
+    fn test_circumpolar_names() {
+        let planets = parse_planets(include_str!("../resources/data/planets.dat")).unwrap();
+        let star = |name: &str, declination: f64, magnitude: f64| Star {
+            name: Some(name.to_string()),
+            ..Star::new(0, 0.0, declination / 180.0 * PI, magnitude)
+        };
+        let stars = [
+            star("Polaris", 89.3, 1.97),
+            star("Dubhe", 61.8, 1.8),
+            star("Faint", 80.0, 4.0),
+        ];
+        let time = DateTime::parse_from_rfc3339("2024-03-20T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        // the list shrinks towards the equator
+        let names = |latitude: f64| {
+            let engine = Engine::new(time, latitude / 180.0 * PI, LON);
+            circumpolar_names(&engine, &stars, &planets, latitude / 180.0 * PI)
+        };
+        assert_eq!(names(51.5), ["Polaris", "Dubhe"]);
+        assert_eq!(names(10.0), ["Polaris"]);
+        assert!(names(0.0).is_empty());
+    }
+
+    #[test]
+    fn test_answer_command() {
+        let planets = parse_planets(include_str!("../resources/data/planets.dat")).unwrap();
+        let stars = [Star {
+            name: Some("Polaris".to_string()),
+            ..Star::new(11767, 0.6622, 1.5580, 1.97)
+        }];
+        assert_eq!(
+            find_object("JUPITER", &stars, &planets),
+            find_object("Jupiter", &stars, &planets)
+        );
+        assert_eq!(find_object("polaris", &stars, &planets), Some(SkyObject::Star(0)));
+        assert_eq!(find_object("HIP 11767", &stars, &planets), Some(SkyObject::Star(0)));
+        assert_eq!(find_object("Vulcan", &stars, &planets), None);
+
+        let time = DateTime::parse_from_rfc3339("2024-03-20T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let engine = Engine::new(time, LAT, LON);
+        let mut pane = Pane {
+            latitude: LAT,
+            longitude: LON,
+            time,
+            step: 0,
+            view: View::default(),
+            flight: None,
+            trip: None,
+            home_latitude: None,
+            world: 0,
+            trails: Trails::default(),
+        };
+        let mut answer = |line: &str| {
+            server::parse_command(line)
+                .and_then(|command| answer_command(command, &mut pane, &engine, &stars, &planets))
+        };
+        let polaris = answer("position of Polaris").unwrap();
+        assert_eq!(polaris.get("type").and_then(Json::as_str), Some("star"));
+        // the pole star stands about as high as the latitude
+        let alt = polaris.get("alt").and_then(Json::as_f64).unwrap();
+        assert!((alt - LAT / PI * 180.0).abs() < 1.0);
+        assert!((polaris.get("ra").and_then(Json::as_f64).unwrap() - 2.53).abs() < 0.01);
+        let sun = answer("position of Sun").unwrap();
+        assert_eq!(sun.get("type").and_then(Json::as_str), Some("sun"));
+        assert!(answer("position of Earth").is_err());
+        assert!(answer("position of Vulcan").is_err());
+        let moved = answer("goto 2030-01-01T00:00:00Z").unwrap();
+        assert_eq!(
+            moved.get("time").and_then(Json::as_str),
+            Some("2030-01-01T00:00:00+00:00")
+        );
+        assert_eq!(pane.time.year(), 2030);
+    }
+
+    #[test]
+    fn test_cycle_selection() {
+        let planets = parse_planets(include_str!("../resources/data/planets.dat")).unwrap();
+        let time = DateTime::parse_from_rfc3339("2024-03-20T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let engine = Engine::new(time, LAT, LON);
+        let cycle = |current, backward| cycle_selection(current, &engine, &planets, backward);
+        // once round forwards, then backwards
+        let mut tour = vec![cycle(None, false).unwrap()];
+        while let Some(next) = cycle(tour.last().copied(), false).filter(|&next| next != tour[0]) {
+            tour.push(next);
+        }
+        assert!(tour.contains(&SkyObject::Sun));
+        assert!(tour
+            .iter()
+            .all(|&object| engine.get_object_position(object, &[], &planets).unwrap().0 > 0.0));
+        assert_eq!(cycle(None, true), tour.last().copied());
+        for pair in tour.windows(2) {
+            assert_eq!(cycle(Some(pair[1]), true), Some(pair[0]));
+        }
+        // the Sun drops out of the tour at midnight
+        let night = Engine::new(time + chrono::Duration::hours(12), LAT, LON);
+        assert!(cycle_selection(Some(SkyObject::Sun), &night, &planets, false) != Some(SkyObject::Sun));
+    }
+
+    #[test]
+    fn test_pick_object() {
+        let time = DateTime::parse_from_rfc3339("2024-03-20T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let engine = Engine::new(time, 0.0, 0.0);
+        let (alt, az) = engine.get_sun_position();
+        let view = View::default();
+        let (x, y) = horizontal_to_canvas(alt, az, (640, 670), Layout::default(), &view);
+        let pick = |x, y| pick_object(&engine, &[], &[], (640, 670), Layout::default(), &view, (x, y));
+        assert!(pick(x.into(), y.into()) == Some(SkyObject::Sun));
+        assert!(pick(x as i32 + 5, y.into()) == Some(SkyObject::Sun));
+        assert!(pick(0, 0).is_none());
+    }
+
+    #[test]
+    fn test_moonlight_extinction() {
+        assert_eq!(moonlight_extinction(-0.1, PI, 0.0), 0.0);
+        assert!(moonlight_extinction(PI / 2.0, 0.0, 0.0).abs() < 1e-15);
+        assert!((moonlight_extinction(PI / 2.0, PI, 0.0) - MOONLIGHT_SKY - MOONLIGHT_HALO).abs() < 1e-12);
+        assert!(moonlight_extinction(PI / 4.0, PI, 0.1) > moonlight_extinction(PI / 4.0, PI, 1.0));
+        assert!(moonlight_extinction(PI / 4.0, PI, PI) > MOONLIGHT_SKY * 0.7);
+    }
 }
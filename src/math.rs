@@ -1,11 +1,67 @@
 use std::f64::consts::PI;
 
+const CURVE_SEGMENT: f64 = 4.0; // pixels
+const MAX_CURVE_POINTS: f64 = 10000.0;
+
+// how the sky is laid onto the plane, the horizon is the unit circle in each
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Projection {
+    #[default]
+    Stereographic,
+    Fisheye, // equidistant, the altitude falls linearly with the radius like in an all-sky camera
+}
+
+impl Projection {
+    pub fn project(self, alt: f64, az: f64) -> (f64, f64) {
+        match self {
+            Projection::Stereographic => stereographic_projection(alt, az),
+            Projection::Fisheye => equidistant_projection(alt, az),
+        }
+    }
+
+    pub fn unproject(self, x: f64, y: f64) -> (f64, f64) {
+        match self {
+            Projection::Stereographic => inverse_stereographic_projection(x, y),
+            Projection::Fisheye => inverse_equidistant_projection(x, y),
+        }
+    }
+
+    pub fn magnification(self, alt: f64) -> f64 {
+        // units of the plane per radian along the vertical at alt
+        match self {
+            Projection::Stereographic => 1.0 / (1.0 + alt.sin()),
+            Projection::Fisheye => 2.0 / PI,
+        }
+    }
+}
+
 pub fn stereographic_projection(alt: f64, az: f64) -> (f64, f64) {
     let zenith_angle = alt + PI / 2.0;
     let r = zenith_angle.sin() / (1.0 - zenith_angle.cos());
     (r * az.sin(), r * az.cos())
 }
 
+pub fn orthographic_projection(alt: f64, az: f64) -> (f64, f64) {
+    (alt.cos() * az.sin(), alt.cos() * az.cos())
+}
+
+pub fn inverse_stereographic_projection(x: f64, y: f64) -> (f64, f64) {
+    let alt = 2.0 * 1.0f64.atan2(x.hypot(y)) - PI / 2.0;
+    let az = x.atan2(y).rem_euclid(2.0 * PI);
+    (alt, az)
+}
+
+pub fn equidistant_projection(alt: f64, az: f64) -> (f64, f64) {
+    let r = 1.0 - alt / (PI / 2.0);
+    (r * az.sin(), r * az.cos())
+}
+
+pub fn inverse_equidistant_projection(x: f64, y: f64) -> (f64, f64) {
+    let alt = (1.0 - x.hypot(y)) * PI / 2.0;
+    let az = x.atan2(y).rem_euclid(2.0 * PI);
+    (alt, az)
+}
+
 pub fn circle_from_three_points(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> (f64, f64, f64) {
     let (ax, ay) = a;
     let (bx, by) = b;
@@ -16,3 +72,185 @@ pub fn circle_from_three_points(a: (f64, f64), b: (f64, f64), c: (f64, f64)) ->
     let r = ((ax - x) * (ax - x) + (ay - y) * (ay - y)).sqrt();
     (x, y, r)
 }
+
+pub fn clip_circle(center: (f64, f64), r: f64, width: f64, height: f64) -> Vec<Vec<(f64, f64)>> {
+    // polylines of the parts of the circle inside the rectangle from (0, 0) to (width, height),
+    // cut where the circle crosses the edges so that huge radii cost no more than small ones
+    let (cx, cy) = center;
+    let point = |angle: f64| (cx + r * angle.cos(), cy + r * angle.sin());
+    let inside = |(x, y): (f64, f64)| (-0.5..=width + 0.5).contains(&x) && (-0.5..=height + 0.5).contains(&y);
+
+    let mut angles = Vec::new();
+    for edge in [0.0 - cx, width - cx] {
+        if edge.abs() <= r {
+            let angle = (edge / r).acos();
+            angles.extend([angle, -angle]);
+        }
+    }
+    for edge in [0.0 - cy, height - cy] {
+        if edge.abs() <= r {
+            let angle = (edge / r).asin();
+            angles.extend([angle, PI - angle]);
+        }
+    }
+    let mut angles: Vec<f64> = angles.into_iter().map(|angle| angle.rem_euclid(2.0 * PI)).collect();
+    angles.sort_by(f64::total_cmp);
+    angles.dedup();
+
+    let arc = |from: f64, to: f64| {
+        let count = ((to - from) * r / CURVE_SEGMENT).ceil().clamp(1.0, MAX_CURVE_POINTS) as usize;
+        (0..=count)
+            .map(|i| point(from + (to - from) * i as f64 / count as f64))
+            .collect::<Vec<_>>()
+    };
+    if angles.is_empty() {
+        // entirely inside or entirely outside
+        return if inside(point(0.0)) {
+            vec![arc(0.0, 2.0 * PI)]
+        } else {
+            Vec::new()
+        };
+    }
+    let mut polylines = Vec::new();
+    for (i, &from) in angles.iter().enumerate() {
+        let to = if i + 1 < angles.len() {
+            angles[i + 1]
+        } else {
+            angles[0] + 2.0 * PI
+        };
+        if inside(point((from + to) / 2.0)) {
+            polylines.push(arc(from, to));
+        }
+    }
+    polylines
+}
+
+pub fn angular_distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (alt1, az1) = a;
+    let (alt2, az2) = b;
+    (alt1.sin() * alt2.sin() + alt1.cos() * alt2.cos() * (az1 - az2).cos())
+        .clamp(-1.0, 1.0)
+        .acos()
+}
+
+pub fn great_circle_points(a: (f64, f64), b: (f64, f64), count: usize) -> Vec<(f64, f64)> {
+    // count + 1 points along the shorter arc from a to b, both taken as (alt, az)
+    let vector = |(alt, az): (f64, f64)| [alt.cos() * az.cos(), alt.cos() * az.sin(), alt.sin()];
+    let (from, to) = (vector(a), vector(b));
+    let angle = angular_distance(a, b);
+    (0..=count)
+        .map(|i| {
+            let t = i as f64 / count.max(1) as f64;
+            // spherical interpolation, a plain mix where the arc is degenerate
+            let (wa, wb) = if angle.sin() < 1e-12 {
+                (1.0 - t, t)
+            } else {
+                (((1.0 - t) * angle).sin() / angle.sin(), (t * angle).sin() / angle.sin())
+            };
+            let [x, y, z] = [0, 1, 2].map(|k| wa * from[k] + wb * to[k]);
+            let r = (x * x + y * y + z * z).sqrt();
+            ((z / r).clamp(-1.0, 1.0).asin(), y.atan2(x).rem_euclid(2.0 * PI))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orthographic_projection() {
+        let (x, y) = orthographic_projection(PI / 2.0, 1.0);
+        assert!(x.abs() < 1e-15 && y.abs() < 1e-15);
+        let (x, y) = orthographic_projection(0.0, PI / 2.0);
+        assert!((x - 1.0).abs() < 1e-15 && y.abs() < 1e-15);
+        let (x, y) = orthographic_projection(PI / 3.0, PI);
+        assert!(x.abs() < 1e-15 && (y + 0.5).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_inverse_stereographic_projection() {
+        for (alt, az) in [(0.0, 0.0), (0.3, 1.0), (-0.5, 4.0), (1.2, 6.0)] {
+            let (x, y) = stereographic_projection(alt, az);
+            let (alt2, az2) = inverse_stereographic_projection(x, y);
+            assert!((alt - alt2).abs() < 1e-12);
+            assert!((az - az2).abs() < 1e-12);
+        }
+        assert!((inverse_stereographic_projection(0.0, 0.0).0 - PI / 2.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_projection() {
+        for projection in [Projection::Stereographic, Projection::Fisheye] {
+            for (alt, az) in [(0.0, 0.0), (0.3, 1.0), (-0.5, 4.0), (1.2, 6.0)] {
+                let (x, y) = projection.project(alt, az);
+                let (alt2, az2) = projection.unproject(x, y);
+                assert!((alt - alt2).abs() < 1e-12 && (az - az2).abs() < 1e-12);
+            }
+            // the same horizon and zenith either way
+            let (x, y) = projection.project(0.0, 1.0);
+            assert!((x.hypot(y) - 1.0).abs() < 1e-12);
+            let (x, y) = projection.project(PI / 2.0, 1.0);
+            assert!(x.hypot(y) < 1e-12);
+        }
+        // altitude circles evenly spaced in the fisheye, crowded towards the zenith in the stereographic
+        let radius = |projection: Projection, degrees: f64| projection.project(degrees / 180.0 * PI, 0.0).1;
+        assert!((radius(Projection::Fisheye, 30.0) - 2.0 / 3.0).abs() < 1e-12);
+        assert!(radius(Projection::Stereographic, 30.0) < 0.6);
+        let step = 1e-6;
+        let slope = (radius(Projection::Stereographic, 30.0) - radius(Projection::Stereographic, 30.0 + step))
+            / (step / 180.0 * PI);
+        assert!((slope - Projection::Stereographic.magnification(PI / 6.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_clip_circle() {
+        // fully inside as one closed polyline
+        let polylines = clip_circle((320.0, 320.0), 100.0, 640.0, 640.0);
+        assert_eq!(polylines.len(), 1);
+        let (first, last) = (polylines[0][0], *polylines[0].last().unwrap());
+        assert!((first.0 - last.0).abs() < 1e-9 && (first.1 - last.1).abs() < 1e-9);
+
+        // fully outside, and around the whole rectangle
+        assert!(clip_circle((2000.0, 320.0), 100.0, 640.0, 640.0).is_empty());
+        assert!(clip_circle((320.0, 320.0), 5000.0, 640.0, 640.0).is_empty());
+
+        // a huge circle grazing the rectangle is a flat line across it
+        let polylines = clip_circle((320.0, 1e9 + 100.0), 1e9, 640.0, 640.0);
+        assert_eq!(polylines.len(), 1);
+        let points = &polylines[0];
+        assert!(points.len() < 1000);
+        assert!(points.iter().all(|&(_, y)| (y - 100.0).abs() < 1.0));
+        let xs: Vec<f64> = points.iter().map(|&(x, _)| x).collect();
+        assert!(xs.iter().cloned().fold(f64::MAX, f64::min) < 1.0);
+        assert!(xs.iter().cloned().fold(f64::MIN, f64::max) > 639.0);
+
+        // a circle crossing two opposite edges leaves two pieces
+        let polylines = clip_circle((320.0, 1000.0), 400.0, 640.0, 2000.0);
+        assert_eq!(polylines.len(), 2);
+    }
+
+    #[test]
+    fn test_angular_distance() {
+        assert!(angular_distance((0.0, 0.0), (0.0, 0.0)).abs() < 1e-15);
+        assert!((angular_distance((0.0, 0.0), (0.0, PI / 2.0)) - PI / 2.0).abs() < 1e-15);
+        assert!((angular_distance((PI / 2.0, 0.0), (0.0, 1.0)) - PI / 2.0).abs() < 1e-15);
+        assert!((angular_distance((PI / 4.0, 0.0), (PI / 4.0, PI)) - PI / 2.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_great_circle_points() {
+        let (a, b) = ((0.2, 0.5), (1.1, 4.0));
+        let points = great_circle_points(a, b, 10);
+        assert_eq!(points.len(), 11);
+        assert!(angular_distance(points[0], a) < 1e-12 && angular_distance(points[10], b) < 1e-12);
+        // evenly spaced along the arc
+        let step = angular_distance(a, b) / 10.0;
+        for pair in points.windows(2) {
+            assert!((angular_distance(pair[0], pair[1]) - step).abs() < 1e-12);
+        }
+        assert!(great_circle_points(a, a, 4)
+            .iter()
+            .all(|&point| angular_distance(point, a) < 1e-12));
+    }
+}
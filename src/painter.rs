@@ -1,28 +1,148 @@
 use std::f64::consts::PI;
+use std::ops::{Deref, DerefMut};
 
+use antikythera::math::clip_circle;
 use sdl2::gfx::primitives::DrawRenderer;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
-use sdl2::render::{Canvas, Texture, TextureCreator};
+use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator};
 use sdl2::ttf::Font;
-use sdl2::video::Window;
+use sdl2::video::{Window, WindowContext};
 
-use crate::{horizontal_to_canvas, PANEL_SIZE};
+use crate::theme::mix_color;
+use crate::{canvas_scale, horizontal_to_canvas, inset_horizon_point, stereo_to_canvas, Grid, Layout, View};
+
+const GLOW_RINGS: i16 = 4;
+const GLOW_ALPHA: f64 = 60.0;
+const GLOW_SPREAD: f64 = 3.0; // pixels between rings at full strength
+const CORONA_RINGS: i16 = 8;
+const CORONA_ALPHA: f64 = 20.0; // per ring
+const CORONA_EXTENT: f64 = 1.5; // disk radii beyond the limb at full strength
+const GRADIENT_STEPS: i16 = 32;
+const COMPASS_STEP: usize = 10; // degrees between ticks
+const COMPASS_LABEL_STEP: usize = 30;
+const COMPASS_TICK: f64 = 4.0; // pixels inwards from the horizon
+const COMPASS_LONG_TICK: f64 = 8.0;
+const COMPASS_LABEL_INSET: f64 = 20.0;
+const STAR_SPIKE: f64 = 0.5; // length of the diagonal spikes of a star glyph against its arms
+const SUBSAMPLES: i16 = 4; // per side of a pixel on the edge of a multisampled disk
 
 pub trait Painter {
-    fn text(&mut self, text: &str, font: &Font, x: i16, y: i16, obj_size: i16);
+    fn text(&mut self, text: &str, font: &Font, x: i16, y: i16, obj_size: i16, color: Color);
     fn aa_filled_circle(&mut self, x: i16, y: i16, rad: i16, color: Color);
-    fn draw_azimuthal_grid(&mut self);
+    fn star(&mut self, x: i16, y: i16, size: i16, color: Color);
+    fn glow(&mut self, x: i16, y: i16, size: i16, strength: f64, color: Color);
+    fn corona(&mut self, x: i16, y: i16, size: i16, strength: f64, color: Color);
+    fn tint(&mut self, rect: Rect, color: Color);
+    fn radial_gradient(&mut self, x: i16, y: i16, radius: i16, inner: Color, outer: Color);
+    fn segment(&mut self, x1: i16, y1: i16, x2: i16, y2: i16, color: Color);
+    fn ring(&mut self, x: i16, y: i16, rad: i16, color: Color);
+
+    // the rest only needs lines and circles, so every painter draws them alike
+
+    fn clipped_circle(&mut self, center: (f64, f64), r: f64, bounds: (u32, u32), color: Color) {
+        for polyline in clip_circle(center, r, bounds.0 as f64, bounds.1 as f64) {
+            for pair in polyline.windows(2) {
+                let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+                self.segment(
+                    x1.round() as i16,
+                    y1.round() as i16,
+                    x2.round() as i16,
+                    y2.round() as i16,
+                    color,
+                );
+            }
+        }
+    }
+
+    fn horizontal_curve(&mut self, points: &[(f64, f64)], size: (u32, u32), layout: Layout, view: &View, color: Color) {
+        // points are (alt, az), segments dipping below the horizon are skipped
+        for pair in points.windows(2) {
+            let ((alt1, az1), (alt2, az2)) = (pair[0], pair[1]);
+            if alt1 < 0.0 || alt2 < 0.0 {
+                continue;
+            }
+            let (x1, y1) = horizontal_to_canvas(alt1, az1, size, layout, view);
+            let (x2, y2) = horizontal_to_canvas(alt2, az2, size, layout, view);
+            self.segment(x1, y1, x2, y2, color);
+        }
+    }
+
+    fn draw_azimuthal_grid(&mut self, size: (u32, u32), layout: Layout, view: &View, grid: Grid, color: Color) {
+        for i in 0..grid.spokes {
+            let angle = i as f64 / grid.spokes as f64 * PI;
+            let (x1, y1) = horizontal_to_canvas(0.0, angle, size, layout, view);
+            let (x2, y2) = horizontal_to_canvas(0.0, angle + PI, size, layout, view);
+            self.segment(x1, y1, x2, y2, color);
+        }
+
+        let (x, y) = stereo_to_canvas(0.0, 0.0, size, layout, view);
+        for i in (grid.spacing..90).step_by(grid.spacing as usize) {
+            let (r, _) = view.projection.project(i as f64 / 180.0 * PI, PI / 2.0);
+            let radius = (r * canvas_scale(size, layout, view)).round() as i16;
+            self.ring(x, y, radius, color);
+        }
+    }
+
+    fn glyph(&mut self, glyph: Glyph, x: i16, y: i16, size: i16, color: Color) {
+        // the faintest stars are too small for a shape and stay dots in every style
+        let arm = size + 1;
+        match glyph {
+            _ if size == 0 => self.star(x, y, size, color),
+            Glyph::Dot => self.star(x, y, size, color),
+            Glyph::Circle => self.ring(x, y, size, color),
+            Glyph::Cross => {
+                self.segment(x - arm, y, x + arm, y, color);
+                self.segment(x, y - arm, x, y + arm, color);
+            }
+            Glyph::Star => {
+                // a cross with shorter spikes between its arms
+                let spike = (arm as f64 * STAR_SPIKE).round() as i16;
+                self.segment(x - arm, y, x + arm, y, color);
+                self.segment(x, y - arm, x, y + arm, color);
+                self.segment(x - spike, y - spike, x + spike, y + spike, color);
+                self.segment(x - spike, y + spike, x + spike, y - spike, color);
+            }
+        }
+    }
+
+    fn draw_compass_rose(&mut self, size: (u32, u32), layout: Layout, view: &View, font: &Font, color: Color) {
+        // graduations along the horizon with the bearing every few ticks, the cardinal points keep their letters
+        let half_height = font.height() as i16 / 2;
+        for degrees in (0..360).step_by(COMPASS_STEP) {
+            if degrees % 90 == 0 {
+                continue;
+            }
+            let az = degrees as f64 / 180.0 * PI;
+            let labeled = degrees % COMPASS_LABEL_STEP == 0;
+            let length = if labeled { COMPASS_LONG_TICK } else { COMPASS_TICK };
+            let (x1, y1) = inset_horizon_point(az, 0.0, size, layout, view);
+            let (x2, y2) = inset_horizon_point(az, length, size, layout, view);
+            self.segment(
+                x1.round() as i16,
+                y1.round() as i16,
+                x2.round() as i16,
+                y2.round() as i16,
+                color,
+            );
+            if labeled {
+                let (x, y) = inset_horizon_point(az, COMPASS_LABEL_INSET, size, layout, view);
+                let label = degrees.to_string();
+                self.text(&label, font, x.round() as i16, y.round() as i16, -half_height, color);
+            }
+        }
+    }
 }
 
 fn render_text<'a, T>(
     font: &'a Font<'a, 'a>,
     texture_creator: &'a TextureCreator<T>,
     text: &'a str,
+    color: Color,
 ) -> (Texture<'a>, u32, u32) {
     let texture = font
         .render(text)
-        .blended(Color::RGB(0, 255, 0))
+        .blended(color)
         .unwrap()
         .as_texture(texture_creator)
         .unwrap();
@@ -30,51 +150,417 @@ fn render_text<'a, T>(
     (texture, x, y)
 }
 
-impl Painter for Canvas<Window> {
-    fn text(&mut self, text: &str, font: &Font, x: i16, y: i16, obj_size: i16) {
-        let texture_creator = self.texture_creator();
-        let (texture, xsize, ysize) = render_text(font, &texture_creator, text);
-        self.copy(
-            &texture,
-            None,
-            Rect::new(
-                (x - i16::try_from(xsize / 2).unwrap()).into(),
-                (y - obj_size - i16::try_from(ysize).unwrap()).into(),
-                xsize,
-                ysize,
-            ),
-        )
-        .unwrap();
+pub fn text_box(size: (u32, u32), x: i16, y: i16, obj_size: i16) -> Rect {
+    // where text of that size goes, centered over an object of obj_size at x, y
+    let (xsize, ysize) = size;
+    Rect::new(
+        (x - i16::try_from(xsize / 2).unwrap()).into(),
+        (y - obj_size - i16::try_from(ysize).unwrap()).into(),
+        xsize,
+        ysize,
+    )
+}
+
+// how stars are marked, their size still follows the magnitude
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Glyph {
+    Dot,
+    Circle,
+    Cross,
+    Star,
+}
+
+impl Glyph {
+    pub const ALL: [Glyph; 4] = [Glyph::Dot, Glyph::Circle, Glyph::Cross, Glyph::Star];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Glyph::Dot => "dot",
+            Glyph::Circle => "circle",
+            Glyph::Cross => "cross",
+            Glyph::Star => "star",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|glyph| glyph.name() == name)
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(Self::ALL.iter().position(|&glyph| glyph == self).unwrap() + 1) % Self::ALL.len()]
+    }
+}
+
+// how the edges of disks are smoothed, trading speed for looks
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Quality {
+    Off,
+    Default,
+    Multisample,
+}
+
+impl Quality {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(Quality::Off),
+            "default" => Some(Quality::Default),
+            "multisample" => Some(Quality::Multisample),
+            _ => None,
+        }
+    }
+}
+
+fn coverage(dx: i16, dy: i16, rad: f64) -> f64 {
+    // the share of the pixel at the offset from the center that falls inside the circle
+    let step = 1.0 / SUBSAMPLES as f64;
+    let mut inside = 0;
+    for i in 0..SUBSAMPLES {
+        for j in 0..SUBSAMPLES {
+            let sx = dx as f64 - 0.5 + (i as f64 + 0.5) * step;
+            let sy = dy as f64 - 0.5 + (j as f64 + 0.5) * step;
+            if sx.hypot(sy) <= rad {
+                inside += 1;
+            }
+        }
+    }
+    inside as f64 / (SUBSAMPLES * SUBSAMPLES) as f64
+}
+
+// the window canvas together with the one texture creator used for everything drawn on it
+pub struct Screen<'t> {
+    canvas: Canvas<Window>,
+    texture_creator: &'t TextureCreator<WindowContext>,
+    quality: Quality,
+}
+
+impl<'t> Screen<'t> {
+    pub fn new(canvas: Canvas<Window>, texture_creator: &'t TextureCreator<WindowContext>, quality: Quality) -> Self {
+        Self {
+            canvas,
+            texture_creator,
+            quality,
+        }
+    }
+
+    fn multisampled_disk(&mut self, x: i16, y: i16, rad: i16, color: Color) {
+        let r = rad as f64;
+        for dy in -rad - 1..=rad + 1 {
+            // the span lying wholly inside is filled at once, only the pixels on the edge are sampled
+            let far = dy.abs() as f64 + 0.5;
+            let inner = if far <= r {
+                ((r * r - far * far).sqrt() - 0.5).floor() as i16
+            } else {
+                -1
+            };
+            if inner >= 0 {
+                self.hline(x - inner, x + inner, y + dy, color).unwrap();
+            }
+            for dx in (inner + 1).max(0)..=rad + 1 {
+                let share = coverage(dx, dy, r);
+                if share == 0.0 {
+                    continue;
+                }
+                let edge = Color::RGBA(color.r, color.g, color.b, (color.a as f64 * share).round() as u8);
+                self.pixel(x + dx, y + dy, edge).unwrap();
+                if dx > 0 {
+                    self.pixel(x - dx, y + dy, edge).unwrap();
+                }
+            }
+        }
+    }
+}
+
+impl Deref for Screen<'_> {
+    type Target = Canvas<Window>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.canvas
+    }
+}
+
+impl DerefMut for Screen<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.canvas
+    }
+}
+
+impl Painter for Screen<'_> {
+    fn text(&mut self, text: &str, font: &Font, x: i16, y: i16, obj_size: i16, color: Color) {
+        let (texture, xsize, ysize) = render_text(font, self.texture_creator, text, color);
+        self.copy(&texture, None, text_box((xsize, ysize), x, y, obj_size))
+            .unwrap();
     }
 
     fn aa_filled_circle(&mut self, x: i16, y: i16, rad: i16, color: Color) {
-        self.filled_circle(x, y, rad, color).unwrap();
-        if rad >= 2 {
-            self.aa_circle(x, y, rad, color).unwrap();
+        match self.quality {
+            Quality::Off => self.filled_circle(x, y, rad, color).unwrap(),
+            Quality::Default => {
+                self.filled_circle(x, y, rad, color).unwrap();
+                if rad >= 2 {
+                    self.aa_circle(x, y, rad, color).unwrap();
+                }
+            }
+            Quality::Multisample => self.multisampled_disk(x, y, rad, color),
         }
     }
 
-    fn draw_azimuthal_grid(&mut self) {
-        let color = Color::RGB(30, 30, 30);
+    fn star(&mut self, x: i16, y: i16, size: i16, color: Color) {
+        match size {
+            0 => self.pixel(x, y, color).unwrap(),
+            _ => self.aa_filled_circle(x, y, size, color),
+        }
+    }
+
+    fn glow(&mut self, x: i16, y: i16, size: i16, strength: f64, color: Color) {
+        // translucent rings fading outwards, strength in 0..1
+        for ring in 1..=GLOW_RINGS {
+            let alpha = (GLOW_ALPHA * strength / ring as f64).round() as u8;
+            let radius = size + (ring as f64 * GLOW_SPREAD * strength).round() as i16;
+            self.filled_circle(x, y, radius, Color::RGBA(color.r, color.g, color.b, alpha))
+                .unwrap();
+        }
+    }
 
-        for i in 0..12 {
-            let angle = i as f64 / 12.0 * PI;
-            let (x1, y1) = horizontal_to_canvas(0.0, angle, self.logical_size());
-            let (x2, y2) = horizontal_to_canvas(0.0, angle + PI, self.logical_size());
-            self.aa_line(x1, y1, x2, y2, color).unwrap();
+    fn corona(&mut self, x: i16, y: i16, size: i16, strength: f64, color: Color) {
+        // like the glow but reaching out in proportion to the disk, the stacked rings thin out outwards
+        let alpha = (CORONA_ALPHA * strength).round() as u8;
+        for ring in 1..=CORONA_RINGS {
+            let reach = ring as f64 / CORONA_RINGS as f64;
+            let radius = size + (size as f64 * CORONA_EXTENT * strength * reach).round() as i16;
+            self.filled_circle(x, y, radius, Color::RGBA(color.r, color.g, color.b, alpha))
+                .unwrap();
         }
+    }
+
+    fn radial_gradient(&mut self, x: i16, y: i16, radius: i16, inner: Color, outer: Color) {
+        // concentric disks from the rim inwards, each a step closer to the inner color
+        for step in 0..GRADIENT_STEPS {
+            let t = step as f64 / GRADIENT_STEPS as f64;
+            let color = mix_color(outer, inner, t);
+            let r = (radius as f64 * (1.0 - t)).round() as i16;
+            self.filled_circle(x, y, r, color).unwrap();
+        }
+    }
 
-        for i in (20..=80).step_by(20) {
-            let (_, y) = horizontal_to_canvas(i as f64 / 180.0 * PI, 0.0, self.logical_size());
-            let (width, height) = self.logical_size();
-            let radius = <u32 as std::convert::TryInto<i16>>::try_into((height - PANEL_SIZE) / 2).unwrap() - y;
-            self.aa_circle(
-                (width / 2).try_into().unwrap(),
-                ((height - PANEL_SIZE) / 2).try_into().unwrap(),
+    fn tint(&mut self, rect: Rect, color: Color) {
+        // multiplies what is already drawn, so textures can be colored after the fact
+        let previous = self.blend_mode();
+        self.set_blend_mode(BlendMode::Mod);
+        self.set_draw_color(color);
+        self.fill_rect(rect).unwrap();
+        self.set_blend_mode(previous);
+    }
+
+    fn segment(&mut self, x1: i16, y1: i16, x2: i16, y2: i16, color: Color) {
+        self.aa_line(x1, y1, x2, y2, color).unwrap();
+    }
+
+    fn ring(&mut self, x: i16, y: i16, rad: i16, color: Color) {
+        self.aa_circle(x, y, rad, color).unwrap();
+    }
+}
+
+// the same drawing written out as svg elements, for a chart that scales cleanly in print
+pub struct SvgPainter {
+    size: (u32, u32),
+    defs: Vec<String>,
+    elements: Vec<String>,
+}
+
+impl SvgPainter {
+    pub fn new(size: (u32, u32)) -> Self {
+        Self {
+            size,
+            defs: Vec::new(),
+            elements: Vec::new(),
+        }
+    }
+
+    pub fn finish(self) -> String {
+        let (width, height) = self.size;
+        let mut document = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+            width, height, width, height
+        );
+        if !self.defs.is_empty() {
+            document += &format!("<defs>\n{}\n</defs>\n", self.defs.join("\n"));
+        }
+        for element in &self.elements {
+            document += element;
+            document.push('\n');
+        }
+        document += "</svg>\n";
+        document
+    }
+
+    fn disk(&mut self, x: f64, y: f64, r: f64, color: Color) {
+        self.elements.push(format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" {}/>",
+            x,
+            y,
+            r,
+            paint("fill", color)
+        ));
+    }
+}
+
+fn paint(attribute: &str, color: Color) -> String {
+    let Color { r, g, b, a } = color;
+    match a {
+        255 => format!("{}=\"rgb({},{},{})\"", attribute, r, g, b),
+        _ => format!(
+            "{}=\"rgb({},{},{})\" {}-opacity=\"{:.3}\"",
+            attribute,
+            r,
+            g,
+            b,
+            attribute,
+            a as f64 / 255.0
+        ),
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+impl Painter for SvgPainter {
+    fn text(&mut self, text: &str, font: &Font, x: i16, y: i16, obj_size: i16, color: Color) {
+        // centered above the object like the rendered text, the baseline a descent up from the bottom
+        let baseline = y - obj_size + font.descent() as i16;
+        self.elements.push(format!(
+            "<text x=\"{}\" y=\"{}\" font-size=\"{}\" font-family=\"sans-serif\" text-anchor=\"middle\" {}>{}</text>",
+            x,
+            baseline,
+            font.height(),
+            paint("fill", color),
+            escape_xml(text)
+        ));
+    }
+
+    fn aa_filled_circle(&mut self, x: i16, y: i16, rad: i16, color: Color) {
+        self.disk(x.into(), y.into(), rad.into(), color);
+    }
+
+    fn star(&mut self, x: i16, y: i16, size: i16, color: Color) {
+        // the faintest stars are a single pixel on screen, here a dot of the same area
+        let r = if size == 0 { 0.5 } else { size.into() };
+        self.disk(x.into(), y.into(), r, color);
+    }
+
+    fn glow(&mut self, x: i16, y: i16, size: i16, strength: f64, color: Color) {
+        for ring in 1..=GLOW_RINGS {
+            let alpha = (GLOW_ALPHA * strength / ring as f64).round() as u8;
+            let radius = size as f64 + ring as f64 * GLOW_SPREAD * strength;
+            self.disk(
+                x.into(),
+                y.into(),
                 radius,
-                color,
-            )
-            .unwrap();
+                Color::RGBA(color.r, color.g, color.b, alpha),
+            );
         }
     }
+
+    fn corona(&mut self, x: i16, y: i16, size: i16, strength: f64, color: Color) {
+        let alpha = (CORONA_ALPHA * strength).round() as u8;
+        for ring in 1..=CORONA_RINGS {
+            let reach = ring as f64 / CORONA_RINGS as f64;
+            let radius = size as f64 * (1.0 + CORONA_EXTENT * strength * reach);
+            self.disk(
+                x.into(),
+                y.into(),
+                radius,
+                Color::RGBA(color.r, color.g, color.b, alpha),
+            );
+        }
+    }
+
+    fn tint(&mut self, _rect: Rect, _color: Color) {
+        // only textures get tinted and the chart has none
+    }
+
+    fn radial_gradient(&mut self, x: i16, y: i16, radius: i16, inner: Color, outer: Color) {
+        let id = format!("gradient{}", self.defs.len());
+        self.defs.push(format!(
+            "<radialGradient id=\"{}\"><stop offset=\"0\" {}/><stop offset=\"1\" {}/></radialGradient>",
+            id,
+            paint("stop-color", inner),
+            paint("stop-color", outer)
+        ));
+        self.elements.push(format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"url(#{})\"/>",
+            x, y, radius, id
+        ));
+    }
+
+    fn segment(&mut self, x1: i16, y1: i16, x2: i16, y2: i16, color: Color) {
+        self.elements.push(format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" {}/>",
+            x1,
+            y1,
+            x2,
+            y2,
+            paint("stroke", color)
+        ));
+    }
+
+    fn ring(&mut self, x: i16, y: i16, rad: i16, color: Color) {
+        self.elements.push(format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" {}/>",
+            x,
+            y,
+            rad,
+            paint("stroke", color)
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glyph() {
+        assert_eq!(Glyph::parse("cross"), Some(Glyph::Cross));
+        assert_eq!(Glyph::parse("square"), None);
+        assert_eq!(Glyph::Dot.next(), Glyph::Circle);
+        assert_eq!(Glyph::Star.next(), Glyph::Dot);
+        let mut svg = SvgPainter::new((100, 100));
+        svg.glyph(Glyph::Cross, 50, 50, 3, Color::WHITE);
+        svg.glyph(Glyph::Circle, 20, 20, 0, Color::WHITE);
+        let document = svg.finish();
+        assert!(document.contains("<line x1=\"46\" y1=\"50\" x2=\"54\" y2=\"50\""));
+        assert!(document.contains("<circle cx=\"20\" cy=\"20\" r=\"0.5\""));
+    }
+
+    #[test]
+    fn test_coverage() {
+        assert_eq!(coverage(0, 0, 3.0), 1.0);
+        assert_eq!(coverage(5, 0, 3.0), 0.0);
+        let edge = coverage(3, 0, 3.0);
+        assert!(edge > 0.0 && edge < 1.0);
+        // the shares add up to about the area of the disk
+        let area: f64 = (-6..=6)
+            .flat_map(|dx| (-6..=6).map(move |dy| coverage(dx, dy, 5.0)))
+            .sum();
+        assert!((area - 25.0 * PI).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_svg_painter() {
+        let mut svg = SvgPainter::new((200, 100));
+        svg.segment(0, 1, 20, 30, Color::RGB(255, 0, 0));
+        svg.ring(50, 50, 10, Color::RGBA(0, 0, 255, 51));
+        svg.star(5, 5, 0, Color::WHITE);
+        svg.radial_gradient(50, 50, 40, Color::BLACK, Color::WHITE);
+        let document = svg.finish();
+        assert!(document.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"200\" height=\"100\""));
+        assert!(document.contains("<line x1=\"0\" y1=\"1\" x2=\"20\" y2=\"30\" stroke=\"rgb(255,0,0)\"/>"));
+        assert!(document.contains("stroke=\"rgb(0,0,255)\" stroke-opacity=\"0.200\""));
+        assert!(document.contains("<circle cx=\"5\" cy=\"5\" r=\"0.5\" fill=\"rgb(255,255,255)\"/>"));
+        assert!(document.contains("<defs>\n<radialGradient id=\"gradient0\">"));
+        assert!(document.contains("fill=\"url(#gradient0)\""));
+        assert!(document.ends_with("</svg>\n"));
+        assert_eq!(escape_xml("Alpha & <Beta>"), "Alpha &amp; &lt;Beta&gt;");
+    }
 }
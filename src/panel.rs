@@ -0,0 +1,110 @@
+use crate::config::Config;
+
+// the readouts the bottom panel can show, in the order the config lists them
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Field {
+    World,
+    Location,
+    Time,
+    Step,
+    TimeDisplay,
+    Sidereal,
+    JulianDate,
+    MoonPhase,
+    Center,
+    Libration,
+    Transits,
+    EclipseSeason,
+    Conditions,
+    Highlight,
+    Circumpolar,
+}
+
+const NAMES: [(Field, &str); 15] = [
+    (Field::World, "world"),
+    (Field::Location, "location"),
+    (Field::Time, "time"),
+    (Field::Step, "step"),
+    (Field::TimeDisplay, "time_display"),
+    (Field::Sidereal, "sidereal"),
+    (Field::JulianDate, "julian_date"),
+    (Field::MoonPhase, "moon_phase"),
+    (Field::Center, "center"),
+    (Field::Libration, "libration"),
+    (Field::Transits, "transits"),
+    (Field::EclipseSeason, "eclipse_season"),
+    (Field::Conditions, "conditions"),
+    (Field::Highlight, "highlight"),
+    (Field::Circumpolar, "circumpolar"),
+];
+
+// the panel as it always was
+const DEFAULT_FIELDS: [Field; 11] = [
+    Field::World,
+    Field::Location,
+    Field::Time,
+    Field::Step,
+    Field::TimeDisplay,
+    Field::Center,
+    Field::Libration,
+    Field::Transits,
+    Field::EclipseSeason,
+    Field::Conditions,
+    Field::Highlight,
+];
+
+pub fn load(config: &Config) -> Vec<Field> {
+    match config.get("panel", "fields") {
+        Some(value) => parse(value),
+        None => DEFAULT_FIELDS.to_vec(),
+    }
+}
+
+fn parse(value: &str) -> Vec<Field> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .filter_map(|name| {
+            let field = NAMES.iter().find(|&&(_, known)| known == name).map(|&(field, _)| field);
+            if field.is_none() {
+                eprintln!("Unknown panel field {}", name);
+            }
+            field
+        })
+        .collect()
+}
+
+pub fn truncate(text: &str, width: usize) -> String {
+    // whole fields are dropped from the end first, a single field too long is cut short, an ellipsis marks the cut
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(width.saturating_sub(1)).collect();
+    let cut = match cut.rfind("; ") {
+        Some(end) => &cut[..end],
+        None => &cut,
+    };
+    format!("{}…", cut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load() {
+        assert_eq!(load(&Config::parse("")), DEFAULT_FIELDS);
+        let config = Config::parse("[panel]\nfields = julian_date, location,, bogus, moon_phase\n");
+        assert_eq!(load(&config), [Field::JulianDate, Field::Location, Field::MoonPhase]);
+        assert!(load(&Config::parse("[panel]\nfields =\n")).is_empty());
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("lat: 1; lon: 2", 14), "lat: 1; lon: 2");
+        assert_eq!(truncate("lat: 1; lon: 2", 13), "lat: 1…");
+        assert_eq!(truncate("Transit of Venus", 8), "Transit…");
+        assert_eq!(truncate("Transit", 0), "…");
+    }
+}
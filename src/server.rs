@@ -0,0 +1,114 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use chrono::{DateTime, Utc};
+
+use crate::json::Json;
+
+// a line from a client, answered by the render loop which owns the sky
+pub struct Request {
+    pub command: String,
+    pub reply: Sender<Json>,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum Command {
+    Position(String),
+    Time,
+    GoTo(DateTime<Utc>),
+}
+
+pub fn listen(port: u16) -> io::Result<Receiver<Request>> {
+    // only on the loopback interface, whoever reaches it can move the time
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let sender = sender.clone();
+            thread::spawn(move || serve(stream, sender));
+        }
+    });
+    Ok(receiver)
+}
+
+fn serve(stream: TcpStream, requests: Sender<Request>) {
+    // a command per line in, a json document per line out, until either side goes away
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (reply, answer) = mpsc::channel();
+        let request = Request {
+            command: line.trim().to_string(),
+            reply,
+        };
+        if requests.send(request).is_err() {
+            return;
+        }
+        let Ok(answer) = answer.recv() else {
+            return;
+        };
+        if writeln!(writer, "{}", answer.compact()).is_err() {
+            return;
+        }
+    }
+}
+
+pub fn parse_command(line: &str) -> Result<Command, String> {
+    let (verb, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let rest = rest.trim();
+    match verb.to_lowercase().as_str() {
+        "position" => {
+            let name = rest.strip_prefix("of ").unwrap_or(rest).trim();
+            if name.is_empty() {
+                Err("position of what?".to_string())
+            } else {
+                Ok(Command::Position(name.to_string()))
+            }
+        }
+        "time" if rest.is_empty() => Ok(Command::Time),
+        "goto" => DateTime::parse_from_rfc3339(rest)
+            .map(|time| Command::GoTo(time.with_timezone(&Utc)))
+            .map_err(|_| format!("invalid time {}", rest)),
+        _ => Err(format!("unknown command {}", line)),
+    }
+}
+
+pub fn error(message: String) -> Json {
+    Json::Object(vec![("error".to_string(), Json::String(message))])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command("position of Jupiter"),
+            Ok(Command::Position("Jupiter".to_string()))
+        );
+        assert_eq!(
+            parse_command("POSITION Polaris Australis"),
+            Ok(Command::Position("Polaris Australis".to_string()))
+        );
+        assert_eq!(parse_command("time"), Ok(Command::Time));
+        let time = DateTime::parse_from_rfc3339("2024-04-08T18:42:00Z").unwrap();
+        assert_eq!(
+            parse_command("goto 2024-04-08T18:42:00Z"),
+            Ok(Command::GoTo(time.with_timezone(&Utc)))
+        );
+        assert!(parse_command("goto tomorrow").is_err());
+        assert!(parse_command("position").is_err());
+        assert!(parse_command("time now").is_err());
+        assert!(parse_command("launch").is_err());
+    }
+}
@@ -0,0 +1,510 @@
+use std::f64::consts::PI;
+use std::fs::{read_to_string, write};
+use std::io;
+use std::path::Path;
+
+use antikythera::astro::WORLDS;
+use antikythera::math::Projection;
+use antikythera::{Planet, SkyObject, Star};
+use chrono::{DateTime, Utc};
+
+use crate::bookmarks::Bookmark;
+use crate::conditions::Conditions;
+use crate::format::Notation;
+use crate::json::{self, Json};
+use crate::painter::Glyph;
+use crate::theme::PRESETS;
+use crate::trails::Trails;
+use crate::{
+    Display, LabelMode, Pane, TimeDisplay, View, HIGHLIGHT_STEP, MAX_HIGHLIGHT_ALTITUDE, MAX_SEEING, MAX_ZOOM, STEPS,
+};
+
+// bumped whenever an older file could no longer be read back faithfully
+const VERSION: f64 = 1.0;
+const MAX_PANES: usize = 2;
+
+// everything the user can change while running, saved and restored as a whole
+pub struct Session {
+    pub panes: Vec<Pane>,
+    pub focus: usize,
+    pub display: Display,
+    pub selected: Option<SkyObject>,
+    pub preset: usize, // index into PRESETS
+    pub bookmarks: Vec<Bookmark>,
+}
+
+pub fn save(path: &Path, session: &Session, stars: &[Star], planets: &[Planet]) -> io::Result<()> {
+    write(path, to_json(session, stars, planets).to_string() + "\n")
+}
+
+pub fn load(path: &Path, session: &mut Session, stars: &[Star], planets: &[Planet]) -> Result<(), String> {
+    let content = read_to_string(path).map_err(|error| format!("Couldn't read {}: {}", path.display(), error))?;
+    let value = json::parse(&content).ok_or_else(|| format!("{} is not a session file", path.display()))?;
+    apply(&value, session, stars, planets).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+fn degrees(angle: f64) -> Json {
+    Json::Number(angle / PI * 180.0)
+}
+
+fn view_to_json(view: &View) -> Json {
+    Json::Object(vec![
+        ("zoom".to_string(), Json::Number(view.zoom)),
+        ("pan_x".to_string(), Json::Number(view.pan.0)),
+        ("pan_y".to_string(), Json::Number(view.pan.1)),
+        ("flipped".to_string(), Json::Bool(view.flipped)),
+        (
+            "projection".to_string(),
+            Json::String(
+                match view.projection {
+                    Projection::Stereographic => "stereographic",
+                    Projection::Fisheye => "fisheye",
+                }
+                .to_string(),
+            ),
+        ),
+    ])
+}
+
+fn selection_to_json(selection: Option<SkyObject>, stars: &[Star], planets: &[Planet]) -> Json {
+    // catalog entries by identifier rather than index, which depends on the star limit
+    let (kind, identifier) = match selection {
+        None => return Json::Null,
+        Some(SkyObject::Star(index)) => ("star", Some(("hip", Json::Number(stars[index].hip as f64)))),
+        Some(SkyObject::Planet(index)) => ("planet", Some(("name", Json::String(planets[index].name.clone())))),
+        Some(SkyObject::Sun) => ("sun", None),
+        Some(SkyObject::Moon) => ("moon", None),
+        // no catalog to find them in again on the next start
+        Some(SkyObject::DeepSky(_) | SkyObject::Satellite(_)) => return Json::Null,
+    };
+    let mut entries = vec![("type".to_string(), Json::String(kind.to_string()))];
+    entries.extend(identifier.map(|(key, value)| (key.to_string(), value)));
+    Json::Object(entries)
+}
+
+fn toggles(display: &mut Display) -> [(&'static str, &mut bool); 24] {
+    // every on and off setting by its name in the file, saving and restoring both go through this one list
+    [
+        ("aberration", &mut display.aberration),
+        ("moonlight", &mut display.moonlight),
+        ("double_markers", &mut display.double_markers),
+        ("galactic_grid", &mut display.galactic_grid),
+        ("boundaries", &mut display.boundaries),
+        ("hour_ring", &mut display.hour_ring),
+        ("rise_points", &mut display.rise_points),
+        ("compass", &mut display.compass),
+        ("stats", &mut display.stats),
+        ("lunar_nodes", &mut display.lunar_nodes),
+        ("trails", &mut display.trails),
+        ("info_panel", &mut display.info_panel),
+        ("fps_overlay", &mut display.fps_overlay),
+        ("crosshair", &mut display.crosshair),
+        ("day_length_chart", &mut display.day_length_chart),
+        ("twinkle", &mut display.twinkle),
+        ("glow", &mut display.glow),
+        ("atmosphere", &mut display.atmosphere),
+        ("refraction", &mut display.refraction),
+        ("gradient", &mut display.gradient),
+        ("zodiacal_light", &mut display.zodiacal_light),
+        ("leaders", &mut display.leaders),
+        ("minimap", &mut display.minimap),
+        ("libration", &mut display.libration),
+    ]
+}
+
+fn to_json(session: &Session, stars: &[Star], planets: &[Planet]) -> Json {
+    let display = &session.display;
+    let panes = session
+        .panes
+        .iter()
+        .map(|pane| {
+            Json::Object(vec![
+                ("time".to_string(), Json::String(pane.time.to_rfc3339())),
+                ("latitude".to_string(), degrees(pane.latitude)),
+                ("longitude".to_string(), degrees(pane.longitude)),
+                ("step".to_string(), Json::Number(pane.step as f64)),
+                ("world".to_string(), Json::String(WORLDS[pane.world].name.to_string())),
+                ("view".to_string(), view_to_json(&pane.view)),
+            ])
+        })
+        .collect();
+    let label_mode = match display.label_mode {
+        LabelMode::Proper => "proper",
+        LabelMode::Designation => "designation",
+        LabelMode::None => "none",
+    };
+    let time_display = match display.time_display {
+        TimeDisplay::Clock => "clock",
+        TimeDisplay::Solar => "solar",
+        TimeDisplay::Sidereal => "sidereal",
+    };
+    let notation = match display.notation {
+        Notation::Decimal => "decimal",
+        Notation::Sexagesimal => "sexagesimal",
+    };
+    // kept whole, the config they came from may have changed since
+    let conditions = match &display.conditions {
+        Some(conditions) => Json::Object(vec![
+            ("name".to_string(), Json::String(conditions.name.clone())),
+            ("limit".to_string(), Json::Number(conditions.limit)),
+            ("extinction".to_string(), Json::Number(conditions.extinction)),
+            ("gamma".to_string(), Json::Number(conditions.gamma)),
+        ]),
+        None => Json::Null,
+    };
+    let mut display_entries: Vec<(String, Json)> = toggles(&mut display.clone())
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), Json::Bool(*value)))
+        .collect();
+    display_entries.extend([
+        ("label_mode".to_string(), Json::String(label_mode.to_string())),
+        ("label_magnitude".to_string(), Json::Number(display.label_magnitude)),
+        ("seeing".to_string(), Json::Number(display.seeing as f64)),
+        (
+            "highlight_altitude".to_string(),
+            Json::Number(display.highlight_altitude as f64),
+        ),
+        ("conditions".to_string(), conditions),
+        ("coordinates".to_string(), Json::String(notation.to_string())),
+        ("star_glyph".to_string(), Json::String(display.glyph.name().to_string())),
+        ("time_display".to_string(), Json::String(time_display.to_string())),
+    ]);
+    let bookmarks = session
+        .bookmarks
+        .iter()
+        .map(|bookmark| {
+            Json::Object(vec![
+                ("label".to_string(), Json::String(bookmark.label.clone())),
+                ("time".to_string(), Json::String(bookmark.time.to_rfc3339())),
+                ("latitude".to_string(), degrees(bookmark.latitude)),
+                ("longitude".to_string(), degrees(bookmark.longitude)),
+                ("view".to_string(), view_to_json(&bookmark.view)),
+            ])
+        })
+        .collect();
+    Json::Object(vec![
+        ("version".to_string(), Json::Number(VERSION)),
+        ("panes".to_string(), Json::Array(panes)),
+        ("focus".to_string(), Json::Number(session.focus as f64)),
+        ("display".to_string(), Json::Object(display_entries)),
+        ("theme".to_string(), Json::String(PRESETS[session.preset].to_string())),
+        (
+            "selected".to_string(),
+            selection_to_json(session.selected, stars, planets),
+        ),
+        ("bookmarks".to_string(), Json::Array(bookmarks)),
+    ])
+}
+
+fn parse_time(value: &Json) -> Option<DateTime<Utc>> {
+    Some(DateTime::parse_from_rfc3339(value.as_str()?).ok()?.with_timezone(&Utc))
+}
+
+fn parse_view(value: &Json) -> Option<View> {
+    Some(View {
+        // a hand edited file can hold anything, the same limits as zooming interactively
+        zoom: value.get("zoom")?.as_f64()?.clamp(1.0, MAX_ZOOM),
+        pan: (value.get("pan_x")?.as_f64()?, value.get("pan_y")?.as_f64()?),
+        flipped: value.get("flipped")?.as_bool()?,
+        // sessions saved before the fisheye have none
+        projection: match value.get("projection").and_then(Json::as_str) {
+            Some("fisheye") => Projection::Fisheye,
+            _ => Projection::Stereographic,
+        },
+    })
+}
+
+fn parse_pane(value: &Json) -> Option<Pane> {
+    let step = value.get("step")?.as_f64()? as usize;
+    let world = value.get("world")?.as_str()?;
+    Some(Pane {
+        latitude: (value.get("latitude")?.as_f64()? / 180.0 * PI).clamp(-PI / 2.0, PI / 2.0),
+        longitude: value.get("longitude")?.as_f64()? / 180.0 * PI,
+        time: parse_time(value.get("time")?)?,
+        step: (step < STEPS.len()).then_some(step)?,
+        view: parse_view(value.get("view")?)?,
+        flight: None,
+        trip: None,
+        home_latitude: None,
+        world: WORLDS.iter().position(|known| known.name == world)?,
+        trails: Trails::default(),
+    })
+}
+
+fn parse_bookmark(value: &Json) -> Option<Bookmark> {
+    Some(Bookmark {
+        label: value.get("label")?.as_str()?.to_string(),
+        time: parse_time(value.get("time")?)?,
+        latitude: (value.get("latitude")?.as_f64()? / 180.0 * PI).clamp(-PI / 2.0, PI / 2.0),
+        longitude: value.get("longitude")?.as_f64()? / 180.0 * PI,
+        view: parse_view(value.get("view")?)?,
+    })
+}
+
+fn parse_selection(value: &Json, stars: &[Star], planets: &[Planet]) -> Option<SkyObject> {
+    match value.get("type")?.as_str()? {
+        "star" => {
+            let hip = value.get("hip")?.as_f64()? as u32;
+            stars.iter().position(|star| star.hip == hip).map(SkyObject::Star)
+        }
+        "planet" => {
+            let name = value.get("name")?.as_str()?;
+            planets
+                .iter()
+                .position(|planet| planet.name == name)
+                .map(SkyObject::Planet)
+        }
+        "sun" => Some(SkyObject::Sun),
+        "moon" => Some(SkyObject::Moon),
+        _ => None,
+    }
+}
+
+fn apply(value: &Json, session: &mut Session, stars: &[Star], planets: &[Planet]) -> Result<(), String> {
+    // the version and the panes have to be right, anything else falls back to what is already set
+    if value.get("version").and_then(Json::as_f64) != Some(VERSION) {
+        return Err("incompatible session version".to_string());
+    }
+    let panes: Option<Vec<Pane>> = value
+        .get("panes")
+        .and_then(Json::as_array)
+        .map(|panes| panes.iter().map(parse_pane).collect())
+        .unwrap_or_default();
+    let panes = match panes {
+        Some(panes) if (1..=MAX_PANES).contains(&panes.len()) => panes,
+        _ => return Err("invalid panes".to_string()),
+    };
+
+    let focus = value.get("focus").and_then(Json::as_f64).unwrap_or(0.0) as usize;
+    session.focus = focus.min(panes.len() - 1);
+    session.panes = panes;
+
+    if let Some(display) = value.get("display") {
+        let settings = &mut session.display;
+        for (name, toggle) in toggles(settings) {
+            if let Some(value) = display.get(name).and_then(Json::as_bool) {
+                *toggle = value;
+            }
+        }
+        match display.get("label_mode").and_then(Json::as_str) {
+            Some("proper") => settings.label_mode = LabelMode::Proper,
+            Some("designation") => settings.label_mode = LabelMode::Designation,
+            Some("none") => settings.label_mode = LabelMode::None,
+            _ => {}
+        }
+        if let Some(magnitude) = display.get("label_magnitude").and_then(Json::as_f64) {
+            settings.label_magnitude = magnitude;
+        }
+        if let Some(seeing) = display.get("seeing").and_then(Json::as_f64) {
+            settings.seeing = (seeing.max(0.0) as u32).min(MAX_SEEING);
+        }
+        if let Some(altitude) = display.get("highlight_altitude").and_then(Json::as_f64) {
+            let altitude = (altitude.max(0.0) as u32).min(MAX_HIGHLIGHT_ALTITUDE);
+            settings.highlight_altitude = altitude - altitude % HIGHLIGHT_STEP;
+        }
+        match display.get("conditions") {
+            Some(Json::Null) => settings.conditions = None,
+            Some(conditions) => {
+                let value = |key: &str| conditions.get(key).and_then(Json::as_f64);
+                if let (Some(name), Some(limit), Some(extinction), Some(gamma)) = (
+                    conditions.get("name").and_then(Json::as_str),
+                    value("limit"),
+                    value("extinction"),
+                    value("gamma"),
+                ) {
+                    settings.conditions = Some(Conditions {
+                        name: name.to_string(),
+                        limit,
+                        extinction,
+                        gamma,
+                    });
+                }
+            }
+            None => {}
+        }
+        if let Some(notation) = display
+            .get("coordinates")
+            .and_then(Json::as_str)
+            .and_then(Notation::parse)
+        {
+            settings.notation = notation;
+        }
+        if let Some(glyph) = display.get("star_glyph").and_then(Json::as_str).and_then(Glyph::parse) {
+            settings.glyph = glyph;
+        }
+        match display.get("time_display").and_then(Json::as_str) {
+            Some("clock") => settings.time_display = TimeDisplay::Clock,
+            Some("solar") => settings.time_display = TimeDisplay::Solar,
+            Some("sidereal") => settings.time_display = TimeDisplay::Sidereal,
+            _ => {}
+        }
+    }
+
+    if let Some(preset) = value
+        .get("theme")
+        .and_then(Json::as_str)
+        .and_then(|name| PRESETS.iter().position(|&preset| preset == name))
+    {
+        session.preset = preset;
+    }
+    session.selected = value
+        .get("selected")
+        .and_then(|selected| parse_selection(selected, stars, planets));
+    if let Some(bookmarks) = value.get("bookmarks").and_then(Json::as_array) {
+        session.bookmarks = bookmarks.iter().filter_map(parse_bookmark).collect();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NEUTRAL_STEP;
+
+    fn session() -> Session {
+        Session {
+            panes: vec![Pane {
+                latitude: 0.9,
+                longitude: 0.1,
+                time: DateTime::parse_from_rfc3339("2026-08-12T17:46:00Z")
+                    .unwrap()
+                    .with_timezone(&Utc),
+                step: NEUTRAL_STEP,
+                view: View::default(),
+                flight: None,
+                trip: None,
+                home_latitude: None,
+                world: 0,
+                trails: Trails::default(),
+            }],
+            focus: 0,
+            display: Display::new(2.5, Notation::Decimal, Glyph::Dot),
+            selected: None,
+            preset: 0,
+            bookmarks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let planets = [Planet {
+            name: "Mars".to_string(),
+            semimajor: 227.956,
+            sidereal: 59355072.0,
+            phase: 0.0,
+            inclination: 0.0,
+            incl_phase: 0.0,
+            abs_magnitude: -1.52,
+            texture: None,
+        }];
+        let mut saved = session();
+        let mut pane = saved.panes[0].clone();
+        pane.world = 1;
+        pane.step = NEUTRAL_STEP + 2;
+        pane.view = View {
+            zoom: 3.0,
+            pan: (0.25, -0.5),
+            flipped: true,
+            projection: Projection::Fisheye,
+        };
+        saved.panes.push(pane);
+        saved.focus = 1;
+        saved.display.hour_ring = true;
+        saved.display.glow = false;
+        saved.display.seeing = 3;
+        saved.display.highlight_altitude = 30;
+        saved.display.conditions = Some(Conditions {
+            name: "backyard".to_string(),
+            limit: 5.5,
+            extinction: 0.25,
+            gamma: 1.1,
+        });
+        saved.display.label_mode = LabelMode::Designation;
+        saved.display.notation = Notation::Sexagesimal;
+        saved.display.glyph = Glyph::Cross;
+        saved.display.time_display = TimeDisplay::Sidereal;
+        saved.selected = Some(SkyObject::Planet(0));
+        saved.preset = 1;
+        saved.bookmarks.push(Bookmark {
+            label: "Perseids".to_string(),
+            time: saved.panes[0].time,
+            latitude: -0.3,
+            longitude: 2.0,
+            view: View::default(),
+        });
+
+        let text = to_json(&saved, &[], &planets).to_string();
+        let mut loaded = session();
+        apply(&json::parse(&text).unwrap(), &mut loaded, &[], &planets).unwrap();
+        assert_eq!(loaded.panes.len(), 2);
+        assert_eq!(loaded.focus, 1);
+        assert!((loaded.panes[0].latitude - 0.9).abs() < 1e-12);
+        assert_eq!(loaded.panes[0].time, saved.panes[0].time);
+        assert_eq!(loaded.panes[1].world, 1);
+        assert_eq!(loaded.panes[1].step, NEUTRAL_STEP + 2);
+        assert_eq!(loaded.panes[1].view.pan, (0.25, -0.5));
+        assert!(loaded.panes[1].view.flipped);
+        assert_eq!(loaded.panes[1].view.projection, Projection::Fisheye);
+        assert_eq!(loaded.panes[0].view.projection, Projection::Stereographic);
+        assert!(loaded.display.hour_ring && !loaded.display.glow);
+        assert_eq!(loaded.display.seeing, 3);
+        assert_eq!(loaded.display.highlight_altitude, 30);
+        assert_eq!(loaded.display.conditions, saved.display.conditions);
+        assert!(matches!(loaded.display.label_mode, LabelMode::Designation));
+        assert_eq!(loaded.display.notation, Notation::Sexagesimal);
+        assert_eq!(loaded.display.glyph, Glyph::Cross);
+        assert!(matches!(loaded.display.time_display, TimeDisplay::Sidereal));
+        assert!(loaded.selected == Some(SkyObject::Planet(0)));
+        assert_eq!(loaded.preset, 1);
+        assert_eq!(loaded.bookmarks.len(), 1);
+        assert!((loaded.bookmarks[0].longitude - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_incompatible() {
+        let mut loaded = session();
+        loaded.display.glow = false;
+        for text in [
+            "{\"version\": 2, \"panes\": []}",
+            "{\"version\": 1, \"panes\": []}",
+            "{\"version\": 1, \"panes\": [{\"time\": \"yesterday\"}]}",
+        ] {
+            assert!(apply(&json::parse(text).unwrap(), &mut loaded, &[], &[]).is_err());
+        }
+        assert!(!loaded.display.glow);
+        assert!(load(Path::new("/nonexistent/antikythera.session"), &mut loaded, &[], &[]).is_err());
+
+        // missing settings keep their current values
+        let text = to_json(&session(), &[], &[]).to_string();
+        let mut value = json::parse(&text).unwrap();
+        if let Json::Object(entries) = &mut value {
+            entries.retain(|(key, _)| key != "display");
+        }
+        apply(&value, &mut loaded, &[], &[]).unwrap();
+        assert!(!loaded.display.glow);
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let mut saved = session();
+        saved.panes[0].latitude = 3.0;
+        saved.panes[0].view.zoom = 1000.0;
+        saved.bookmarks.push(Bookmark {
+            label: "pole".to_string(),
+            time: saved.panes[0].time,
+            latitude: -3.0,
+            longitude: 0.0,
+            view: View {
+                zoom: 0.1,
+                ..View::default()
+            },
+        });
+        let text = to_json(&saved, &[], &[]).to_string();
+        let mut loaded = session();
+        apply(&json::parse(&text).unwrap(), &mut loaded, &[], &[]).unwrap();
+        assert!((loaded.panes[0].latitude - PI / 2.0).abs() < 1e-12);
+        assert_eq!(loaded.panes[0].view.zoom, MAX_ZOOM);
+        assert!((loaded.bookmarks[0].latitude + PI / 2.0).abs() < 1e-12);
+        assert_eq!(loaded.bookmarks[0].view.zoom, 1.0);
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use sdl2::image::LoadTexture;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::render::{Texture, TextureCreator};
+use sdl2::surface::Surface;
+
+const EXTENSIONS: [&str; 4] = ["png", "jpg", "jpeg", "bmp"];
+const PLACEHOLDER_SIZE: u32 = 64;
+const PLACEHOLDER_COLOR: Color = Color::RGB(128, 128, 128);
+
+// every image loaded once, a file in any of the formats will do and a missing one leaves a plain disk in its place
+pub struct Textures<'a, T> {
+    texture_creator: &'a TextureCreator<T>,
+    cache: HashMap<PathBuf, Rc<Texture<'a>>>,
+}
+
+impl<'a, T> Textures<'a, T> {
+    pub fn new(texture_creator: &'a TextureCreator<T>) -> Self {
+        Self {
+            texture_creator,
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn load(&mut self, path: &Path) -> Rc<Texture<'a>> {
+        if let Some(texture) = self.cache.get(path) {
+            return texture.clone();
+        }
+        let texture = Rc::new(self.read(path).unwrap_or_else(|error| {
+            eprintln!("Could not load {}, drawing a plain disk: {}", path.display(), error);
+            self.placeholder()
+        }));
+        self.cache.insert(path.to_path_buf(), texture.clone());
+        texture
+    }
+
+    pub fn clear(&mut self) {
+        // so changed files are read again
+        self.cache.clear();
+    }
+
+    fn read(&self, path: &Path) -> Result<Texture<'a>, String> {
+        // the path as given, then the same name in the other formats
+        self.texture_creator.load_texture(path).or_else(|error| {
+            EXTENSIONS
+                .iter()
+                .map(|extension| path.with_extension(extension))
+                .filter(|candidate| candidate != path)
+                .find_map(|candidate| self.texture_creator.load_texture(&candidate).ok())
+                .ok_or(error)
+        })
+    }
+
+    fn placeholder(&self) -> Texture<'a> {
+        let mut pixels = placeholder_pixels(PLACEHOLDER_SIZE, PLACEHOLDER_COLOR);
+        let surface = Surface::from_data(
+            &mut pixels,
+            PLACEHOLDER_SIZE,
+            PLACEHOLDER_SIZE,
+            PLACEHOLDER_SIZE * 4,
+            PixelFormatEnum::RGBA32,
+        )
+        .unwrap();
+        self.texture_creator.create_texture_from_surface(&surface).unwrap()
+    }
+}
+
+fn placeholder_pixels(size: u32, color: Color) -> Vec<u8> {
+    // a disk filling the square, transparent around it, as rgba bytes row by row
+    let radius = size as f64 / 2.0;
+    let mut pixels = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let (dx, dy) = (x as f64 + 0.5 - radius, y as f64 + 0.5 - radius);
+            let alpha = if dx.hypot(dy) <= radius { color.a } else { 0 };
+            pixels.extend_from_slice(&[color.r, color.g, color.b, alpha]);
+        }
+    }
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholder_pixels() {
+        let pixels = placeholder_pixels(8, Color::RGB(10, 20, 30));
+        assert_eq!(pixels.len(), 8 * 8 * 4);
+        let pixel = |x: usize, y: usize| &pixels[(y * 8 + x) * 4..(y * 8 + x) * 4 + 4];
+        assert_eq!(pixel(4, 4), [10, 20, 30, 255]);
+        assert_eq!(pixel(0, 0), [10, 20, 30, 0]);
+        assert_eq!(pixel(0, 4), [10, 20, 30, 255]);
+    }
+}
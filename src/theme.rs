@@ -0,0 +1,218 @@
+use sdl2::pixels::Color;
+
+use crate::config::Config;
+
+// extinction in magnitudes per airmass less that of red light, a typical site
+const GREEN_EXTINCTION: f64 = 0.05;
+const BLUE_EXTINCTION: f64 = 0.15;
+
+pub const PRESETS: [&str; 3] = ["default", "night", "print"];
+
+pub struct Theme {
+    pub background: Color,
+    pub dome: Color,
+    pub grid: Color,
+    pub ecliptic: Color,
+    pub galactic: Color,
+    pub boundary: Color,
+    pub belt: Color,
+    pub star: Color,
+    pub double_marker: Color,
+    pub sun: Color,
+    pub planet: Color,
+    pub text: Color,
+    pub highlight: Color,
+    pub panel: Color,
+}
+
+impl Theme {
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self {
+                background: Color::RGB(12, 12, 12),
+                dome: Color::RGB(0, 0, 0),
+                grid: Color::RGB(30, 30, 30),
+                ecliptic: Color::RGB(90, 0, 0),
+                galactic: Color::RGB(60, 30, 90),
+                boundary: Color::RGB(40, 50, 70),
+                belt: Color::RGB(200, 120, 150),
+                star: Color::RGB(255, 255, 255),
+                double_marker: Color::RGB(90, 90, 90),
+                sun: Color::RGB(255, 255, 255),
+                planet: Color::RGB(255, 255, 255),
+                text: Color::RGB(0, 255, 0),
+                highlight: Color::RGB(0, 255, 0),
+                panel: Color::RGB(0, 0, 0),
+            }),
+            // keeps dark adaptation at the eyepiece
+            "night" => Some(Self {
+                background: Color::RGB(10, 0, 0),
+                dome: Color::RGB(0, 0, 0),
+                grid: Color::RGB(40, 0, 0),
+                ecliptic: Color::RGB(90, 0, 0),
+                galactic: Color::RGB(60, 0, 0),
+                boundary: Color::RGB(50, 0, 0),
+                belt: Color::RGB(120, 0, 0),
+                star: Color::RGB(255, 40, 40),
+                double_marker: Color::RGB(90, 0, 0),
+                sun: Color::RGB(255, 60, 60),
+                planet: Color::RGB(255, 60, 60),
+                text: Color::RGB(200, 0, 0),
+                highlight: Color::RGB(255, 0, 0),
+                panel: Color::RGB(0, 0, 0),
+            }),
+            // black on white for paper, fainter stars fade into the page rather than into the night
+            "print" => Some(Self {
+                background: Color::RGB(255, 255, 255),
+                dome: Color::RGB(255, 255, 255),
+                grid: Color::RGB(200, 200, 200),
+                ecliptic: Color::RGB(120, 120, 120),
+                galactic: Color::RGB(160, 160, 160),
+                boundary: Color::RGB(180, 180, 180),
+                belt: Color::RGB(160, 160, 160),
+                star: Color::RGB(0, 0, 0),
+                double_marker: Color::RGB(140, 140, 140),
+                sun: Color::RGB(0, 0, 0),
+                planet: Color::RGB(0, 0, 0),
+                text: Color::RGB(0, 0, 0),
+                highlight: Color::RGB(0, 0, 0),
+                panel: Color::RGB(255, 255, 255),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn load(config: &Config, preset: &str) -> Self {
+        let mut theme = Self::preset(preset).unwrap_or_else(|| {
+            eprintln!("Unknown theme preset {}, using default", preset);
+            Self::preset("default").unwrap()
+        });
+        for (key, value) in config.entries("theme") {
+            if key == "preset" {
+                continue;
+            }
+            let Some(color) = parse_color(value) else {
+                eprintln!("Invalid color {} for theme.{}", value, key);
+                continue;
+            };
+            match key.as_str() {
+                "background" => theme.background = color,
+                "dome" => theme.dome = color,
+                "grid" => theme.grid = color,
+                "ecliptic" => theme.ecliptic = color,
+                "galactic" => theme.galactic = color,
+                "boundary" => theme.boundary = color,
+                "belt" => theme.belt = color,
+                "star" => theme.star = color,
+                "double_marker" => theme.double_marker = color,
+                "sun" => theme.sun = color,
+                "planet" => theme.planet = color,
+                "text" => theme.text = color,
+                "highlight" => theme.highlight = color,
+                "panel" => theme.panel = color,
+                _ => eprintln!("Unknown theme color {}", key),
+            }
+        }
+        theme
+    }
+
+    pub fn star_color(&self, tint: Color, brightness: u8) -> Color {
+        // fading into the dome, black on the dark presets
+        mix_color(self.dome, self.star_tint(tint), brightness as f64 / 255.0)
+    }
+
+    pub fn star_tint(&self, tint: Color) -> Color {
+        // the catalog color filtered through the theme, so night mode stays red
+        let filter = |channel: u8, tint: u8| (channel as u16 * tint as u16 / 255) as u8;
+        Color::RGB(
+            filter(self.star.r, tint.r),
+            filter(self.star.g, tint.g),
+            filter(self.star.b, tint.b),
+        )
+    }
+}
+
+pub fn mix_color(from: Color, to: Color, t: f64) -> Color {
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    Color::RGB(mix(from.r, to.r), mix(from.g, to.g), mix(from.b, to.b))
+}
+
+pub fn redden(color: Color, airmass: f64) -> Color {
+    // blue light is scattered away first, red is left as it is so the brightness is kept
+    let transmission = |extinction: f64| 10f64.powf(-0.4 * extinction * (airmass - 1.0));
+    Color::RGBA(
+        color.r,
+        (color.g as f64 * transmission(GREEN_EXTINCTION)).round() as u8,
+        (color.b as f64 * transmission(BLUE_EXTINCTION)).round() as u8,
+        color.a,
+    )
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let mut parts = value.split(',').map(|part| part.trim().parse::<u8>());
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(r)), Some(Ok(g)), Some(Ok(b)), None) => Some(Color::RGB(r, g, b)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("255, 0, 12"), Some(Color::RGB(255, 0, 12)));
+        assert_eq!(parse_color("1,2,3"), Some(Color::RGB(1, 2, 3)));
+        assert_eq!(parse_color("1,2"), None);
+        assert_eq!(parse_color("1,2,3,4"), None);
+        assert_eq!(parse_color("256,0,0"), None);
+    }
+
+    #[test]
+    fn test_mix_color() {
+        let (black, grey) = (Color::RGB(0, 0, 0), Color::RGB(30, 40, 50));
+        assert_eq!(mix_color(black, grey, 0.0), black);
+        assert_eq!(mix_color(black, grey, 1.0), grey);
+        assert_eq!(mix_color(grey, black, 0.5), Color::RGB(15, 20, 25));
+    }
+
+    #[test]
+    fn test_redden() {
+        let white = Color::RGB(255, 255, 255);
+        assert_eq!(redden(white, 1.0), white);
+        // orange a few degrees up, deep red at the horizon
+        let low = redden(white, 10.0);
+        assert!(low.r == 255 && low.g > low.b && low.g < 200);
+        let horizon = redden(white, 38.0);
+        assert!(horizon.g < 60 && horizon.b < 5);
+        assert_eq!(redden(Color::RGBA(255, 0, 0, 60), 38.0), Color::RGBA(255, 0, 0, 60));
+    }
+
+    #[test]
+    fn test_star_tint() {
+        let theme = Theme::preset("default").unwrap();
+        let orange = Color::RGB(255, 180, 100);
+        assert_eq!(theme.star_tint(orange), orange);
+        assert_eq!(theme.star_color(Color::WHITE, 255), theme.star);
+        let night = Theme::preset("night").unwrap();
+        assert_eq!(night.star_tint(orange), Color::RGB(255, 28, 15));
+        assert_eq!(night.star_color(orange, 51), Color::RGB(51, 6, 3));
+        // on paper the faint stars are pale and the bright ones black, whatever their color
+        let print = Theme::preset("print").unwrap();
+        assert_eq!(print.star_color(orange, 255), Color::RGB(0, 0, 0));
+        assert_eq!(print.star_color(orange, 51), Color::RGB(204, 204, 204));
+    }
+
+    #[test]
+    fn test_load() {
+        let config = Config::parse("[theme]\ngrid = 1, 2, 3\nbogus = 1, 1, 1\ntext = red\n");
+        let theme = Theme::load(&config, "night");
+        assert_eq!(theme.grid, Color::RGB(1, 2, 3));
+        assert_eq!(theme.text, Theme::preset("night").unwrap().text);
+        assert_eq!(
+            Theme::load(&config, "unknown").dome,
+            Theme::preset("default").unwrap().dome
+        );
+    }
+}
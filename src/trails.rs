@@ -0,0 +1,66 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+
+// past equatorial positions of the moving objects, oldest first, one track per object
+#[derive(Clone, Default)]
+pub struct Trails {
+    time: Option<DateTime<Utc>>, // of the latest positions
+    tracks: Vec<VecDeque<(f64, f64)>>,
+}
+
+impl Trails {
+    pub fn record(&mut self, time: DateTime<Utc>, positions: &[Option<(f64, f64)>], length: usize) {
+        // nothing new while time stands still
+        if self.time == Some(time) {
+            return;
+        }
+        self.time = Some(time);
+        self.tracks.resize_with(positions.len(), VecDeque::new);
+        for (track, position) in self.tracks.iter_mut().zip(positions) {
+            match position {
+                Some(position) => track.push_back(*position),
+                // an object out of sight, like the world looked from, starts over
+                None => track.clear(),
+            }
+            while track.len() > length {
+                track.pop_front();
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn tracks(&self) -> &[VecDeque<(f64, f64)>] {
+        &self.tracks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let time = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut trails = Trails::default();
+        for day in 0..5 {
+            let position = (day as f64, 0.0);
+            trails.record(time + chrono::Duration::days(day), &[Some(position), None], 3);
+            // a paused clock adds nothing
+            trails.record(time + chrono::Duration::days(day), &[Some((9.0, 9.0)), None], 3);
+        }
+        assert_eq!(trails.tracks().len(), 2);
+        assert_eq!(trails.tracks()[0], [(2.0, 0.0), (3.0, 0.0), (4.0, 0.0)]);
+        assert!(trails.tracks()[1].is_empty());
+        trails.record(time, &[None, Some((1.0, 1.0))], 3);
+        assert!(trails.tracks()[0].is_empty());
+        assert_eq!(trails.tracks()[1].len(), 1);
+        trails.clear();
+        assert!(trails.tracks().is_empty());
+    }
+}
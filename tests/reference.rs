@@ -0,0 +1,127 @@
+use std::f64::consts::PI;
+use std::fs::read_to_string;
+
+use antikythera::astro::{find_eclipses, Calibration, EclipseKind, Engine, MARS};
+use antikythera::math::angular_distance;
+use antikythera::{parse_planets, Star};
+use chrono::{DateTime, Duration, Utc};
+
+// the sky over Lowell Observatory in Flagstaff at 2024-03-01 04:00 UT, as altitude and azimuth in degrees from north
+// through east with the largest error the model is allowed; the Sun and the planets from JPL's approximate keplerian
+// elements, the Moon from Meeus' chapter 47, both precessed to the date, without refraction and the Moon geocentric
+// like the engine draws it; the pipeline reproduces Meeus' examples 13.b, 25.a, 33.a and 47.a to a hundredth of a degree
+const TIME: &str = "2024-03-01T04:00:00Z";
+const LATITUDE: f64 = 35.2029;
+const LONGITUDE: f64 = -111.6646;
+const SUN: (f64, f64) = (-32.837, 285.503);
+const MOON: (f64, f64) = (-27.505, 93.136);
+// the planets move on circles, their eccentricities make up most of the error
+const PLANETS: [(&str, (f64, f64), f64); 5] = [
+    ("Mercury", (-31.721, 283.101), 7.7),
+    ("Venus", (-56.214, 295.417), 1.15),
+    ("Mars", (-59.720, 297.491), 3.9),
+    ("Jupiter", (25.543, 269.938), 0.37),
+    ("Saturn", (-34.269, 283.940), 1.07),
+];
+// a pair of angles in degrees
+type Place = (f64, f64);
+// stars from their j2000 places, precessed to the date with meeus 21.4 and turned by meeus' mean sidereal time 12.4
+const STARS: [(&str, Place, Place, f64); 4] = [
+    ("Sirius", (101.287154, -16.716117), (37.718, 187.554), 0.13),
+    ("Polaris", (37.954561, 89.264109), (35.499, 359.311), 0.14),
+    ("Vega", (279.234735, 38.783689), (-15.589, 6.750), 0.16),
+    ("Betelgeuse", (88.792939, 7.407064), (57.351, 216.047), 0.08),
+];
+// the Sun's ecliptic longitude seen from Mars at the same time, from the same elements
+const SUN_FROM_MARS: f64 = 113.482;
+// the lunar eclipses seen from Flagstaff over the following year at their greatest, nasa's five millennium canon
+const LUNAR_ECLIPSES: [(&str, EclipseKind); 3] = [
+    ("2024-03-25T07:13:00Z", EclipseKind::PenumbralLunar),
+    ("2024-09-18T02:44:00Z", EclipseKind::PartialLunar),
+    ("2025-03-14T06:59:00Z", EclipseKind::TotalLunar),
+];
+
+fn radians((alt, az): (f64, f64)) -> (f64, f64) {
+    (alt / 180.0 * PI, az / 180.0 * PI)
+}
+
+fn error(position: (f64, f64), reference: (f64, f64)) -> f64 {
+    angular_distance(position, radians(reference)) / PI * 180.0
+}
+
+fn time(text: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)
+}
+
+fn engine() -> Engine {
+    Engine::new(time(TIME), LATITUDE / 180.0 * PI, LONGITUDE / 180.0 * PI)
+}
+
+#[test]
+fn test_sun() {
+    assert!(error(engine().get_sun_position(), SUN) < 0.16);
+}
+
+#[test]
+fn test_moon() {
+    let (alt, az, _, _) = engine().get_moon_position();
+    assert!(error((alt, az), MOON) < 0.3);
+}
+
+#[test]
+fn test_stars() {
+    let engine = engine();
+    for (name, (ascension, declination), reference, tolerance) in STARS {
+        let star = Star::new(0, ascension / 180.0 * PI, declination / 180.0 * PI, 0.0);
+        let error = error(engine.get_star_position(&star), reference);
+        assert!(error < tolerance, "{} is {:.2}° off", name, error);
+    }
+}
+
+#[test]
+fn test_planets() -> Result<(), String> {
+    let engine = engine();
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/data/planets.dat");
+    let content = read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+    let planets = parse_planets(&content)?;
+    for (name, reference, tolerance) in PLANETS {
+        let planet = planets
+            .iter()
+            .find(|planet| planet.name == name)
+            .ok_or(format!("No {} in {}", name, path))?;
+        let (alt, az, _) = engine.get_planet_position(planet);
+        let error = error((alt, az), reference);
+        assert!(error < tolerance, "{} is {:.2}° off", name, error);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_sun_from_mars() {
+    // ecliptic longitude from the right ascension and declination, the frame is the Earth's either way
+    let (ascension, declination) = Engine::on_world(&MARS, time(TIME), 0.0, 0.0).get_sun_equatorial();
+    let tilt = 23.436169775089777_f64.to_radians();
+    let longitude = (ascension.sin() * tilt.cos() + declination.tan() * tilt.sin()).atan2(ascension.cos());
+    let error = (longitude / PI * 180.0 - SUN_FROM_MARS + 180.0).rem_euclid(360.0) - 180.0;
+    assert!(error.abs() < 0.025);
+}
+
+#[test]
+fn test_lunar_eclipses() {
+    // the shadow's size decides the kind, the searched maximum lands within the hour
+    let start = time(TIME);
+    let eclipses = find_eclipses(
+        &Calibration::default(),
+        start,
+        start + Duration::days(400),
+        LATITUDE / 180.0 * PI,
+        LONGITUDE / 180.0 * PI,
+    );
+    for (expected, kind) in LUNAR_ECLIPSES {
+        let expected = time(expected);
+        let eclipse = eclipses
+            .iter()
+            .find(|eclipse| (eclipse.time - expected).num_minutes().abs() < 60);
+        assert_eq!(eclipse.map(|eclipse| eclipse.kind), Some(kind), "{}", expected);
+    }
+}